@@ -1,3 +1,4 @@
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -12,32 +13,49 @@ use ratatui::{
     widgets::ListState,
 };
 use tokio::sync::mpsc;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use crate::compression;
 use crate::config::Config;
 use crate::error::Result;
-use crate::library::models::TrackedSeries;
+use crate::indexer::{self, SearchPage, SharedIndexer};
+use crate::library::models::{BindTarget, DownloadBinding, TrackedSeries};
 use crate::library::{
-    Library,
-    tracking::{self, UpdateResult},
+    Library, schedule,
+    tracking::{self, UpdateResult, select_batch_files},
+};
+use crate::metadata::credentials::{CredentialBackend, OAuthToken, TokenManager};
+use crate::metadata::mal_sync::{
+    self, ImportPlan, MalSyncQueue, ProgressUpdate, PushOutcome, push_progress,
 };
+use crate::metadata::matcher::{self, AutoMatcher, Candidate, MatchCache, MatchOutcome, ShowQuery};
+use crate::metadata::offline::OfflineDatabase;
+use crate::metadata::redirect;
+use crate::metadata::{EpisodeMetadata, ListSync, ProviderKind, episodes};
 use crate::notify::Notifier;
-use crate::nyaa::{NyaaCategory, NyaaClient, NyaaFilter, NyaaResult, NyaaSort};
+use crate::nyaa::{NyaaCategory, NyaaFilter, NyaaResult, NyaaSort, query};
 use crate::player::ExternalPlayer;
 use crate::rpc::DiscordRpc;
 use crate::torrent::preview::{
-    FileType, PreviewSection, PreviewState, TorrentFileEntry, extract_anime_title,
-    fetch_torrent_files,
+    FileType, PreviewSection, PreviewSource, PreviewState, TorrentFileEntry, extract_anime_title,
+    fetch_torrent_files, load_preview_source, magnet_hash, magnet_name, parse_torrent,
+};
+use crate::torrent::{
+    AnyTorrentClient, FilePriority, QBittorrentClient, TorrentDetails, TorrentStatus,
+    TorrentUpdate, TransmissionClient, seeding,
 };
-use crate::torrent::{AnyTorrentClient, QBittorrentClient, TorrentStatus, TransmissionClient};
 use crate::ui::{
-    render_downloads_view, render_episodes_view, render_glossary_popup, render_library_view,
-    render_preview_popup, render_search_view, widgets,
+    CandidateSelection, render_downloads_view, render_episodes_view, render_glossary_popup,
+    render_library_view, render_match_candidates, render_preview_popup, render_search_view,
+    render_torrent_details_view, widgets,
 };
 
 const VIDEO_EXTENSIONS: &[&str] = &["mkv", "mp4", "avi", "webm", "m4v", "mov", "wmv"];
 
+/// Search pages in a row that may come back with nothing visible before
+/// loading more waits for the user to scroll
+const MAX_EMPTY_SEARCH_PAGES: u32 = 2;
+
 /// Clean up a torrent filename to a more readable format
 /// e.g., "[SubGroup] Show Name - 01 (1080p) [HASH].mkv" -> "Show Name - S01E01.mkv"
 fn clean_filename(name: &str) -> String {
@@ -146,6 +164,24 @@ fn list_subdirs(path: &Path) -> Vec<String> {
         .unwrap_or_default()
 }
 
+fn torrent_files_in(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.is_file()
+                        && p.extension()
+                            .is_some_and(|e| e.eq_ignore_ascii_case("torrent"))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
 fn find_video_in_dir(dir: &Path) -> Result<PathBuf> {
     if let Ok(entries) = std::fs::read_dir(dir) {
         let mut videos: Vec<_> = entries
@@ -242,6 +278,13 @@ pub enum View {
     TrackingList,
     PreviewPopup,
     MalSyncDialog,
+    TorrentDetails,
+    AddTorrentDialog,
+    ScorePrompt,
+    Watchlist,
+    MatchReview,
+    MatchDialog,
+    Calendar,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -271,6 +314,8 @@ pub enum MalSyncStep {
     ShowAuthUrl,
     WaitingForCode,
     Syncing,
+    /// Showing `MalSyncState::import_plan` for confirmation
+    Preview,
     Complete(usize),
     Error(String),
 }
@@ -281,6 +326,23 @@ pub struct MalSyncState {
     pub auth_url: String,
     pub code_input: String,
     pub code_verifier: String,
+    /// OAuth `state` sent with the auth URL, checked on the way back
+    pub oauth_state: String,
+    /// Set when the loopback listener is catching the redirect
+    pub redirect_uri: Option<String>,
+    pub redirect_listener: Option<tokio::task::AbortHandle>,
+    /// The listener brought back a code; finish logging in next frame
+    pub login_pending: bool,
+    pub import_plan: Option<ImportPlan>,
+    pub preview_scroll: u16,
+}
+
+impl MalSyncState {
+    fn stop_listener(&mut self) {
+        if let Some(handle) = self.redirect_listener.take() {
+            handle.abort();
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -313,6 +375,41 @@ impl BatchMoveStrategy {
     }
 }
 
+/// Asks for a MAL score after the final episode of a show is watched
+pub struct ScorePromptState {
+    pub update: ProgressUpdate,
+    pub title: String,
+    pub input: String,
+}
+
+/// A show the matcher couldn't decide on, waiting for the user to pick
+pub struct MatchReview {
+    pub show_id: String,
+    pub title: String,
+    pub candidates: Vec<Candidate>,
+}
+
+/// Picking the metadata for a show by hand
+#[derive(Default)]
+pub struct MatchDialogState {
+    pub show_id: String,
+    pub show_title: String,
+    /// Search text, or an ID / MAL or AniList URL
+    pub input: String,
+    /// What `candidates` came from; Enter searches again once `input` differs
+    pub searched: String,
+    pub candidates: Vec<Candidate>,
+    pub list_state: ListState,
+    pub loading: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Default)]
+pub struct AddTorrentDialogState {
+    pub input: String,
+    pub error: Option<String>,
+}
+
 pub struct MoveDialogState {
     pub step: MoveDialogStep,
     pub torrent_idx: usize,
@@ -358,20 +455,41 @@ pub struct ArchiveDialogState {
 }
 
 pub enum AppMessage {
-    SearchResults(Vec<NyaaResult>),
-    SearchError(String),
+    /// A page of results, numbered from 1, for the search of the given
+    /// generation
+    SearchResults(u64, u32, SearchPage),
+    SearchError(u64, String),
     TorrentAdded(String),
     TorrentError(String),
     MetadataFound(String, crate::metadata::AnimeMetadata),
     CoverUpdated(String),
-    MetadataError(String),
     TorrentList(Vec<TorrentStatus>),
+    TorrentUpdate(TorrentUpdate),
     UpdatesFound(Vec<UpdateResult>),
     AutoSave,
+    SeedingCheck,
     PreviewTorrentData(Vec<TorrentFileEntry>),
     PreviewTorrentError(String),
     PreviewMalData(crate::metadata::AnimeMetadata),
     PreviewMalError(String),
+    TorrentDetailsLoaded(TorrentDetails),
+    TorrentDetailsError(String),
+    WatchedTorrent(PathBuf, Vec<u8>),
+    MalProgressPushed(ProgressUpdate, std::result::Result<PushOutcome, String>),
+    MalSyncRetry,
+    /// Code (or error) caught by the loopback OAuth listener
+    MalAuthRedirect(std::result::Result<String, String>),
+    /// Several plausible metadata matches, for the review queue
+    MatchNeedsReview(ShowQuery, Vec<Candidate>),
+    /// Search or ID lookup results for the match dialog
+    MatchCandidates(
+        String,
+        std::result::Result<Vec<crate::metadata::AnimeMetadata>, String>,
+    ),
+    EpisodeInfo(String, std::result::Result<Vec<EpisodeMetadata>, String>),
+    /// Fresh details, with airing times, for a show or tracked series
+    AiringInfo(BindTarget, crate::metadata::AnimeMetadata),
+    UpdateCheckTick,
 }
 
 pub struct App {
@@ -384,6 +502,7 @@ pub struct App {
 
     pub library_state: ListState,
     pub tracking_list_state: ListState,
+    pub watchlist_state: ListState,
     pub episodes_state: ListState,
     pub selected_show_idx: Option<usize>,
 
@@ -394,12 +513,24 @@ pub struct App {
     pub is_filtering: bool,
     pub search_state: ListState,
     pub search_loading: bool,
+    /// Bumped whenever the results are cleared, so pages still on their
+    /// way from an earlier search are dropped
+    pub search_generation: u64,
+    /// Pages loaded so far
+    pub search_page: u32,
+    pub search_more: bool,
+    /// Pages in a row that added no visible rows; past a couple, further
+    /// pages wait for the user to scroll
+    pub search_empty_pages: u32,
+    pub search_total: Option<usize>,
     pub search_category: NyaaCategory,
     pub search_filter: NyaaFilter,
     pub search_sort: NyaaSort,
 
     pub torrents: Vec<TorrentStatus>,
     pub downloads_state: ListState,
+    pub torrent_details: PreviewSection<TorrentDetails>,
+    pub torrent_files_state: ListState,
 
     pub move_dialog: MoveDialogState,
     pub tracking_state: TrackingDialogState,
@@ -410,9 +541,12 @@ pub struct App {
     pub msg_tx: mpsc::UnboundedSender<AppMessage>,
     pub msg_rx: mpsc::UnboundedReceiver<AppMessage>,
 
-    pub nyaa_client: Arc<NyaaClient>,
+    /// Enabled torrent indexers, in the configured order
+    pub indexers: Arc<Vec<SharedIndexer>>,
     pub torrent_client: Option<Arc<AnyTorrentClient>>,
     pub metadata_provider: Option<Arc<dyn crate::metadata::MetadataProvider + Send + Sync>>,
+    /// anime-offline-database dump, when present, for cross-provider IDs
+    pub offline_db: Option<Arc<OfflineDatabase>>,
     pub image_cache: Arc<crate::image_cache::ImageCache>,
     pub picker: ratatui_image::picker::Picker,
     pub rpc: Option<DiscordRpc>,
@@ -421,8 +555,24 @@ pub struct App {
     pub startup_scan_completed: bool,
     pub dirty: bool,
     pub preview_state: Option<PreviewState>,
+    /// Magnets and `.torrent` files waiting for their preview popup
+    pub pending_ingest: VecDeque<PreviewSource>,
+    pub add_torrent_dialog: AddTorrentDialogState,
     pub show_glossary: bool,
     pub mal_sync: MalSyncState,
+    /// List sync tokens, refreshed and persisted outside config.toml
+    pub tokens: Arc<TokenManager>,
+    pub mal_queue: MalSyncQueue,
+    pub score_prompt: Option<ScorePromptState>,
+    pub match_review: VecDeque<MatchReview>,
+    pub match_review_state: ListState,
+    pub match_dialog: MatchDialogState,
+    pub matcher_task: Option<tokio::task::JoinHandle<()>>,
+    /// Shows whose episode list was already fetched this session
+    pub episode_info_requested: HashSet<String>,
+    pub calendar_state: ListState,
+    /// When to next look for tracked releases (unix seconds)
+    pub next_update_check: i64,
 }
 
 impl App {
@@ -439,15 +589,45 @@ impl App {
 
         let torrent_client = create_torrent_client(&config);
 
+        let offline_db = config
+            .expanded_offline_db()
+            .filter(|p| p.exists())
+            .and_then(|path| match OfflineDatabase::load(&path) {
+                Ok(db) => {
+                    info!(entries = db.len(), "Loaded offline anime database");
+                    Some(Arc::new(db))
+                }
+                Err(e) => {
+                    warn!(path = %path.display(), error = %e, "Failed to load offline database");
+                    None
+                }
+            });
+
+        let indexers = Arc::new(indexer::from_config(&config.indexers));
+
         let metadata_provider: Option<Arc<dyn crate::metadata::MetadataProvider + Send + Sync>> =
-            if !config.metadata.mal_client_id.is_empty() {
-                Some(Arc::new(crate::metadata::mal::MalClient::new(
-                    config.metadata.mal_client_id.clone(),
-                )))
-            } else {
-                None
+            match config.metadata.provider {
+                ProviderKind::Mal if !config.metadata.mal_client_id.is_empty() => Some(Arc::new(
+                    crate::metadata::mal::MalClient::new(config.metadata.mal_client_id.clone()),
+                )),
+                ProviderKind::Mal => None,
+                ProviderKind::Anilist => {
+                    Some(Arc::new(crate::metadata::anilist::AnilistClient::new(
+                        config.metadata.anilist_client_id.clone(),
+                    )))
+                }
+                ProviderKind::Kitsu => Some(Arc::new(crate::metadata::kitsu::KitsuClient::new())),
+                ProviderKind::Offline => match &offline_db {
+                    Some(db) => Some(db.clone()),
+                    None => {
+                        warn!("Offline metadata provider selected but no database was loaded");
+                        None
+                    }
+                },
             };
 
+        let tokens = Arc::new(create_token_manager(&config));
+
         let image_cache = Arc::new(crate::image_cache::ImageCache::new().unwrap_or_else(|e| {
             tracing::error!("Failed to initialize image cache: {}", e);
             panic!("Failed to initialize image cache: {}", e);
@@ -463,6 +643,7 @@ impl App {
 
             library_state,
             tracking_list_state: ListState::default(),
+            watchlist_state: ListState::default(),
             episodes_state: ListState::default(),
             selected_show_idx: None,
 
@@ -473,12 +654,19 @@ impl App {
             is_filtering: false,
             search_state: ListState::default(),
             search_loading: false,
+            search_generation: 0,
+            search_page: 0,
+            search_more: false,
+            search_empty_pages: 0,
+            search_total: None,
             search_category: NyaaCategory::AnimeEnglish,
             search_filter: NyaaFilter::NoFilter,
             search_sort: NyaaSort::default(),
 
             torrents: Vec::new(),
             downloads_state: ListState::default(),
+            torrent_details: PreviewSection::Loading,
+            torrent_files_state: ListState::default(),
 
             move_dialog: MoveDialogState::default(),
             tracking_state: TrackingDialogState::default(),
@@ -489,9 +677,10 @@ impl App {
             msg_tx,
             msg_rx,
 
-            nyaa_client: Arc::new(NyaaClient::new()),
+            indexers,
             torrent_client: torrent_client.map(Arc::new),
             metadata_provider,
+            offline_db,
             image_cache,
             picker,
             rpc: Some(DiscordRpc::new("1465518237599928381")),
@@ -500,8 +689,23 @@ impl App {
             startup_scan_completed: false,
             dirty: false,
             preview_state: None,
+            pending_ingest: VecDeque::new(),
+            add_torrent_dialog: AddTorrentDialogState::default(),
             show_glossary: false,
             mal_sync: MalSyncState::default(),
+            tokens,
+            mal_queue: MalSyncQueue::load().unwrap_or_else(|e| {
+                warn!("Failed to load MAL sync queue: {}", e);
+                MalSyncQueue::default()
+            }),
+            score_prompt: None,
+            match_review: VecDeque::new(),
+            match_review_state: ListState::default(),
+            match_dialog: MatchDialogState::default(),
+            matcher_task: None,
+            episode_info_requested: HashSet::new(),
+            calendar_state: ListState::default(),
+            next_update_check: chrono::Utc::now().timestamp() + schedule::FALLBACK_CHECK_SECS,
         }
     }
 
@@ -510,6 +714,9 @@ impl App {
 
         self.spawn_managed_daemon();
 
+        self.start_auto_match();
+        self.refresh_airing_metadata();
+
         let auto_save_tx = self.msg_tx.clone();
         tokio::spawn(async move {
             let mut interval = tokio::time::interval(Duration::from_secs(60));
@@ -522,10 +729,107 @@ impl App {
             }
         });
 
+        let seeding_tx = self.msg_tx.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(300));
+            loop {
+                interval.tick().await;
+                if seeding_tx.send(AppMessage::SeedingCheck).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let update_check_tx = self.msg_tx.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60));
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if update_check_tx.send(AppMessage::UpdateCheckTick).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mal_retry_tx = self.msg_tx.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(300));
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if mal_retry_tx.send(AppMessage::MalSyncRetry).is_err() {
+                    break;
+                }
+            }
+        });
+
+        if let Some(client) = self.torrent_client.clone() {
+            let poll_tx = self.msg_tx.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(Duration::from_secs(2));
+                interval.tick().await;
+                loop {
+                    interval.tick().await;
+                    match client.poll_changes().await {
+                        Ok(update) => {
+                            if !update.full
+                                && update.changed.is_empty()
+                                && update.removed.is_empty()
+                            {
+                                continue;
+                            }
+                            if poll_tx.send(AppMessage::TorrentUpdate(update)).is_err() {
+                                break;
+                            }
+                        }
+                        // The daemon may not be up yet, the next tick resyncs fully
+                        Err(e) => debug!(error = %e, "Torrent poll failed"),
+                    }
+                }
+            });
+        }
+
+        if let Some(watch_dir) = self.config.expanded_watch_dir() {
+            let watch_tx = self.msg_tx.clone();
+            tokio::spawn(async move {
+                let mut seen = HashSet::new();
+                let mut interval = tokio::time::interval(Duration::from_secs(5));
+                loop {
+                    interval.tick().await;
+                    for path in torrent_files_in(&watch_dir) {
+                        if seen.contains(&path) {
+                            continue;
+                        }
+                        let Ok(bytes) = tokio::fs::read(&path).await else {
+                            continue;
+                        };
+                        // Files still being written won't parse yet, retry next tick
+                        if let Err(e) = parse_torrent(&bytes) {
+                            debug!(path = %path.display(), error = %e, "Skipping watched file");
+                            continue;
+                        }
+                        seen.insert(path.clone());
+                        if watch_tx
+                            .send(AppMessage::WatchedTorrent(path, bytes))
+                            .is_err()
+                        {
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+
         while self.running {
             terminal.draw(|frame| self.render(frame))?;
+            if self.mal_sync.login_pending {
+                self.mal_sync.login_pending = false;
+                self.finish_list_sync().await?;
+            }
             self.handle_events().await?;
             self.process_messages();
+            self.open_pending_ingest();
         }
 
         self.cleanup();
@@ -535,55 +839,166 @@ impl App {
     fn process_messages(&mut self) {
         while let Ok(msg) = self.msg_rx.try_recv() {
             match msg {
-                AppMessage::SearchResults(results) => {
+                AppMessage::SearchResults(generation, page, found) => {
+                    // Left over from a search that has since been cleared
+                    if generation != self.search_generation || page != self.search_page + 1 {
+                        continue;
+                    }
                     self.search_loading = false;
-                    self.search_results = results;
-                    self.filtered_search_results = (0..self.search_results.len()).collect();
-                    if !self.filtered_search_results.is_empty() {
-                        self.search_state.select(Some(0));
+                    self.search_page = page;
+                    self.search_total = found.total;
+                    let visible = self.filtered_search_results.len();
+                    if page == 1 {
+                        self.search_more = found.more;
+                        self.search_results = found.results;
+                        self.filtered_search_results = (0..self.search_results.len()).collect();
+                        if !self.filtered_search_results.is_empty() {
+                            self.search_state.select(Some(0));
+                        }
+                    } else {
+                        let before = self.search_results.len();
+                        let results = std::mem::take(&mut self.search_results);
+                        self.search_results = indexer::merge(vec![results, found.results]);
+                        // A page of nothing but duplicates ends the scroll
+                        self.search_more = found.more && self.search_results.len() > before;
+                        self.filter_search_results();
+                    }
+                    if self.filtered_search_results.len() > visible {
+                        self.search_empty_pages = 0;
+                    } else {
+                        self.search_empty_pages += 1;
+                    }
+                    // Query filters can leave a page short of filling the
+                    // list, but when they hide whole pages stop fetching
+                    if self.search_empty_pages < MAX_EMPTY_SEARCH_PAGES {
+                        self.load_more_search_results();
                     }
                 }
-                AppMessage::SearchError(err) => {
+                AppMessage::SearchError(generation, err) => {
+                    if generation != self.search_generation {
+                        continue;
+                    }
                     self.search_loading = false;
+                    self.search_more = false;
                     error!(error = %err, "Search failed");
                 }
                 AppMessage::TorrentAdded(hash) => {
                     debug!(hash = %hash, "Torrent added");
+                    let hash = hash.to_lowercase();
+                    if !hash.is_empty() && !self.library.added_torrents.contains(&hash) {
+                        self.library.added_torrents.push(hash);
+                        self.dirty = true;
+                    }
                     self.refresh_torrent_list();
                 }
                 AppMessage::TorrentError(e) => {
                     error!("Torrent client error: {}", e);
                 }
                 AppMessage::MetadataFound(show_id, metadata) => {
-                    if let Some(show) = self.library.shows.iter_mut().find(|s| s.id == show_id) {
+                    // Never overrule a match the user picked
+                    if let Some(show) = self
+                        .library
+                        .shows
+                        .iter_mut()
+                        .find(|s| s.id == show_id && !s.metadata_locked)
+                    {
                         info!("Updated metadata for: {}", show.title);
-
-                        if let Some(url) = metadata.cover_url.clone() {
-                            let cache = self.image_cache.clone();
-                            let tx = self.msg_tx.clone();
-                            let s_id = show_id.clone();
-                            tokio::spawn(async move {
-                                if let Err(e) = cache.download(&url).await {
-                                    tracing::error!("Failed to download cover for {}: {}", s_id, e);
-                                } else {
-                                    let _ = tx.send(AppMessage::CoverUpdated(s_id));
-                                }
-                            });
-                        }
-
+                        let cover_url = metadata.cover_url.clone();
                         show.metadata = Some(metadata);
                         self.dirty = true;
                         let _ = self.library.save();
+                        if let Some(url) = cover_url {
+                            self.download_cover(show_id, url);
+                        }
+                    }
+                }
+                AppMessage::MatchCandidates(show_id, result) => {
+                    if self.view != View::MatchDialog || self.match_dialog.show_id != show_id {
+                        continue;
+                    }
+                    self.match_dialog.loading = false;
+                    let Some(show) = self.library.get_show(&show_id) else {
+                        continue;
+                    };
+                    match result {
+                        Ok(results) => {
+                            let query = ShowQuery::from_show(show);
+                            let candidates = matcher::rank_candidates(&query, results);
+                            self.prefetch_covers(&candidates);
+                            self.match_dialog.error =
+                                candidates.is_empty().then(|| "No results".to_string());
+                            self.match_dialog
+                                .list_state
+                                .select((!candidates.is_empty()).then_some(0));
+                            self.match_dialog.candidates = candidates;
+                        }
+                        Err(e) => self.match_dialog.error = Some(e),
+                    }
+                }
+                AppMessage::MatchNeedsReview(query, candidates) => {
+                    // A manual match may have landed while it was searching
+                    let unmatched = self
+                        .library
+                        .get_show(&query.show_id)
+                        .is_some_and(|s| s.metadata.is_none());
+                    if unmatched {
+                        self.match_review.push_back(MatchReview {
+                            show_id: query.show_id,
+                            title: query.title,
+                            candidates,
+                        });
+                    }
+                }
+                AppMessage::EpisodeInfo(show_id, result) => match result {
+                    Ok(info) if !info.is_empty() => {
+                        if let Some(show) = self.library.get_show_mut(&show_id) {
+                            show.episode_info = info;
+                            self.dirty = true;
+                        }
+                    }
+                    Ok(_) => debug!(show = %show_id, "Provider has no episode list"),
+                    Err(e) => warn!(show = %show_id, error = %e, "Failed to fetch episode list"),
+                },
+                AppMessage::AiringInfo(target, metadata) => {
+                    match target {
+                        BindTarget::Show(id) => {
+                            if let Some(show) = self.library.get_show_mut(&id) {
+                                // Only if it's still matched to the same entry
+                                if show.metadata.as_ref().is_some_and(|m| m.id == metadata.id) {
+                                    show.metadata = Some(metadata);
+                                }
+                            }
+                        }
+                        BindTarget::Series(id) => {
+                            if let Some(series) =
+                                self.library.tracked_shows.iter_mut().find(|s| s.id == id)
+                            {
+                                series.cached_metadata = Some(metadata);
+                            }
+                        }
+                    }
+                    self.dirty = true;
+                    self.schedule_update_check();
+                }
+                AppMessage::UpdateCheckTick => {
+                    if self.startup_scan_completed
+                        && chrono::Utc::now().timestamp() >= self.next_update_check
+                    {
+                        self.check_for_updates();
+                        self.schedule_update_check();
                     }
                 }
                 AppMessage::CoverUpdated(show_id) => {
                     info!("Cover image updated for show: {}", show_id);
                 }
-                AppMessage::MetadataError(e) => {
-                    error!("Metadata fetch failed: {}", e);
-                }
                 AppMessage::TorrentList(torrents) => {
                     self.torrents = torrents;
+                    if self
+                        .library
+                        .prune_added_torrents(self.torrents.iter().map(|t| t.hash.as_str()))
+                    {
+                        self.dirty = true;
+                    }
                     if !self.torrents.is_empty() && self.downloads_state.selected().is_none() {
                         self.downloads_state.select(Some(0));
                     }
@@ -591,6 +1006,26 @@ impl App {
                     if !self.startup_scan_completed {
                         self.startup_scan_completed = true;
                         self.check_for_updates();
+                        self.schedule_update_check();
+                    }
+                }
+                AppMessage::TorrentUpdate(update) => {
+                    let full = update.full;
+                    update.apply_to(&mut self.torrents);
+                    if full
+                        && self
+                            .library
+                            .prune_added_torrents(self.torrents.iter().map(|t| t.hash.as_str()))
+                    {
+                        self.dirty = true;
+                    }
+                    match self.downloads_state.selected() {
+                        _ if self.torrents.is_empty() => self.downloads_state.select(None),
+                        None => self.downloads_state.select(Some(0)),
+                        Some(i) if i >= self.torrents.len() => {
+                            self.downloads_state.select(Some(self.torrents.len() - 1))
+                        }
+                        Some(_) => {}
                     }
                 }
                 AppMessage::UpdatesFound(updates) => {
@@ -605,20 +1040,59 @@ impl App {
                         }
 
                         if let Some(client) = &self.torrent_client {
-                            self.notifier
-                                .new_episode(&update.series_title, update.episode_number);
+                            if let Some((show_id, season, number)) = &update.replaces {
+                                info!(
+                                    "Upgrading: {} - Episode {} to {}",
+                                    update.series_title, update.episode_number, update.title
+                                );
+                                // Imported like any other download, after which the
+                                // old file goes
+                                let hash = magnet_hash(&update.link)
+                                    .or_else(|| Some(update.info_hash.clone()))
+                                    .filter(|h| h.len() == 40);
+                                self.library
+                                    .remove_binding(hash.as_deref().unwrap_or(""), &update.title);
+                                self.library.bindings.push(DownloadBinding {
+                                    name: update.title.clone(),
+                                    hash,
+                                    target: BindTarget::Show(show_id.clone()),
+                                    replaces: Some((*season, *number)),
+                                });
+                                self.dirty = true;
+                            } else if let Some(batch) = &update.batch {
+                                self.notifier
+                                    .missed_episodes(&update.series_title, batch.episodes.len());
+                            } else {
+                                self.notifier
+                                    .new_episode(&update.series_title, update.episode_number);
+                            }
 
                             info!(
                                 "Auto-downloading: {} - {}",
                                 update.series_title, update.title
                             );
                             let client = client.clone();
-                            let magnet = update.magnet.clone();
+                            let link = update.link.clone();
+                            let info_hash = update.info_hash.clone();
+                            let batch = update.batch;
                             let tx = self.msg_tx.clone();
                             tokio::spawn(async move {
-                                match client.add_magnet(&magnet).await {
-                                    Ok(_) => {
-                                        let _ = tx.send(AppMessage::TorrentAdded(magnet));
+                                match client.add_link(&link, &info_hash).await {
+                                    Ok(hash) => {
+                                        let _ = tx.send(AppMessage::TorrentAdded(hash.clone()));
+                                        // Only the missing episodes' files
+                                        if let Some(batch) = batch {
+                                            match select_batch_files(&client, &hash, &batch).await {
+                                                Ok(skipped) => {
+                                                    info!(hash = %hash, skipped, "Selected batch files")
+                                                }
+                                                Err(e) => {
+                                                    let _ = tx.send(AppMessage::TorrentError(
+                                                        e.to_string(),
+                                                    ));
+                                                }
+                                            }
+                                        }
                                     }
                                     Err(e) => {
                                         let _ = tx.send(AppMessage::TorrentError(e.to_string()));
@@ -638,6 +1112,9 @@ impl App {
                         }
                     }
                 }
+                AppMessage::SeedingCheck => {
+                    self.enforce_seeding_policies();
+                }
                 AppMessage::PreviewTorrentData(files) => {
                     if self.view == View::PreviewPopup {
                         if let Some(ref mut state) = self.preview_state {
@@ -666,6 +1143,81 @@ impl App {
                         }
                     }
                 }
+                AppMessage::TorrentDetailsLoaded(details) => {
+                    let selected_hash = self
+                        .downloads_state
+                        .selected()
+                        .and_then(|i| self.torrents.get(i))
+                        .map(|t| t.hash.as_str());
+                    if self.view == View::TorrentDetails && selected_hash == Some(&details.hash) {
+                        if details.files.is_empty() {
+                            self.torrent_files_state.select(None);
+                        } else if self
+                            .torrent_files_state
+                            .selected()
+                            .is_none_or(|i| i >= details.files.len())
+                        {
+                            self.torrent_files_state.select(Some(0));
+                        }
+                        self.torrent_details = PreviewSection::Loaded(details);
+                    }
+                }
+                AppMessage::TorrentDetailsError(err) => {
+                    if self.view == View::TorrentDetails {
+                        self.torrent_details = PreviewSection::Error(err);
+                    }
+                }
+                AppMessage::WatchedTorrent(path, bytes) => {
+                    info!(path = %path.display(), "Found torrent in watch folder");
+                    self.ingest(PreviewSource::TorrentFile { path, bytes });
+                }
+                AppMessage::MalProgressPushed(update, result) => match result {
+                    Ok(PushOutcome::Updated) => {
+                        info!(anime_id = update.anime_id, "Pushed list progress");
+                        self.mal_queue.remove(&update);
+                        self.mal_queue.persist();
+                    }
+                    Ok(PushOutcome::RemoteAhead(watched)) => {
+                        info!(
+                            anime_id = update.anime_id,
+                            watched, "List is ahead, catching up locally"
+                        );
+                        self.mal_queue.remove(&update);
+                        self.mal_queue.persist();
+                        if self.library.mark_watched_through(
+                            update.provider,
+                            update.anime_id,
+                            watched,
+                        ) {
+                            self.dirty = true;
+                            let _ = self.library.save();
+                        }
+                    }
+                    Err(e) => {
+                        warn!(anime_id = update.anime_id, error = %e, "List progress push failed, queued");
+                        self.mal_queue.enqueue(update);
+                        self.mal_queue.persist();
+                    }
+                },
+                AppMessage::MalSyncRetry => self.retry_mal_queue(),
+                AppMessage::MalAuthRedirect(result) => {
+                    self.mal_sync.redirect_listener = None;
+                    let waiting = matches!(
+                        self.mal_sync.step,
+                        MalSyncStep::ShowAuthUrl | MalSyncStep::WaitingForCode
+                    );
+                    if self.view != View::MalSyncDialog || !waiting {
+                        continue;
+                    }
+                    match result {
+                        Ok(code) => {
+                            self.mal_sync.code_input = code;
+                            self.mal_sync.step = MalSyncStep::Syncing;
+                            self.mal_sync.login_pending = true;
+                        }
+                        Err(e) => self.mal_sync.step = MalSyncStep::Error(e),
+                    }
+                }
             }
         }
     }
@@ -691,7 +1243,13 @@ impl App {
                     &mut self.picker,
                 );
 
-                let help = widgets::help_bar(&[("?", "help"), ("q", "quit")]);
+                let review = format!("review {} matches", self.match_review.len());
+                let mut keys = vec![("?", "help")];
+                if !self.match_review.is_empty() {
+                    keys.push(("M", review.as_str()));
+                }
+                keys.push(("q", "quit"));
+                let help = widgets::help_bar(&keys);
                 frame.render_widget(help, help_area);
             }
             View::Episodes => {
@@ -718,9 +1276,12 @@ impl App {
                     &self.search_results,
                     &mut self.search_state,
                     self.search_loading,
+                    self.search_total,
+                    self.search_more,
                     self.search_category,
                     self.search_filter,
                     self.search_sort,
+                    &self.config.scoring.default,
                     self.accent,
                 );
 
@@ -728,7 +1289,12 @@ impl App {
                     render_glossary_popup(frame, self.accent);
                 }
 
-                let help = widgets::help_bar(&[("^g", "glossary"), ("?", "help"), ("Esc", "back")]);
+                let help = widgets::help_bar(&[
+                    ("Tab", "complete filter"),
+                    ("^g", "glossary"),
+                    ("?", "help"),
+                    ("Esc", "back"),
+                ]);
                 frame.render_widget(help, help_area);
             }
             View::Downloads => {
@@ -743,6 +1309,68 @@ impl App {
                 let help = widgets::help_bar(&[("?", "help"), ("Esc", "back")]);
                 frame.render_widget(help, help_area);
             }
+            View::AddTorrentDialog => {
+                render_downloads_view(
+                    frame,
+                    main_area,
+                    &self.torrents,
+                    &mut self.downloads_state,
+                    self.accent,
+                );
+                self.render_add_torrent_dialog(frame);
+
+                let help = widgets::help_bar(&[("Enter", "preview"), ("Esc", "cancel")]);
+                frame.render_widget(help, help_area);
+            }
+            View::ScorePrompt => {
+                if self.previous_view == View::Episodes {
+                    if let Some(show) = self
+                        .selected_show_idx
+                        .and_then(|i| self.library.shows.get(i))
+                    {
+                        render_episodes_view(
+                            frame,
+                            main_area,
+                            show,
+                            &mut self.episodes_state,
+                            self.accent,
+                        );
+                    }
+                } else {
+                    render_library_view(
+                        frame,
+                        main_area,
+                        &self.library.shows,
+                        &mut self.library_state,
+                        self.accent,
+                        &self.image_cache,
+                        &mut self.picker,
+                    );
+                }
+                self.render_score_prompt(frame);
+
+                let help = widgets::help_bar(&[("Enter", "submit"), ("Esc", "skip")]);
+                frame.render_widget(help, help_area);
+            }
+            View::TorrentDetails => {
+                render_torrent_details_view(
+                    frame,
+                    main_area,
+                    &self.torrents,
+                    &mut self.downloads_state,
+                    &self.torrent_details,
+                    &mut self.torrent_files_state,
+                    self.accent,
+                );
+
+                let help = widgets::help_bar(&[
+                    ("j/k", "files"),
+                    ("Space", "toggle file"),
+                    ("r", "refresh"),
+                    ("Esc", "back"),
+                ]);
+                frame.render_widget(help, help_area);
+            }
             View::Archives => {
                 self.render_archives_view(frame, main_area);
                 let help = widgets::help_bar(&[("?", "help"), ("Esc", "back")]);
@@ -841,50 +1469,117 @@ impl App {
                 self.render_tracking_list(frame, main_area);
                 let help = widgets::help_bar(&[
                     ("?", "help"),
-                    ("S", "MAL sync"),
+                    ("S", "list sync"),
+                    ("u", "upgrades"),
+                    ("w", "watchlist"),
                     ("x", "untrack"),
                     ("Esc", "back"),
                 ]);
                 frame.render_widget(help, help_area);
             }
-            View::MalSyncDialog => {
-                self.render_tracking_list(frame, main_area);
-                self.render_mal_sync_dialog(frame);
-                let help = widgets::help_bar(&[("Enter", "continue"), ("Esc", "cancel")]);
+            View::Calendar => {
+                self.render_calendar(frame, main_area);
+                let help = widgets::help_bar(&[("j/k", "scroll"), ("Esc", "back")]);
                 frame.render_widget(help, help_area);
             }
-            View::PreviewPopup => {
-                render_search_view(
+            View::Watchlist => {
+                self.render_watchlist(frame, main_area);
+                let help =
+                    widgets::help_bar(&[("Enter", "track"), ("x", "remove"), ("Esc", "back")]);
+                frame.render_widget(help, help_area);
+            }
+            View::MatchDialog => {
+                render_library_view(
                     frame,
                     main_area,
-                    &self.search_query,
-                    &self.search_results,
-                    &mut self.search_state,
-                    self.search_loading,
-                    self.search_category,
-                    self.search_filter,
-                    self.search_sort,
+                    &self.library.shows,
+                    &mut self.library_state,
                     self.accent,
+                    &self.image_cache,
+                    &mut self.picker,
                 );
-
-                if let Some(ref mut preview) = self.preview_state {
-                    render_preview_popup(frame, preview, self.accent);
-                }
-
-                let help_hints: &[(&str, &str)] = if self
-                    .preview_state
-                    .as_ref()
-                    .is_some_and(|p| p.is_magnet_only)
-                {
-                    &[("Enter", "download anyway"), ("Esc", "close")]
-                } else {
-                    &[("Enter", "download"), ("j/k", "scroll"), ("Esc", "close")]
-                };
-                let help = widgets::help_bar(help_hints);
+                self.render_match_dialog(frame);
+                let help = widgets::help_bar(&[
+                    ("Enter", "search/pick"),
+                    ("↑/↓", "select"),
+                    ("Esc", "cancel"),
+                ]);
                 frame.render_widget(help, help_area);
             }
-            View::Help => {
-                match self.previous_view {
+            View::MatchReview => {
+                self.render_match_review(frame, main_area);
+                let help = widgets::help_bar(&[
+                    ("Enter", "accept"),
+                    ("s", "skip"),
+                    ("n", "no match"),
+                    ("Esc", "back"),
+                ]);
+                frame.render_widget(help, help_area);
+            }
+            View::MalSyncDialog => {
+                self.render_tracking_list(frame, main_area);
+                self.render_mal_sync_dialog(frame);
+                let help = widgets::help_bar(&[("Enter", "continue"), ("Esc", "cancel")]);
+                frame.render_widget(help, help_area);
+            }
+            View::PreviewPopup => {
+                let from_search = self
+                    .preview_state
+                    .as_ref()
+                    .is_none_or(|p| matches!(p.source, PreviewSource::Search));
+                if from_search {
+                    render_search_view(
+                        frame,
+                        main_area,
+                        &self.search_query,
+                        &self.search_results,
+                        &mut self.search_state,
+                        self.search_loading,
+                        self.search_total,
+                        self.search_more,
+                        self.search_category,
+                        self.search_filter,
+                        self.search_sort,
+                        &self.config.scoring.default,
+                        self.accent,
+                    );
+                } else {
+                    render_downloads_view(
+                        frame,
+                        main_area,
+                        &self.torrents,
+                        &mut self.downloads_state,
+                        self.accent,
+                    );
+                }
+
+                if let Some(ref mut preview) = self.preview_state {
+                    render_preview_popup(frame, preview, self.accent);
+                }
+
+                let help_hints: &[(&str, &str)] = if self
+                    .preview_state
+                    .as_ref()
+                    .is_some_and(|p| p.is_magnet_only)
+                {
+                    &[
+                        ("Enter", "download anyway"),
+                        ("b", "bind"),
+                        ("Esc", "close"),
+                    ]
+                } else {
+                    &[
+                        ("Enter", "download"),
+                        ("j/k", "scroll"),
+                        ("b", "bind"),
+                        ("Esc", "close"),
+                    ]
+                };
+                let help = widgets::help_bar(help_hints);
+                frame.render_widget(help, help_area);
+            }
+            View::Help => {
+                match self.previous_view {
                     View::Library => render_library_view(
                         frame,
                         main_area,
@@ -914,9 +1609,12 @@ impl App {
                         &self.search_results,
                         &mut self.search_state,
                         self.search_loading,
+                        self.search_total,
+                        self.search_more,
                         self.search_category,
                         self.search_filter,
                         self.search_sort,
+                        &self.config.scoring.default,
                         self.accent,
                     ),
                     View::Downloads => render_downloads_view(
@@ -927,6 +1625,9 @@ impl App {
                         self.accent,
                     ),
                     View::TrackingList => self.render_tracking_list(frame, main_area),
+                    View::Watchlist => self.render_watchlist(frame, main_area),
+                    View::Calendar => self.render_calendar(frame, main_area),
+                    View::MatchReview => self.render_match_review(frame, main_area),
                     View::MalSyncDialog => {
                         self.render_tracking_list(frame, main_area);
                         self.render_mal_sync_dialog(frame);
@@ -963,6 +1664,13 @@ impl App {
                     View::TrackingList => self.handle_tracking_list_input(key.code)?,
                     View::PreviewPopup => self.handle_preview_input(key.code)?,
                     View::MalSyncDialog => self.handle_mal_sync_input(key).await?,
+                    View::TorrentDetails => self.handle_torrent_details_input(key.code)?,
+                    View::AddTorrentDialog => self.handle_add_torrent_input(key.code)?,
+                    View::ScorePrompt => self.handle_score_prompt_input(key.code),
+                    View::Watchlist => self.handle_watchlist_input(key.code)?,
+                    View::MatchReview => self.handle_match_review_input(key.code)?,
+                    View::MatchDialog => self.handle_match_dialog_input(key.code)?,
+                    View::Calendar => self.handle_calendar_input(key.code),
                 }
             }
         }
@@ -988,42 +1696,17 @@ impl App {
             }
             KeyCode::Char('m') => {
                 if let Some(idx) = self.library_state.selected() {
-                    if let Some(show) = self.library.shows.get(idx) {
-                        if let Some(provider) = self.metadata_provider.clone() {
-                            let show_id = show.id.clone();
-                            let query = show.title.clone();
-                            let tx = self.msg_tx.clone();
-
-                            info!("Fetching metadata for: {}", query);
-
-                            tokio::spawn(async move {
-                                match provider.search(&query).await {
-                                    Ok(results) => {
-                                        if let Some(first) = results.into_iter().next() {
-                                            let _ =
-                                                tx.send(AppMessage::MetadataFound(show_id, first));
-                                        } else {
-                                            let _ = tx.send(AppMessage::MetadataError(format!(
-                                                "No results for: {}",
-                                                query
-                                            )));
-                                        }
-                                    }
-                                    Err(e) => {
-                                        let _ = tx.send(AppMessage::MetadataError(e.to_string()));
-                                    }
-                                }
-                            });
-                        } else {
-                            error!("No metadata provider configured (check mal_client_id)");
-                        }
-                    }
+                    self.open_match_dialog(idx);
                 }
             }
+            KeyCode::Char('M') if !self.match_review.is_empty() => {
+                self.view = View::MatchReview;
+                self.next_match_review();
+            }
             KeyCode::Char('/') => {
                 self.view = View::Search;
                 self.search_query.clear();
-                self.search_results.clear();
+                self.clear_search_results();
             }
             KeyCode::Char('d') => {
                 self.view = View::Downloads;
@@ -1061,6 +1744,10 @@ impl App {
                     self.archives_state.select(Some(0));
                 }
             }
+            KeyCode::Char('c') => {
+                self.view = View::Calendar;
+                self.calendar_state.select(Some(0));
+            }
             KeyCode::Char('?') => {
                 self.toggle_help();
             }
@@ -1123,7 +1810,10 @@ impl App {
                     self.update_filtered_results();
                 }
                 KeyCode::Up => self.move_selection_up(&View::Search),
-                KeyCode::Down => self.move_selection_down(&View::Search),
+                KeyCode::Down => {
+                    self.move_selection_down(&View::Search);
+                    self.load_more_search_results();
+                }
                 _ => {}
             }
         } else if self.show_glossary {
@@ -1153,9 +1843,18 @@ impl App {
                 KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                     self.search_filter = self.search_filter.next();
                 }
-                KeyCode::Tab | KeyCode::Down => {
+                KeyCode::Tab => {
+                    if let Some(completed) = query::complete(&self.search_query) {
+                        self.search_query = completed;
+                    } else if !self.search_results.is_empty() {
+                        self.move_selection_down(&View::Search);
+                        self.load_more_search_results();
+                    }
+                }
+                KeyCode::Down => {
                     if !self.search_results.is_empty() {
                         self.move_selection_down(&View::Search);
+                        self.load_more_search_results();
                     }
                 }
                 KeyCode::Up => {
@@ -1205,6 +1904,17 @@ impl App {
     }
 
     fn update_filtered_results(&mut self) {
+        self.filter_search_results();
+        if !self.filtered_search_results.is_empty() {
+            self.search_state.select(Some(0));
+        } else {
+            self.search_state.select(None);
+        }
+    }
+
+    /// Recomputes which results the filter lets through, leaving the
+    /// selection where it is
+    fn filter_search_results(&mut self) {
         if self.search_filter_input.is_empty() {
             self.filtered_search_results = (0..self.search_results.len()).collect();
         } else {
@@ -1217,11 +1927,6 @@ impl App {
                 .map(|(i, _)| i)
                 .collect();
         }
-        if !self.filtered_search_results.is_empty() {
-            self.search_state.select(Some(0));
-        } else {
-            self.search_state.select(None);
-        }
     }
 
     async fn handle_downloads_input(&mut self, key: KeyCode) -> Result<()> {
@@ -1253,6 +1958,13 @@ impl App {
             KeyCode::Char('t') => {
                 self.open_tracking_dialog();
             }
+            KeyCode::Char('i') => {
+                self.open_torrent_details();
+            }
+            KeyCode::Char('a') => {
+                self.add_torrent_dialog = AddTorrentDialogState::default();
+                self.view = View::AddTorrentDialog;
+            }
             KeyCode::Enter => {
                 self.play_selected_download()?;
             }
@@ -1264,6 +1976,133 @@ impl App {
         Ok(())
     }
 
+    fn open_torrent_details(&mut self) {
+        if self
+            .downloads_state
+            .selected()
+            .and_then(|i| self.torrents.get(i))
+            .is_none()
+        {
+            return;
+        }
+
+        self.torrent_details = PreviewSection::Loading;
+        self.torrent_files_state = ListState::default();
+        self.view = View::TorrentDetails;
+        self.load_torrent_details();
+    }
+
+    fn load_torrent_details(&self) {
+        let Some(client) = self.torrent_client.clone() else {
+            return;
+        };
+        let Some(torrent) = self
+            .downloads_state
+            .selected()
+            .and_then(|i| self.torrents.get(i))
+        else {
+            return;
+        };
+
+        let hash = torrent.hash.clone();
+        let tx = self.msg_tx.clone();
+
+        tokio::spawn(async move {
+            match client.details(&hash).await {
+                Ok(details) => {
+                    let _ = tx.send(AppMessage::TorrentDetailsLoaded(details));
+                }
+                Err(e) => {
+                    let _ = tx.send(AppMessage::TorrentDetailsError(e.to_string()));
+                }
+            }
+        });
+    }
+
+    fn handle_torrent_details_input(&mut self, key: KeyCode) -> Result<()> {
+        let file_count = match &self.torrent_details {
+            PreviewSection::Loaded(details) => details.files.len(),
+            _ => 0,
+        };
+
+        match key {
+            KeyCode::Esc | KeyCode::Char('i') | KeyCode::Char('h') => {
+                self.view = View::Downloads;
+            }
+            KeyCode::Char('q') => {
+                self.running = false;
+            }
+            KeyCode::Char('j') | KeyCode::Down => {
+                if file_count > 0 {
+                    let next = self
+                        .torrent_files_state
+                        .selected()
+                        .map(|i| (i + 1).min(file_count - 1))
+                        .unwrap_or(0);
+                    self.torrent_files_state.select(Some(next));
+                }
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                let prev = self
+                    .torrent_files_state
+                    .selected()
+                    .map(|i| i.saturating_sub(1))
+                    .unwrap_or(0);
+                self.torrent_files_state.select(Some(prev));
+            }
+            KeyCode::Char('r') => {
+                self.load_torrent_details();
+            }
+            KeyCode::Char(' ') => {
+                self.toggle_selected_torrent_file();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn toggle_selected_torrent_file(&mut self) {
+        let Some(client) = self.torrent_client.clone() else {
+            return;
+        };
+        let PreviewSection::Loaded(details) = &mut self.torrent_details else {
+            return;
+        };
+        let Some(file) = self
+            .torrent_files_state
+            .selected()
+            .and_then(|i| details.files.get_mut(i))
+        else {
+            return;
+        };
+
+        let priority = if file.priority == FilePriority::Skip {
+            FilePriority::Normal
+        } else {
+            FilePriority::Skip
+        };
+        // Update locally right away, the reload below confirms it
+        file.priority = priority;
+
+        let hash = details.hash.clone();
+        let index = file.index;
+        let tx = self.msg_tx.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = client.set_file_priority(&hash, &[index], priority).await {
+                let _ = tx.send(AppMessage::TorrentError(e.to_string()));
+            }
+            match client.details(&hash).await {
+                Ok(details) => {
+                    let _ = tx.send(AppMessage::TorrentDetailsLoaded(details));
+                }
+                Err(e) => {
+                    let _ = tx.send(AppMessage::TorrentDetailsError(e.to_string()));
+                }
+            }
+        });
+    }
+
     fn move_selection_down(&mut self, view: &View) {
         let (state, len) = match view {
             View::Library => (&mut self.library_state, self.library.shows.len()),
@@ -1284,7 +2123,14 @@ impl App {
             | View::Help
             | View::TrackingList
             | View::PreviewPopup
-            | View::MalSyncDialog => return,
+            | View::MalSyncDialog
+            | View::TorrentDetails
+            | View::AddTorrentDialog
+            | View::ScorePrompt
+            | View::Watchlist
+            | View::MatchReview
+            | View::MatchDialog
+            | View::Calendar => return,
         };
 
         if len == 0 {
@@ -1318,7 +2164,14 @@ impl App {
             | View::Help
             | View::TrackingList
             | View::PreviewPopup
-            | View::MalSyncDialog => return,
+            | View::MalSyncDialog
+            | View::TorrentDetails
+            | View::AddTorrentDialog
+            | View::ScorePrompt
+            | View::Watchlist
+            | View::MatchReview
+            | View::MatchDialog
+            | View::Calendar => return,
         };
 
         if len > 0 {
@@ -1345,8 +2198,42 @@ impl App {
                 if !self.library.shows[idx].episodes.is_empty() {
                     self.episodes_state.select(Some(0));
                 }
+                self.load_episode_info(idx);
+            }
+        }
+    }
+
+    /// Episode titles and filler flags: the show's `episodes.toml` if it has
+    /// one, otherwise fetched once a session (only refetched while airing)
+    fn load_episode_info(&mut self, idx: usize) {
+        let show = &mut self.library.shows[idx];
+        match episodes::load_local(&show.path) {
+            Ok(Some(info)) => {
+                show.episode_info = info;
+                return;
             }
+            Ok(None) => {}
+            Err(e) => warn!(show = %show.title, error = %e, "Bad {}", episodes::LOCAL_FILE),
+        }
+
+        let Some(metadata) = show.metadata.clone() else {
+            return;
+        };
+        let stale = show.episode_info.is_empty() || metadata.status == "currently_airing";
+        if !stale || !self.episode_info_requested.insert(show.id.clone()) {
+            return;
         }
+
+        let show_id = show.id.clone();
+        let offline_db = self.offline_db.clone();
+        let tx = self.msg_tx.clone();
+        tokio::spawn(async move {
+            let result = episodes::fetch(&metadata, offline_db.as_deref()).await;
+            let _ = tx.send(AppMessage::EpisodeInfo(
+                show_id,
+                result.map_err(|e| e.to_string()),
+            ));
+        });
     }
 
     fn play_selected_episode(&mut self) -> Result<()> {
@@ -1424,19 +2311,24 @@ impl App {
             }
         }
 
+        let mut progress = None;
         if let Some(pos) = last_position {
             if last_duration > 0 && pos > last_duration.saturating_sub(120) {
-                self.library.mark_watched(&show_id, episode_number);
+                progress = self.library.mark_watched(&show_id, episode_number);
             } else if pos > 10 {
                 self.library.update_position(&show_id, episode_number, pos);
             }
         } else {
-            self.library.mark_watched(&show_id, episode_number);
+            progress = self.library.mark_watched(&show_id, episode_number);
         }
         self.dirty = true;
         self.library.save()?;
         self.dirty = false;
 
+        if let Some(update) = progress {
+            self.sync_progress(update, &show_title);
+        }
+
         Ok(())
     }
 
@@ -1447,7 +2339,12 @@ impl App {
 
         let (show_id, show_title, episode_number, path, start_pos) = {
             let show = &self.library.shows[show_idx];
-            let Some(episode) = show.next_unwatched() else {
+            let next = if self.config.general.skip_filler {
+                show.next_unwatched_skipping_filler()
+            } else {
+                show.next_unwatched()
+            };
+            let Some(episode) = next else {
                 return Ok(());
             };
 
@@ -1519,26 +2416,29 @@ impl App {
             }
         }
 
+        let mut progress = None;
         if let Some(pos) = last_position {
             if last_duration > 0 && pos > last_duration.saturating_sub(120) {
-                self.library.mark_watched(&show_id, episode_number);
+                progress = self.library.mark_watched(&show_id, episode_number);
             } else if pos > 10 {
                 self.library.update_position(&show_id, episode_number, pos);
             }
         } else {
-            self.library.mark_watched(&show_id, episode_number);
+            progress = self.library.mark_watched(&show_id, episode_number);
         }
         self.dirty = true;
         self.library.save()?;
         self.dirty = false;
 
+        if let Some(update) = progress {
+            self.sync_progress(update, &show_title);
+        }
+
         Ok(())
     }
 
-    fn download_selected_torrent(&mut self) {
-        let Some(idx) = self.search_state.selected() else {
-            return;
-        };
+    fn selected_search_result(&self) -> Option<&NyaaResult> {
+        let idx = self.search_state.selected()?;
 
         let result_idx = if !self.filtered_search_results.is_empty() {
             *self.filtered_search_results.get(idx).unwrap_or(&idx)
@@ -1546,18 +2446,23 @@ impl App {
             idx
         };
 
-        let Some(result) = self.search_results.get(result_idx) else {
+        self.search_results.get(result_idx)
+    }
+
+    fn download_selected_torrent(&mut self) {
+        let Some(result) = self.selected_search_result() else {
             return;
         };
 
         if let Some(client) = self.torrent_client.clone() {
-            let magnet = result.magnet_link.clone();
+            let link = result.download_link().to_string();
+            let info_hash = result.info_hash.clone();
             let tx = self.msg_tx.clone();
 
             info!(title = %result.title, "Adding torrent");
 
             tokio::spawn(async move {
-                match client.add_magnet(&magnet).await {
+                match client.add_link(&link, &info_hash).await {
                     Ok(hash) => {
                         let _ = tx.send(AppMessage::TorrentAdded(hash));
                     }
@@ -1572,22 +2477,13 @@ impl App {
     }
 
     fn open_preview_popup(&mut self) {
-        let Some(idx) = self.search_state.selected() else {
-            return;
-        };
-
-        let result_idx = if !self.filtered_search_results.is_empty() {
-            *self.filtered_search_results.get(idx).unwrap_or(&idx)
-        } else {
-            idx
-        };
-
-        let Some(result) = self.search_results.get(result_idx) else {
+        let Some(result) = self.selected_search_result() else {
             return;
         };
 
         let is_magnet_only = result.torrent_url.is_empty();
 
+        let (bind_options, bind_selected) = self.bind_options_for(&result.title);
         let mut state = PreviewState {
             torrent_title: result.title.clone(),
             torrent_files: if is_magnet_only {
@@ -1601,6 +2497,9 @@ impl App {
             mal_info: PreviewSection::Loading,
             is_magnet_only,
             scroll_state: ListState::default(),
+            source: PreviewSource::Search,
+            bind_options,
+            bind_selected,
         };
 
         if !is_magnet_only {
@@ -1619,32 +2518,7 @@ impl App {
             });
         }
 
-        if let Some(provider) = self.metadata_provider.clone() {
-            let title = extract_anime_title(&result.title);
-            let tx = self.msg_tx.clone();
-            tokio::spawn(async move {
-                let result =
-                    tokio::time::timeout(Duration::from_secs(10), provider.search(&title)).await;
-                match result {
-                    Ok(Ok(results)) => {
-                        if let Some(first) = results.into_iter().next() {
-                            let _ = tx.send(AppMessage::PreviewMalData(first));
-                        } else {
-                            let _ =
-                                tx.send(AppMessage::PreviewMalError("No MAL match".to_string()));
-                        }
-                    }
-                    Ok(Err(e)) => {
-                        let _ = tx.send(AppMessage::PreviewMalError(e.to_string()));
-                    }
-                    Err(_) => {
-                        let _ = tx.send(AppMessage::PreviewMalError(
-                            "MAL lookup timed out".to_string(),
-                        ));
-                    }
-                }
-            });
-        } else {
+        if !self.start_preview_mal_lookup(&result.title) {
             state.mal_info = PreviewSection::Error("MAL not configured".to_string());
         }
 
@@ -1652,51 +2526,453 @@ impl App {
         self.view = View::PreviewPopup;
     }
 
-    fn handle_preview_input(&mut self, key: KeyCode) -> Result<()> {
-        match key {
-            KeyCode::Esc => {
-                self.view = View::Search;
-                self.preview_state = None;
-            }
-            KeyCode::Char('j') | KeyCode::Down => {
-                if let Some(ref mut state) = self.preview_state {
-                    let total = preview_item_count(state);
-                    if total > 0 {
-                        let next = match state.scroll_state.selected() {
-                            Some(i) => (i + 1).min(total - 1),
-                            None => 0,
-                        };
-                        state.scroll_state.select(Some(next));
+    /// Look up the torrent's anime on MAL for the preview footer. Returns
+    /// false when no metadata provider is configured.
+    fn start_preview_mal_lookup(&self, torrent_title: &str) -> bool {
+        let Some(provider) = self.metadata_provider.clone() else {
+            return false;
+        };
+
+        let title = extract_anime_title(torrent_title);
+        let tx = self.msg_tx.clone();
+        tokio::spawn(async move {
+            let result =
+                tokio::time::timeout(Duration::from_secs(10), provider.search(&title)).await;
+            match result {
+                Ok(Ok(results)) => {
+                    if let Some(first) = results.into_iter().next() {
+                        let _ = tx.send(AppMessage::PreviewMalData(first));
+                    } else {
+                        let _ = tx.send(AppMessage::PreviewMalError("No MAL match".to_string()));
                     }
                 }
-            }
-            KeyCode::Char('k') | KeyCode::Up => {
-                if let Some(ref mut state) = self.preview_state {
-                    let total = preview_item_count(state);
-                    if total > 0 {
-                        let prev = match state.scroll_state.selected() {
-                            Some(0) | None => 0,
-                            Some(i) => i - 1,
-                        };
-                        state.scroll_state.select(Some(prev));
-                    }
+                Ok(Err(e)) => {
+                    let _ = tx.send(AppMessage::PreviewMalError(e.to_string()));
+                }
+                Err(_) => {
+                    let _ = tx.send(AppMessage::PreviewMalError(
+                        "MAL lookup timed out".to_string(),
+                    ));
                 }
             }
-            KeyCode::Enter => {
-                self.download_selected_torrent();
-                self.preview_state = None;
-            }
-            _ => {}
-        }
-        Ok(())
+        });
+        true
     }
 
-    fn play_selected_download(&mut self) -> Result<()> {
-        let Some(idx) = self.downloads_state.selected() else {
-            return Ok(());
+    /// Queue a magnet or `.torrent` file for preview. It opens once the user
+    /// is back on a browsing view, so it never interrupts typing.
+    pub fn ingest(&mut self, source: PreviewSource) {
+        self.pending_ingest.push_back(source);
+    }
+
+    /// Open the add dialog on something that couldn't be loaded, with the
+    /// reason, so it can be corrected
+    pub fn ingest_failed(&mut self, input: &str, error: &crate::error::Error) {
+        warn!(input, error = %error, "Could not open torrent");
+        self.add_torrent_dialog = AddTorrentDialogState {
+            input: input.to_string(),
+            error: Some(error.to_string()),
         };
-        let Some(torrent) = self.torrents.get(idx) else {
-            return Ok(());
+        self.view = View::AddTorrentDialog;
+    }
+
+    /// The active provider, if its list can be synced and the user has
+    /// logged in to it
+    fn list_kind(&self) -> Option<ProviderKind> {
+        let metadata = &self.config.metadata;
+        let usable = match metadata.provider {
+            ProviderKind::Mal => !metadata.mal_client_id.is_empty(),
+            ProviderKind::Anilist => true,
+            ProviderKind::Kitsu | ProviderKind::Offline => false,
+        };
+        let kind = metadata.provider;
+        (usable && self.tokens.has_token(kind)).then_some(kind)
+    }
+
+    /// Re-key progress for the active provider's list, using the offline
+    /// database's ID mapping when the show was matched on another provider
+    fn for_active_list(&self, mut update: ProgressUpdate) -> ProgressUpdate {
+        let active = self.config.metadata.provider;
+        if update.provider == active {
+            return update;
+        }
+        let mapped = self
+            .offline_db
+            .as_ref()
+            .and_then(|db| db.map_id(update.provider, update.anime_id, active));
+        if let Some(id) = mapped {
+            update.provider = active;
+            update.anime_id = id;
+        }
+        update
+    }
+
+    /// Send newly watched progress to the list, asking for a score first when
+    /// the show was just finished
+    fn sync_progress(&mut self, update: ProgressUpdate, title: &str) {
+        let update = self.for_active_list(update);
+        if update.is_complete() && update.score.is_none() {
+            self.score_prompt = Some(ScorePromptState {
+                update,
+                title: title.to_string(),
+                input: String::new(),
+            });
+            self.previous_view = self.view;
+            self.view = View::ScorePrompt;
+            return;
+        }
+        self.push_progress(update);
+    }
+
+    fn push_progress(&mut self, update: ProgressUpdate) {
+        if self.list_kind() != Some(update.provider) {
+            // Not logged in yet (or matched on the other provider), keep it
+            // for when we are
+            self.mal_queue.enqueue(update);
+            self.mal_queue.persist();
+            return;
+        }
+
+        let tx = self.msg_tx.clone();
+        let tokens = self.tokens.clone();
+        let metadata = self.config.metadata.clone();
+        tokio::spawn(async move {
+            let result = tokens
+                .with_token(update.provider, |token| {
+                    let client = list_client_for(&metadata, update.provider, token);
+                    let update = &update;
+                    async move { push_progress(&*client, update).await }
+                })
+                .await
+                .map_err(|e| e.to_string());
+            let _ = tx.send(AppMessage::MalProgressPushed(update, result));
+        });
+    }
+
+    fn retry_mal_queue(&mut self) {
+        let Some(kind) = self.list_kind() else {
+            return;
+        };
+        for update in self.mal_queue.pending.clone() {
+            let mapped = self.for_active_list(update.clone());
+            if mapped.provider != kind {
+                continue;
+            }
+            if mapped != update {
+                self.mal_queue.remove(&update);
+                self.mal_queue.enqueue(mapped.clone());
+            }
+            self.push_progress(mapped);
+        }
+    }
+
+    fn handle_score_prompt_input(&mut self, key: KeyCode) {
+        let Some(prompt) = &mut self.score_prompt else {
+            self.view = self.previous_view;
+            return;
+        };
+        match key {
+            KeyCode::Char(c) if c.is_ascii_digit() && prompt.input.len() < 2 => {
+                prompt.input.push(c);
+            }
+            KeyCode::Backspace => {
+                prompt.input.pop();
+            }
+            KeyCode::Enter => {
+                let score = prompt
+                    .input
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|s| (1..=10).contains(s));
+                if score.is_none() && !prompt.input.is_empty() {
+                    return;
+                }
+                let mut update = self.score_prompt.take().unwrap().update;
+                update.score = score;
+                self.view = self.previous_view;
+                self.push_progress(update);
+            }
+            KeyCode::Esc => {
+                let update = self.score_prompt.take().unwrap().update;
+                self.view = self.previous_view;
+                self.push_progress(update);
+            }
+            _ => {}
+        }
+    }
+
+    fn open_pending_ingest(&mut self) {
+        if !matches!(
+            self.view,
+            View::Library
+                | View::Episodes
+                | View::Downloads
+                | View::Archives
+                | View::TrackingList
+                | View::Watchlist
+                | View::Calendar
+        ) {
+            return;
+        }
+
+        if let Some(source) = self.pending_ingest.pop_front() {
+            self.open_ingest_preview(source);
+        }
+    }
+
+    fn open_ingest_preview(&mut self, source: PreviewSource) {
+        let (title, torrent_files, is_magnet_only) = match &source {
+            PreviewSource::Search => return,
+            PreviewSource::Magnet(magnet) => (
+                magnet_name(magnet).unwrap_or_else(|| "Magnet link".to_string()),
+                PreviewSection::Error(
+                    "Preview unavailable \u{2014} magnet links don't include file listings"
+                        .to_string(),
+                ),
+                true,
+            ),
+            PreviewSource::TorrentFile { path, bytes } => match parse_torrent(bytes) {
+                Ok((name, files)) => (name, PreviewSection::Loaded(files), false),
+                Err(e) => {
+                    error!(path = %path.display(), error = %e, "Failed to read torrent file");
+                    return;
+                }
+            },
+        };
+
+        let (bind_options, bind_selected) = self.bind_options_for(&title);
+        let mut state = PreviewState {
+            torrent_title: title,
+            torrent_files,
+            mal_info: PreviewSection::Loading,
+            is_magnet_only,
+            scroll_state: ListState::default(),
+            source,
+            bind_options,
+            bind_selected,
+        };
+
+        if !self.start_preview_mal_lookup(&state.torrent_title) {
+            state.mal_info = PreviewSection::Error("MAL not configured".to_string());
+        }
+
+        self.previous_view = self.view;
+        self.preview_state = Some(state);
+        self.view = View::PreviewPopup;
+    }
+
+    /// Shows and tracked series a download can be bound to, with the ones
+    /// matching the torrent's title first (and pre-selected)
+    fn bind_options_for(&self, torrent_title: &str) -> (Vec<(BindTarget, String)>, Option<usize>) {
+        let anime = extract_anime_title(torrent_title).to_lowercase();
+        let is_match = |title: &str| {
+            let title = title.to_lowercase();
+            !anime.is_empty()
+                && !title.is_empty()
+                && (title.contains(&anime) || anime.contains(&title))
+        };
+
+        let mut options: Vec<(BindTarget, String, bool)> = self
+            .library
+            .shows
+            .iter()
+            .map(|s| {
+                (
+                    BindTarget::Show(s.id.clone()),
+                    s.title.clone(),
+                    is_match(&s.title),
+                )
+            })
+            .chain(self.library.tracked_shows.iter().map(|s| {
+                (
+                    BindTarget::Series(s.id.clone()),
+                    format!("{} (tracked)", s.title),
+                    is_match(&s.title),
+                )
+            }))
+            .collect();
+        options.sort_by_key(|(_, _, matched)| !matched);
+
+        let selected = options
+            .first()
+            .filter(|(_, _, matched)| *matched)
+            .map(|_| 0);
+        let options = options
+            .into_iter()
+            .map(|(target, label, _)| (target, label))
+            .collect();
+        (options, selected)
+    }
+
+    fn add_previewed_torrent(&mut self, state: PreviewState) {
+        let hash = match &state.source {
+            PreviewSource::Search => {
+                let result = self.selected_search_result();
+                let hash = result
+                    .and_then(|r| magnet_hash(&r.magnet_link))
+                    .or_else(|| result.map(|r| r.info_hash.clone()));
+                self.download_selected_torrent();
+                hash
+            }
+            PreviewSource::Magnet(magnet) => {
+                self.spawn_add_torrent(&state.source);
+                magnet_hash(magnet)
+            }
+            PreviewSource::TorrentFile { .. } => {
+                self.spawn_add_torrent(&state.source);
+                None
+            }
+        };
+
+        if let Some((target, label)) = state.binding() {
+            // Base32 magnet hashes never match what the client reports
+            let hash = hash.filter(|h| h.len() == 40);
+            info!(torrent = %state.torrent_title, bound_to = %label, "Binding download");
+            self.library
+                .remove_binding(hash.as_deref().unwrap_or(""), &state.torrent_title);
+            self.library.bindings.push(DownloadBinding {
+                name: state.torrent_title.clone(),
+                hash,
+                target: target.clone(),
+                replaces: None,
+            });
+            self.dirty = true;
+        }
+
+        self.view = View::Downloads;
+    }
+
+    fn spawn_add_torrent(&self, source: &PreviewSource) {
+        let Some(client) = self.torrent_client.clone() else {
+            error!("No torrent client configured");
+            return;
+        };
+
+        let (magnet, bytes, watched_path) = match source {
+            PreviewSource::Search => return,
+            PreviewSource::Magnet(magnet) => (Some(magnet.clone()), None, None),
+            PreviewSource::TorrentFile { path, bytes } => {
+                // Files from the watch folder get renamed once added so they
+                // aren't picked up again
+                let watched = self
+                    .config
+                    .expanded_watch_dir()
+                    .is_some_and(|dir| path.parent() == Some(dir.as_path()))
+                    .then(|| path.clone());
+                (None, Some(bytes.clone()), watched)
+            }
+        };
+        let tx = self.msg_tx.clone();
+
+        tokio::spawn(async move {
+            let result = match (magnet, bytes) {
+                (Some(magnet), _) => client.add_magnet(&magnet).await,
+                (None, Some(bytes)) => client.add_torrent_file(&bytes).await,
+                (None, None) => return,
+            };
+
+            match result {
+                Ok(hash) => {
+                    if let Some(path) = watched_path {
+                        let added = path.with_extension("torrent.added");
+                        if let Err(e) = tokio::fs::rename(&path, &added).await {
+                            error!(path = %path.display(), error = %e, "Failed to mark torrent as added");
+                        }
+                    }
+                    let _ = tx.send(AppMessage::TorrentAdded(hash));
+                }
+                Err(e) => {
+                    error!("Failed to add torrent: {}", e);
+                }
+            }
+        });
+    }
+
+    fn handle_add_torrent_input(&mut self, key: KeyCode) -> Result<()> {
+        match key {
+            KeyCode::Esc => {
+                self.view = View::Downloads;
+            }
+            KeyCode::Enter => match load_preview_source(&self.add_torrent_dialog.input) {
+                Ok(source) => {
+                    self.view = View::Downloads;
+                    self.ingest(source);
+                }
+                Err(e) => {
+                    self.add_torrent_dialog.error = Some(e.to_string());
+                }
+            },
+            KeyCode::Backspace => {
+                self.add_torrent_dialog.input.pop();
+            }
+            KeyCode::Char(c) => {
+                self.add_torrent_dialog.input.push(c);
+                self.add_torrent_dialog.error = None;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_preview_input(&mut self, key: KeyCode) -> Result<()> {
+        match key {
+            KeyCode::Esc => {
+                let from_search = self
+                    .preview_state
+                    .as_ref()
+                    .is_none_or(|p| matches!(p.source, PreviewSource::Search));
+                self.view = if from_search {
+                    View::Search
+                } else {
+                    self.previous_view
+                };
+                self.preview_state = None;
+            }
+            KeyCode::Char('b') => {
+                if let Some(ref mut state) = self.preview_state {
+                    state.cycle_binding();
+                }
+            }
+            KeyCode::Char('j') | KeyCode::Down => {
+                if let Some(ref mut state) = self.preview_state {
+                    let total = preview_item_count(state);
+                    if total > 0 {
+                        let next = match state.scroll_state.selected() {
+                            Some(i) => (i + 1).min(total - 1),
+                            None => 0,
+                        };
+                        state.scroll_state.select(Some(next));
+                    }
+                }
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                if let Some(ref mut state) = self.preview_state {
+                    let total = preview_item_count(state);
+                    if total > 0 {
+                        let prev = match state.scroll_state.selected() {
+                            Some(0) | None => 0,
+                            Some(i) => i - 1,
+                        };
+                        state.scroll_state.select(Some(prev));
+                    }
+                }
+            }
+            KeyCode::Enter => {
+                if let Some(state) = self.preview_state.take() {
+                    self.add_previewed_torrent(state);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn play_selected_download(&mut self) -> Result<()> {
+        let Some(idx) = self.downloads_state.selected() else {
+            return Ok(());
+        };
+        let Some(torrent) = self.torrents.get(idx) else {
+            return Ok(());
         };
 
         if torrent.progress < 1.0 {
@@ -1763,31 +3039,127 @@ impl App {
             self.library_state.select(Some(0));
         }
 
+        self.start_auto_match();
         Ok(())
     }
 
+    /// Look for metadata for every show that has none, in the background.
+    /// Confident matches arrive as `MetadataFound`, the rest as
+    /// `MatchNeedsReview`.
+    fn start_auto_match(&mut self) {
+        if self.matcher_task.as_ref().is_some_and(|t| !t.is_finished()) {
+            return;
+        }
+        let Some(provider) = self.metadata_provider.clone() else {
+            return;
+        };
+
+        let queries: Vec<ShowQuery> = self
+            .library
+            .shows
+            .iter()
+            .filter(|s| s.metadata.is_none())
+            .filter(|s| !self.library.dismissed_matches.contains(&s.id))
+            .filter(|s| !self.match_review.iter().any(|r| r.show_id == s.id))
+            .map(ShowQuery::from_show)
+            .collect();
+        if queries.is_empty() {
+            return;
+        }
+
+        info!(count = queries.len(), "Matching shows to metadata");
+        let kind = self.config.metadata.provider;
+        let tx = self.msg_tx.clone();
+        self.matcher_task = Some(tokio::spawn(async move {
+            let cache = MatchCache::load().unwrap_or_else(|e| {
+                warn!("Failed to load match cache: {}", e);
+                MatchCache::default()
+            });
+            let mut matcher = AutoMatcher::new(provider, kind, cache);
+
+            for query in queries {
+                let message = match matcher.match_show(&query).await {
+                    Ok(MatchOutcome::Accepted(metadata)) => {
+                        AppMessage::MetadataFound(query.show_id, *metadata)
+                    }
+                    Ok(MatchOutcome::Ambiguous(candidates)) => {
+                        AppMessage::MatchNeedsReview(query, candidates)
+                    }
+                    Ok(MatchOutcome::NoMatch) => {
+                        debug!(title = %query.title, "No metadata match");
+                        continue;
+                    }
+                    // Most likely rate limited or offline; the next refresh
+                    // picks up where this left off
+                    Err(e) => {
+                        warn!(title = %query.title, error = %e, "Stopped metadata matching");
+                        break;
+                    }
+                };
+                if tx.send(message).is_err() {
+                    break;
+                }
+            }
+
+            if let Err(e) = matcher.into_cache().save() {
+                warn!("Failed to save match cache: {}", e);
+            }
+        }));
+    }
+
+    fn clear_search_results(&mut self) {
+        self.search_generation += 1;
+        self.search_loading = false;
+        self.search_results.clear();
+        self.filtered_search_results.clear();
+        self.search_page = 0;
+        self.search_more = false;
+        self.search_empty_pages = 0;
+        self.search_total = None;
+    }
+
     fn perform_search(&mut self) {
-        if self.search_query.is_empty() || self.search_loading {
+        if self.search_query.is_empty() {
             return;
         }
+        self.clear_search_results();
+        self.fetch_search_page(1);
+    }
+
+    /// The next page once the selection gets within a screenful's reach of
+    /// the end of what's loaded
+    fn load_more_search_results(&mut self) {
+        const LOOKAHEAD: usize = 10;
+        if !self.search_more || self.search_loading {
+            return;
+        }
+        let selected = self.search_state.selected().unwrap_or(0);
+        if selected + LOOKAHEAD >= self.filtered_search_results.len() {
+            self.fetch_search_page(self.search_page + 1);
+        }
+    }
 
+    fn fetch_search_page(&mut self, page: u32) {
         self.search_loading = true;
-        self.search_results.clear();
 
+        let generation = self.search_generation;
         let query = self.search_query.clone();
         let category = self.search_category;
         let filter = self.search_filter;
         let sort = self.search_sort;
-        let client = Arc::clone(&self.nyaa_client);
+        let indexers = Arc::clone(&self.indexers);
+        let profile = self.config.scoring.default.clone();
         let tx = self.msg_tx.clone();
 
         tokio::spawn(async move {
-            match client.search(&query, category, filter, sort).await {
-                Ok(results) => {
-                    let _ = tx.send(AppMessage::SearchResults(results));
+            match indexer::search_all(&indexers, &profile, &query, page, category, filter, sort)
+                .await
+            {
+                Ok(found) => {
+                    let _ = tx.send(AppMessage::SearchResults(generation, page, found));
                 }
                 Err(e) => {
-                    let _ = tx.send(AppMessage::SearchError(e.to_string()));
+                    let _ = tx.send(AppMessage::SearchError(generation, e.to_string()));
                 }
             }
         });
@@ -1812,6 +3184,58 @@ impl App {
         });
     }
 
+    fn enforce_seeding_policies(&self) {
+        let Some(client) = self.torrent_client.clone() else {
+            return;
+        };
+
+        let tracked = self.library.tracked_shows.clone();
+        let global = self.config.torrent.seeding.clone();
+        if !seeding::has_any_policy(&tracked, &global) {
+            return;
+        }
+        let added: HashSet<String> = self.library.added_torrents.iter().cloned().collect();
+
+        let tx = self.msg_tx.clone();
+
+        tokio::spawn(async move {
+            let torrents = match client.list_torrents().await {
+                Ok(t) => t,
+                Err(e) => {
+                    let _ = tx.send(AppMessage::TorrentError(e.to_string()));
+                    return;
+                }
+            };
+
+            let to_remove = seeding::torrents_to_remove(&torrents, &added, &tracked, &global);
+            let mut removed = Vec::new();
+            for hash in to_remove {
+                if let Some(t) = torrents.iter().find(|t| t.hash == hash) {
+                    info!(
+                        ratio = t.ratio,
+                        seeding_time = t.seeding_time,
+                        "Seeding policy met, removing torrent: {}",
+                        t.name
+                    );
+                }
+                match client.remove(&hash, false).await {
+                    Ok(()) => removed.push(hash),
+                    Err(e) => {
+                        let _ = tx.send(AppMessage::TorrentError(e.to_string()));
+                    }
+                }
+            }
+
+            // Leaving the removed ones out of the list also drops them
+            // from the library's added torrents
+            let remaining = torrents
+                .into_iter()
+                .filter(|t| !removed.contains(&t.hash))
+                .collect();
+            let _ = tx.send(AppMessage::TorrentList(remaining));
+        });
+    }
+
     async fn toggle_torrent_pause(&mut self) {
         let Some(idx) = self.downloads_state.selected() else {
             return;
@@ -1936,10 +3360,63 @@ impl App {
             batch_analysis,
             batch_strategy: BatchMoveStrategy::default(),
         };
+        self.apply_move_binding(idx);
 
         self.view = View::MoveDialog;
     }
 
+    /// Skip straight to the bound show's folder when the download was bound
+    /// to a show (or a tracked series that already has one) when it was added
+    fn apply_move_binding(&mut self, torrent_idx: usize) {
+        let Some(torrent) = self.torrents.get(torrent_idx) else {
+            return;
+        };
+        let Some(binding) = self.library.binding_for(&torrent.hash, &torrent.name) else {
+            return;
+        };
+
+        let show = match &binding.target {
+            BindTarget::Show(id) => self.library.get_show(id),
+            BindTarget::Series(id) => self
+                .library
+                .tracked_shows
+                .iter()
+                .find(|s| &s.id == id)
+                .and_then(|series| {
+                    self.library
+                        .shows
+                        .iter()
+                        .find(|s| s.title.eq_ignore_ascii_case(&series.title))
+                }),
+        };
+        let Some(show_path) = show.map(|s| s.path.clone()) else {
+            return;
+        };
+        let (Some(media_dir), Some(folder)) = (show_path.parent(), show_path.file_name()) else {
+            return;
+        };
+        let Some(dir_idx) = self
+            .move_dialog
+            .media_dirs
+            .iter()
+            .position(|d| d == media_dir)
+        else {
+            return;
+        };
+
+        let mut shows = list_subdirs(media_dir);
+        shows.sort();
+        let folder = folder.to_string_lossy();
+
+        self.move_dialog.media_dir_state.select(Some(dir_idx));
+        self.move_dialog.selected_media_dir = Some(media_dir.to_path_buf());
+        self.move_dialog
+            .show_state
+            .select(shows.iter().position(|s| *s == folder).or(Some(0)));
+        self.move_dialog.shows_in_dir = shows;
+        self.move_dialog.step = MoveDialogStep::SelectShow;
+    }
+
     fn open_tracking_dialog(&mut self) {
         self.tracking_state = TrackingDialogState::default();
         self.view = View::TrackingDialog;
@@ -1994,6 +3471,9 @@ impl App {
                         season,
                         metadata_id: None,
                         cached_metadata: None,
+                        seeding: None,
+                        scoring_profile: None,
+                        upgrade: false,
                     };
 
                     self.library.tracked_shows.push(series);
@@ -2032,7 +3512,8 @@ impl App {
 
     fn check_for_updates(&self) {
         let library = self.library.clone();
-        let client = self.nyaa_client.clone();
+        let indexers = Arc::clone(&self.indexers);
+        let scoring = self.config.scoring.clone();
         let tx = self.msg_tx.clone();
 
         let existing_torrents: Vec<tracking::ExistingTorrent> = self
@@ -2045,13 +3526,182 @@ impl App {
             .collect();
 
         tokio::spawn(async move {
-            let updates = tracking::check_for_updates(&library, &client, &existing_torrents).await;
+            let updates =
+                tracking::check_for_updates(&library, &indexers, &existing_torrents, &scoring)
+                    .await;
             if !updates.is_empty() {
                 let _ = tx.send(AppMessage::UpdatesFound(updates));
             }
         });
     }
 
+    fn schedule_update_check(&mut self) {
+        let now = chrono::Utc::now().timestamp();
+        self.next_update_check = schedule::next_update_check(&self.library, now);
+        debug!(
+            in_minutes = (self.next_update_check - now) / 60,
+            "Next release check scheduled"
+        );
+    }
+
+    /// Re-fetch details of airing shows and tracked series in the
+    /// background, so next-episode times don't go stale
+    fn refresh_airing_metadata(&self) {
+        let Some(provider) = self.metadata_provider.clone() else {
+            return;
+        };
+        let kind = self.config.metadata.provider;
+        let airing = |m: &crate::metadata::AnimeMetadata| {
+            matches!(m.status.as_str(), "currently_airing" | "not_yet_aired")
+        };
+
+        let mut targets: Vec<(u64, BindTarget)> = Vec::new();
+        for series in &self.library.tracked_shows {
+            let stale = series.cached_metadata.as_ref().is_none_or(airing);
+            if let Some(id) = series.metadata_id.filter(|_| stale) {
+                targets.push((id, BindTarget::Series(series.id.clone())));
+            }
+        }
+        for show in &self.library.shows {
+            let metadata = show.metadata.as_ref();
+            if let Some(m) = metadata.filter(|m| m.provider == kind && airing(m)) {
+                targets.push((m.id, BindTarget::Show(show.id.clone())));
+            }
+        }
+        if targets.is_empty() {
+            return;
+        }
+
+        let tx = self.msg_tx.clone();
+        tokio::spawn(async move {
+            let mut fetched: HashMap<u64, crate::metadata::AnimeMetadata> = HashMap::new();
+            for (id, target) in targets {
+                if !fetched.contains_key(&id) {
+                    if !fetched.is_empty() {
+                        tokio::time::sleep(kind.min_request_interval()).await;
+                    }
+                    match provider.get_details(id).await {
+                        Ok(metadata) => {
+                            fetched.insert(id, metadata);
+                        }
+                        Err(e) => {
+                            warn!(id, error = %e, "Stopped refreshing airing times");
+                            break;
+                        }
+                    }
+                }
+                if tx
+                    .send(AppMessage::AiringInfo(target, fetched[&id].clone()))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+    }
+
+    fn handle_calendar_input(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('c') => {
+                self.view = View::Library;
+            }
+            KeyCode::Char('j') | KeyCode::Down => {
+                let next = self.calendar_state.selected().map_or(0, |i| i + 1);
+                self.calendar_state.select(Some(next));
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                let next = self
+                    .calendar_state
+                    .selected()
+                    .map_or(0, |i| i.saturating_sub(1));
+                self.calendar_state.select(Some(next));
+            }
+            _ => {}
+        }
+    }
+
+    /// This week's expected episodes, grouped by (local) day
+    fn render_calendar(&mut self, frame: &mut Frame, area: ratatui::layout::Rect) {
+        use chrono::{DateTime, Local, TimeZone};
+        use ratatui::style::{Modifier, Style};
+        use ratatui::text::{Line, Span};
+        use ratatui::widgets::{Block, Borders, List, ListItem};
+
+        let now = chrono::Utc::now().timestamp();
+        let today = Local::now().date_naive().and_hms_opt(0, 0, 0).unwrap();
+        let from = Local
+            .from_local_datetime(&today)
+            .earliest()
+            .map_or(now, |t| t.timestamp());
+        let episodes = schedule::schedule(&self.library, from, from + 7 * 24 * 60 * 60);
+
+        let mut items: Vec<ListItem> = Vec::new();
+        let mut current_day = None;
+        for ep in &episodes {
+            let Some(local) = DateTime::from_timestamp(ep.airs_at, 0) else {
+                continue;
+            };
+            let local = local.with_timezone(&Local);
+            if current_day != Some(local.date_naive()) {
+                current_day = Some(local.date_naive());
+                items.push(ListItem::new(Span::styled(
+                    local.format("%A %d %b").to_string(),
+                    Style::default()
+                        .fg(self.accent)
+                        .add_modifier(Modifier::BOLD),
+                )));
+            }
+
+            let aired = ep.airs_at <= now;
+            let style = if aired {
+                Style::default().fg(Color::DarkGray)
+            } else {
+                Style::default()
+            };
+            let mut spans = vec![
+                Span::styled(format!("  {}  ", local.format("%H:%M")), style),
+                Span::styled(ep.title.clone(), style),
+            ];
+            if let Some(number) = ep.episode {
+                spans.push(Span::styled(format!(" - Ep {}", number), style));
+            }
+            if ep.tracked {
+                spans.push(Span::styled(
+                    " [tracked]",
+                    Style::default().fg(Color::Green),
+                ));
+            }
+            if aired {
+                spans.push(Span::styled(
+                    " (aired)",
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            items.push(ListItem::new(Line::from(spans)));
+        }
+        if items.is_empty() {
+            items.push(ListItem::new(Span::styled(
+                "Nothing expected this week. Airing times come from show metadata (m to match).",
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+
+        let last = items.len() - 1;
+        if self.calendar_state.selected().is_some_and(|i| i > last) {
+            self.calendar_state.select(Some(last));
+        }
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(" This Week ")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(self.accent)),
+            )
+            .highlight_symbol("> ");
+
+        frame.render_stateful_widget(list, area, &mut self.calendar_state);
+    }
+
     fn handle_move_dialog_input(&mut self, key: KeyCode) -> Result<()> {
         match self.move_dialog.step {
             MoveDialogStep::SelectMediaDir => match key {
@@ -2530,11 +4180,21 @@ impl App {
             std::fs::copy(&real_source_path, &dest_path)?;
             std::fs::remove_file(&real_source_path)?;
         }
-        if self.config.general.compress_episodes {
+        let imported = if self.config.general.compress_episodes {
             info!(path = %dest_path.display(), "Compressing episode");
-            compression::compress_file(&dest_path, self.config.general.compression_level)?;
-        }
+            compression::compress_file(&dest_path, self.config.general.compression_level)?
+        } else {
+            dest_path
+        };
 
+        let torrent = self
+            .torrents
+            .get(self.move_dialog.torrent_idx)
+            .map(|t| (t.hash.clone(), t.name.clone()));
+        if let Some((hash, name)) = &torrent {
+            self.remove_replaced_episode(hash, name, &imported);
+            self.library.remove_binding(hash, name);
+        }
         if let Some(client) = self.torrent_client.clone() {
             if let Some(torrent) = self.torrents.get(self.move_dialog.torrent_idx) {
                 let hash = torrent.hash.clone();
@@ -2550,12 +4210,75 @@ impl App {
         }
 
         self.refresh_library()?;
+        if let Some((_, name)) = &torrent {
+            self.record_release(&imported, name);
+        }
 
         self.view = View::Downloads;
 
         Ok(())
     }
 
+    /// Deletes the old file of the episode an upgrade download replaces,
+    /// now that the new one is in place. Watch state follows the episode
+    /// number, so the library refresh carries it over.
+    fn remove_replaced_episode(&self, hash: &str, name: &str, imported: &Path) {
+        let Some(binding) = self.library.binding_for(hash, name) else {
+            return;
+        };
+        let (BindTarget::Show(show_id), Some((season, number))) =
+            (&binding.target, binding.replaces)
+        else {
+            return;
+        };
+        let Some(show) = self.library.get_show(show_id) else {
+            return;
+        };
+        if !imported.starts_with(&show.path) {
+            warn!(
+                imported = %imported.display(),
+                "Upgrade imported outside the show's folder, keeping the old file"
+            );
+            return;
+        }
+        let Some(old_path) = show.replaced_file(season, number, imported) else {
+            return;
+        };
+        match std::fs::remove_file(&old_path) {
+            Ok(()) => info!(path = %old_path.display(), "Removed upgraded episode"),
+            Err(e) => {
+                warn!(path = %old_path.display(), error = %e, "Failed to remove upgraded episode")
+            }
+        }
+    }
+
+    /// Remember which release an imported file came from, for later upgrades
+    fn record_release(&mut self, imported: &Path, release: &str) {
+        // Season folders and other subfolders count as part of the show
+        let episode = self
+            .library
+            .shows
+            .iter_mut()
+            .filter(|s| imported.starts_with(&s.path))
+            .flat_map(|s| {
+                let path = &s.path;
+                s.episodes
+                    .iter_mut()
+                    .chain(
+                        s.seasons
+                            .iter_mut()
+                            .flat_map(|season| season.episodes.iter_mut()),
+                    )
+                    .map(move |e| (path, e))
+            })
+            .find(|(path, e)| e.full_path(path) == imported)
+            .map(|(_, e)| e);
+        if let Some(episode) = episode {
+            episode.release = Some(release.to_string());
+            self.dirty = true;
+        }
+    }
+
     fn execute_batch_move(&mut self) -> Result<()> {
         let Some(media_dir) = &self.move_dialog.selected_media_dir else {
             return Ok(());
@@ -2590,6 +4313,9 @@ impl App {
         if self.config.general.compress_episodes {
             self.compress_directory_videos(&dest_dir)?;
         }
+        if let Some(torrent) = self.torrents.get(self.move_dialog.torrent_idx) {
+            self.library.remove_binding(&torrent.hash, &torrent.name);
+        }
         if let Some(client) = self.torrent_client.clone() {
             if let Some(torrent) = self.torrents.get(self.move_dialog.torrent_idx) {
                 let hash = torrent.hash.clone();
@@ -2991,6 +4717,9 @@ impl App {
             Row::new(vec!["", "V", "View Archives"]),
             Row::new(vec!["", "x", "Delete Show"]),
             Row::new(vec!["", "r", "Refresh"]),
+            Row::new(vec!["", "m", "Match Metadata"]),
+            Row::new(vec!["", "M", "Review Metadata Matches"]),
+            Row::new(vec!["", "c", "Airing Calendar"]),
             Row::new(vec!["Episodes", "Enter", "Play"]),
             Row::new(vec!["", "Space", "Toggle Watched"]),
             Row::new(vec!["", "x", "Delete Episode"]),
@@ -2999,6 +4728,8 @@ impl App {
             Row::new(vec!["Downloads", "p", "Pause/Resume"]),
             Row::new(vec!["", "x", "Remove"]),
             Row::new(vec!["", "m", "Move to Library"]),
+            Row::new(vec!["", "i", "Torrent Details"]),
+            Row::new(vec!["", "a", "Add Magnet/.torrent"]),
         ];
 
         let table = Table::new(
@@ -3047,53 +4778,533 @@ impl App {
                     self.tracking_list_state.select(Some(next));
                 }
             }
-            KeyCode::Char('k') | KeyCode::Up => {
-                let next = self
-                    .tracking_list_state
+            KeyCode::Char('k') | KeyCode::Up => {
+                let next = self
+                    .tracking_list_state
+                    .selected()
+                    .map(|i| i.saturating_sub(1))
+                    .unwrap_or(0);
+                self.tracking_list_state.select(Some(next));
+            }
+            KeyCode::Char('x') | KeyCode::Char('d') => {
+                if let Some(idx) = self.tracking_list_state.selected() {
+                    if idx < self.library.tracked_shows.len() {
+                        self.library.tracked_shows.remove(idx);
+                        self.dirty = true;
+                        self.library.save()?;
+                        self.dirty = false;
+                        let len = self.library.tracked_shows.len();
+                        if len == 0 {
+                            self.tracking_list_state.select(None);
+                        } else if idx >= len {
+                            self.tracking_list_state.select(Some(len - 1));
+                        }
+                    }
+                }
+            }
+            KeyCode::Char('u') => {
+                if let Some(series) = self
+                    .tracking_list_state
+                    .selected()
+                    .and_then(|idx| self.library.tracked_shows.get_mut(idx))
+                {
+                    series.upgrade = !series.upgrade;
+                    let upgrade = series.upgrade;
+                    self.library.save()?;
+                    if upgrade {
+                        self.check_for_updates();
+                    }
+                }
+            }
+            KeyCode::Char('w') => {
+                self.view = View::Watchlist;
+                if !self.library.watchlist.is_empty() {
+                    self.watchlist_state.select(Some(0));
+                }
+            }
+            KeyCode::Char('S') => {
+                let metadata = &self.config.metadata;
+                match metadata.provider {
+                    ProviderKind::Mal if metadata.mal_client_id.is_empty() => {
+                        self.mal_sync.step = MalSyncStep::Error(
+                            "No MAL client ID configured. Add mal_client_id in config.toml"
+                                .to_string(),
+                        );
+                    }
+                    ProviderKind::Mal => {
+                        let (verifier, challenge) =
+                            crate::metadata::mal::MalClient::generate_pkce_pair();
+                        let client =
+                            crate::metadata::mal::MalClient::new(metadata.mal_client_id.clone());
+                        let state = redirect::generate_state();
+                        let redirect_uri = metadata
+                            .mal_redirect_port
+                            .and_then(|port| self.start_redirect_listener(port, &state));
+                        self.mal_sync.auth_url =
+                            client.build_auth_url(&challenge, &state, redirect_uri.as_deref());
+                        self.mal_sync.code_verifier = verifier;
+                        self.mal_sync.oauth_state = state;
+                        self.mal_sync.redirect_uri = redirect_uri;
+                        self.mal_sync.code_input.clear();
+                        self.mal_sync.step = MalSyncStep::ShowAuthUrl;
+                    }
+                    ProviderKind::Anilist if metadata.anilist_client_id.is_empty() => {
+                        self.mal_sync.step = MalSyncStep::Error(
+                            "No AniList client ID configured. Add anilist_client_id in config.toml"
+                                .to_string(),
+                        );
+                    }
+                    ProviderKind::Anilist => {
+                        let client = crate::metadata::anilist::AnilistClient::new(
+                            metadata.anilist_client_id.clone(),
+                        );
+                        self.mal_sync.auth_url = client.build_auth_url();
+                        self.mal_sync.redirect_uri = None;
+                        self.mal_sync.code_input.clear();
+                        self.mal_sync.step = MalSyncStep::ShowAuthUrl;
+                    }
+                    ProviderKind::Kitsu | ProviderKind::Offline => {
+                        self.mal_sync.step = MalSyncStep::Error(format!(
+                            "The {} provider is metadata only. Switch to mal or anilist for list sync",
+                            metadata.provider.name()
+                        ));
+                    }
+                }
+                self.view = View::MalSyncDialog;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_watchlist_input(&mut self, key: KeyCode) -> Result<()> {
+        let len = self.library.watchlist.len();
+        match key {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('w') => {
+                self.view = View::TrackingList;
+            }
+            KeyCode::Char('j') | KeyCode::Down => {
+                if len > 0 {
+                    let next = self
+                        .watchlist_state
+                        .selected()
+                        .map(|i| (i + 1).min(len - 1))
+                        .unwrap_or(0);
+                    self.watchlist_state.select(Some(next));
+                }
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                let next = self
+                    .watchlist_state
+                    .selected()
+                    .map(|i| i.saturating_sub(1))
+                    .unwrap_or(0);
+                self.watchlist_state.select(Some(next));
+            }
+            // Enter starts tracking it, x just drops it; either way it
+            // leaves the watchlist
+            KeyCode::Enter | KeyCode::Char('x') | KeyCode::Char('d') => {
+                let Some(idx) = self.watchlist_state.selected().filter(|&i| i < len) else {
+                    return Ok(());
+                };
+                let entry = self.library.watchlist.remove(idx);
+                if key == KeyCode::Enter {
+                    self.library.tracked_shows.push(mal_sync::new_tracked(
+                        entry.provider,
+                        entry.anime_id,
+                        &entry.title,
+                        entry.num_watched,
+                    ));
+                    self.check_for_updates();
+                }
+                self.library.save()?;
+                let len = self.library.watchlist.len();
+                if len == 0 {
+                    self.watchlist_state.select(None);
+                } else if idx >= len {
+                    self.watchlist_state.select(Some(len - 1));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_match_review_input(&mut self, key: KeyCode) -> Result<()> {
+        let len = self.match_review.front().map_or(0, |r| r.candidates.len());
+        match key {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.view = View::Library;
+            }
+            KeyCode::Char('j') | KeyCode::Down if len > 0 => {
+                let next = self
+                    .match_review_state
+                    .selected()
+                    .map(|i| (i + 1).min(len - 1))
+                    .unwrap_or(0);
+                self.match_review_state.select(Some(next));
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                let next = self
+                    .match_review_state
+                    .selected()
+                    .map(|i| i.saturating_sub(1))
+                    .unwrap_or(0);
+                self.match_review_state.select(Some(next));
+            }
+            KeyCode::Enter => {
+                let Some(idx) = self.match_review_state.selected().filter(|&i| i < len) else {
+                    return Ok(());
+                };
+                if let Some(review) = self.match_review.pop_front() {
+                    let metadata = review.candidates.into_iter().nth(idx).unwrap().metadata;
+                    self.pick_match(&review.show_id, metadata)?;
+                }
+                self.next_match_review();
+            }
+            // Decide later; it goes to the back of the queue
+            KeyCode::Char('s') => {
+                if let Some(review) = self.match_review.pop_front() {
+                    self.match_review.push_back(review);
+                }
+                self.next_match_review();
+            }
+            // None of these, stop asking about it
+            KeyCode::Char('n') | KeyCode::Char('x') => {
+                if let Some(review) = self.match_review.pop_front() {
+                    self.library.dismissed_matches.push(review.show_id);
+                    self.library.save()?;
+                }
+                self.next_match_review();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn next_match_review(&mut self) {
+        match self.match_review.front() {
+            Some(review) => {
+                self.prefetch_covers(&review.candidates);
+                self.match_review_state.select(Some(0));
+            }
+            None => {
+                self.match_review_state.select(None);
+                self.view = View::Library;
+            }
+        }
+    }
+
+    fn open_match_dialog(&mut self, idx: usize) {
+        let Some(show) = self.library.shows.get(idx) else {
+            return;
+        };
+        let query = ShowQuery::from_show(show).query;
+        self.match_dialog = MatchDialogState {
+            show_id: show.id.clone(),
+            show_title: show.title.clone(),
+            input: query,
+            ..MatchDialogState::default()
+        };
+        self.view = View::MatchDialog;
+        self.run_match_search();
+    }
+
+    /// Search the provider for the dialog's input, or look it up directly
+    /// when it's an ID
+    fn run_match_search(&mut self) {
+        let dialog = &mut self.match_dialog;
+        dialog.searched = dialog.input.trim().to_string();
+        dialog.candidates.clear();
+        dialog.list_state.select(None);
+        dialog.error = None;
+        if dialog.searched.is_empty() {
+            return;
+        }
+        let Some(provider) = self.metadata_provider.clone() else {
+            dialog.error = Some("No metadata provider configured".to_string());
+            return;
+        };
+
+        let active = self.config.metadata.provider;
+        let lookup_id = match matcher::parse_id_input(&dialog.searched) {
+            None => None,
+            Some((None, id)) => Some(id),
+            Some((Some(from), id)) if from == active => Some(id),
+            // The offline database has every provider's IDs, but the same
+            // number can be different anime on different sites
+            Some((Some(from), id)) if active == ProviderKind::Offline => {
+                let found = self.offline_db.as_ref().and_then(|db| db.details(from, id));
+                let _ = self.msg_tx.send(AppMessage::MatchCandidates(
+                    dialog.show_id.clone(),
+                    found.map(|m| vec![m]).ok_or_else(|| {
+                        format!("No offline entry for {} ID {}", from.short_name(), id)
+                    }),
+                ));
+                dialog.loading = true;
+                return;
+            }
+            Some((Some(from), id)) => {
+                let mapped = self
+                    .offline_db
+                    .as_ref()
+                    .and_then(|db| db.map_id(from, id, active));
+                if mapped.is_none() {
+                    dialog.error = Some(match self.offline_db {
+                        Some(_) => format!(
+                            "No {} entry found for {} ID {}",
+                            active.short_name(),
+                            from.short_name(),
+                            id
+                        ),
+                        None => format!(
+                            "Mapping {} IDs to {} needs the offline database",
+                            from.short_name(),
+                            active.short_name()
+                        ),
+                    });
+                    return;
+                }
+                mapped
+            }
+        };
+
+        dialog.loading = true;
+        let show_id = dialog.show_id.clone();
+        let query = dialog.searched.clone();
+        let tx = self.msg_tx.clone();
+        tokio::spawn(async move {
+            let result = match lookup_id {
+                Some(id) => provider.get_details(id).await.map(|m| vec![m]),
+                None => provider.search(&query).await,
+            };
+            let _ = tx.send(AppMessage::MatchCandidates(
+                show_id,
+                result.map_err(|e| e.to_string()),
+            ));
+        });
+    }
+
+    fn handle_match_dialog_input(&mut self, key: KeyCode) -> Result<()> {
+        let dialog = &mut self.match_dialog;
+        let len = dialog.candidates.len();
+        match key {
+            KeyCode::Esc => {
+                self.view = View::Library;
+            }
+            KeyCode::Down | KeyCode::Tab if len > 0 => {
+                let next = dialog
+                    .list_state
+                    .selected()
+                    .map(|i| (i + 1).min(len - 1))
+                    .unwrap_or(0);
+                dialog.list_state.select(Some(next));
+            }
+            KeyCode::Up => {
+                let next = dialog
+                    .list_state
                     .selected()
                     .map(|i| i.saturating_sub(1))
                     .unwrap_or(0);
-                self.tracking_list_state.select(Some(next));
+                dialog.list_state.select(Some(next));
             }
-            KeyCode::Char('x') | KeyCode::Char('d') => {
-                if let Some(idx) = self.tracking_list_state.selected() {
-                    if idx < self.library.tracked_shows.len() {
-                        self.library.tracked_shows.remove(idx);
-                        self.dirty = true;
-                        self.library.save()?;
-                        self.dirty = false;
-                        let len = self.library.tracked_shows.len();
-                        if len == 0 {
-                            self.tracking_list_state.select(None);
-                        } else if idx >= len {
-                            self.tracking_list_state.select(Some(len - 1));
-                        }
-                    }
-                }
+            KeyCode::Backspace => {
+                dialog.input.pop();
             }
-            KeyCode::Char('S') => {
-                if self.config.metadata.mal_client_id.is_empty() {
-                    self.mal_sync.step = MalSyncStep::Error(
-                        "No MAL client ID configured. Add mal_client_id in config.toml".to_string(),
-                    );
-                } else {
-                    let (verifier, challenge) =
-                        crate::metadata::mal::MalClient::generate_pkce_pair();
-                    let client = crate::metadata::mal::MalClient::new(
-                        self.config.metadata.mal_client_id.clone(),
-                    );
-                    self.mal_sync.auth_url = client.build_auth_url(&challenge);
-                    self.mal_sync.code_verifier = verifier;
-                    self.mal_sync.code_input.clear();
-                    self.mal_sync.step = MalSyncStep::ShowAuthUrl;
-                }
-                self.view = View::MalSyncDialog;
+            KeyCode::Char(c) => {
+                dialog.input.push(c);
+            }
+            KeyCode::Enter if dialog.loading => {}
+            KeyCode::Enter if dialog.input.trim() != dialog.searched => {
+                self.run_match_search();
+            }
+            KeyCode::Enter => {
+                let Some(idx) = dialog.list_state.selected().filter(|&i| i < len) else {
+                    return Ok(());
+                };
+                let metadata = dialog.candidates[idx].metadata.clone();
+                let show_id = dialog.show_id.clone();
+                self.pick_match(&show_id, metadata)?;
+                self.view = View::Library;
             }
             _ => {}
         }
         Ok(())
     }
 
+    /// Apply metadata the user chose and lock it in
+    fn pick_match(
+        &mut self,
+        show_id: &str,
+        metadata: crate::metadata::AnimeMetadata,
+    ) -> Result<()> {
+        let cover_url = metadata.cover_url.clone();
+        info!(
+            show = show_id,
+            id = metadata.id,
+            "Matched by hand to {}",
+            metadata.title
+        );
+        if !self.library.correct_match(show_id, metadata) {
+            return Ok(());
+        }
+        self.match_review.retain(|r| r.show_id != show_id);
+        self.library.save()?;
+        if let Some(url) = cover_url {
+            self.download_cover(show_id.to_string(), url);
+        }
+        Ok(())
+    }
+
+    fn download_cover(&self, show_id: String, url: String) {
+        let cache = self.image_cache.clone();
+        let tx = self.msg_tx.clone();
+        tokio::spawn(async move {
+            if let Err(e) = cache.download(&url).await {
+                tracing::error!("Failed to download cover for {}: {}", show_id, e);
+            } else {
+                let _ = tx.send(AppMessage::CoverUpdated(show_id));
+            }
+        });
+    }
+
+    /// Fetch candidate covers so they show up as the user moves through them
+    fn prefetch_covers(&self, candidates: &[Candidate]) {
+        let urls: Vec<String> = candidates
+            .iter()
+            .filter_map(|c| c.metadata.cover_url.clone())
+            .collect();
+        let cache = self.image_cache.clone();
+        tokio::spawn(async move {
+            for url in urls {
+                if let Err(e) = cache.download(&url).await {
+                    debug!(url = %url, error = %e, "Failed to fetch candidate cover");
+                }
+            }
+        });
+    }
+
+    fn render_match_dialog(&mut self, frame: &mut Frame) {
+        use ratatui::layout::Rect;
+        use ratatui::style::{Color, Style};
+        use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+        let area = frame.area();
+        let popup_width = (area.width as f32 * 0.8) as u16;
+        let popup_height = ((area.height as f32 * 0.8) as u16).max(12);
+        let popup_area = Rect::new(
+            (area.width - popup_width) / 2,
+            (area.height - popup_height) / 2,
+            popup_width,
+            popup_height,
+        );
+        frame.render_widget(Clear, popup_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ])
+            .split(popup_area);
+
+        let dialog = &mut self.match_dialog;
+        let input = Paragraph::new(format!("{}_", dialog.input)).block(
+            Block::default()
+                .title(" Search or MAL/AniList ID ")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(self.accent)),
+        );
+        frame.render_widget(input, chunks[0]);
+
+        render_match_candidates(
+            frame,
+            chunks[1],
+            &format!("Match {}", dialog.show_title),
+            &dialog.candidates,
+            CandidateSelection {
+                list_state: &mut dialog.list_state,
+                image_cache: &self.image_cache,
+                picker: &mut self.picker,
+            },
+            self.accent,
+        );
+
+        let status = if dialog.loading {
+            Paragraph::new("Searching...").style(Style::default().fg(Color::DarkGray))
+        } else if let Some(error) = &dialog.error {
+            Paragraph::new(error.as_str()).style(Style::default().fg(Color::Red))
+        } else {
+            Paragraph::new("")
+        };
+        frame.render_widget(Clear, chunks[2]);
+        frame.render_widget(status, chunks[2]);
+    }
+
+    fn render_match_review(&mut self, frame: &mut Frame, area: ratatui::layout::Rect) {
+        let Some(review) = self.match_review.front() else {
+            return;
+        };
+        let title = format!(
+            "Match for {} ({} left)",
+            review.title,
+            self.match_review.len()
+        );
+        render_match_candidates(
+            frame,
+            area,
+            &title,
+            &review.candidates,
+            CandidateSelection {
+                list_state: &mut self.match_review_state,
+                image_cache: &self.image_cache,
+                picker: &mut self.picker,
+            },
+            self.accent,
+        );
+    }
+
+    fn render_watchlist(&mut self, frame: &mut Frame, area: ratatui::layout::Rect) {
+        use ratatui::style::{Modifier, Style};
+        use ratatui::widgets::{Block, Borders, List, ListItem};
+
+        let items: Vec<ListItem> = self
+            .library
+            .watchlist
+            .iter()
+            .map(|w| {
+                let episodes = w
+                    .num_episodes
+                    .map(|n| n.to_string())
+                    .unwrap_or_else(|| "?".to_string());
+                ListItem::new(format!(
+                    "{} [{}] {}/{}",
+                    w.title,
+                    w.status.name(),
+                    w.num_watched,
+                    episodes
+                ))
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(" Watchlist ")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(self.accent)),
+            )
+            .highlight_style(
+                Style::default()
+                    .fg(self.accent)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("> ");
+
+        frame.render_stateful_widget(list, area, &mut self.watchlist_state);
+    }
+
     fn render_tracking_list(&mut self, frame: &mut Frame, area: ratatui::layout::Rect) {
         use ratatui::style::{Color, Modifier, Style};
         use ratatui::widgets::{Block, Borders, List, ListItem};
@@ -3103,7 +5314,10 @@ impl App {
             .tracked_shows
             .iter()
             .map(|s| {
-                let title = format!("{} S{:02} (Query: {})", s.title, s.season, s.query);
+                let mut title = format!("{} S{:02} (Query: {})", s.title, s.season, s.query);
+                if s.upgrade {
+                    title.push_str(" [upgrades]");
+                }
                 ListItem::new(title)
             })
             .collect();
@@ -3131,6 +5345,7 @@ impl App {
         match &self.mal_sync.step {
             MalSyncStep::ShowAuthUrl => match key.code {
                 KeyCode::Esc => {
+                    self.mal_sync.stop_listener();
                     self.mal_sync.step = MalSyncStep::Idle;
                     self.view = View::TrackingList;
                 }
@@ -3139,62 +5354,39 @@ impl App {
                 }
                 _ => {}
             },
-            MalSyncStep::WaitingForCode => {
-                match key.code {
-                    KeyCode::Esc => {
-                        self.mal_sync.step = MalSyncStep::Idle;
-                        self.view = View::TrackingList;
-                    }
-                    KeyCode::Char(c) => {
-                        self.mal_sync.code_input.push(c);
-                    }
-                    KeyCode::Backspace => {
-                        self.mal_sync.code_input.pop();
-                    }
-                    KeyCode::Enter => {
-                        // Exchange code for tokens and sync
-                        self.mal_sync.step = MalSyncStep::Syncing;
-                        let client_id = self.config.metadata.mal_client_id.clone();
-                        let code = self.mal_sync.code_input.clone();
-                        let verifier = self.mal_sync.code_verifier.clone();
-
-                        let client = crate::metadata::mal::MalClient::new(client_id);
-                        match client.exchange_code(&code, &verifier).await {
-                            Ok(token_resp) => {
-                                // TODO: Save tokens to config
-                                let mut mal_client = crate::metadata::mal::MalClient::new(
-                                    self.config.metadata.mal_client_id.clone(),
-                                );
-                                mal_client.set_access_token(token_resp.access_token);
-
-                                // Fetch watching list and import
-                                match crate::metadata::mal_sync::sync_from_mal(
-                                    &mal_client,
-                                    &self.library.tracked_shows,
-                                )
-                                .await
-                                {
-                                    Ok(new_shows) => {
-                                        let count = new_shows.len();
-                                        self.library.tracked_shows.extend(new_shows);
-                                        self.library.save()?;
-                                        self.mal_sync.step = MalSyncStep::Complete(count);
-                                        // Trigger episode check for newly added shows
-                                        self.check_for_updates();
-                                    }
-                                    Err(e) => {
-                                        self.mal_sync.step = MalSyncStep::Error(e.to_string());
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                self.mal_sync.step = MalSyncStep::Error(e.to_string());
-                            }
-                        }
-                    }
-                    _ => {}
+            MalSyncStep::WaitingForCode => match key.code {
+                KeyCode::Esc => {
+                    self.mal_sync.stop_listener();
+                    self.mal_sync.step = MalSyncStep::Idle;
+                    self.view = View::TrackingList;
                 }
-            }
+                KeyCode::Char(c) => {
+                    self.mal_sync.code_input.push(c);
+                }
+                KeyCode::Backspace => {
+                    self.mal_sync.code_input.pop();
+                }
+                KeyCode::Enter => {
+                    self.mal_sync.step = MalSyncStep::Syncing;
+                    self.finish_list_sync().await?;
+                }
+                _ => {}
+            },
+            MalSyncStep::Preview => match key.code {
+                KeyCode::Enter => self.apply_import_plan()?,
+                KeyCode::Esc => {
+                    self.mal_sync.import_plan = None;
+                    self.mal_sync.step = MalSyncStep::Idle;
+                    self.view = View::TrackingList;
+                }
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.mal_sync.preview_scroll = self.mal_sync.preview_scroll.saturating_add(1);
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.mal_sync.preview_scroll = self.mal_sync.preview_scroll.saturating_sub(1);
+                }
+                _ => {}
+            },
             MalSyncStep::Complete(_) | MalSyncStep::Error(_) => {
                 if key.code == KeyCode::Enter || key.code == KeyCode::Esc {
                     self.mal_sync.step = MalSyncStep::Idle;
@@ -3211,6 +5403,204 @@ impl App {
         Ok(())
     }
 
+    /// Log in with the code in the dialog, then import the watching list
+    async fn finish_list_sync(&mut self) -> Result<()> {
+        // A pasted code beats the listener; stop it so it can't log in twice
+        self.mal_sync.stop_listener();
+        match self.list_login().await {
+            Ok(kind) => {
+                self.retry_mal_queue();
+
+                // Fetch the list and preview what importing it would change
+                let metadata = &self.config.metadata;
+                let result = self
+                    .tokens
+                    .with_token(kind, |token| async move {
+                        let client = list_client_for(metadata, kind, token);
+                        mal_sync::fetch_list(&*client, &metadata.import).await
+                    })
+                    .await;
+                match result {
+                    Ok(entries) => {
+                        let plan =
+                            mal_sync::plan_import(entries, &self.library, &metadata.import, kind);
+                        if plan.is_empty() {
+                            self.mal_sync.step = MalSyncStep::Complete(0);
+                        } else {
+                            self.mal_sync.import_plan = Some(plan);
+                            self.mal_sync.preview_scroll = 0;
+                            self.mal_sync.step = MalSyncStep::Preview;
+                        }
+                    }
+                    Err(e) => {
+                        self.mal_sync.step = MalSyncStep::Error(e.to_string());
+                    }
+                }
+            }
+            Err(e) => {
+                self.mal_sync.step = MalSyncStep::Error(e.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Commit the previewed import
+    fn apply_import_plan(&mut self) -> Result<()> {
+        let Some(plan) = self.mal_sync.import_plan.take() else {
+            return Ok(());
+        };
+        let new_tracked = !plan.tracked.is_empty();
+        let count = plan.apply(&mut self.library);
+        self.library.save()?;
+        self.mal_sync.step = MalSyncStep::Complete(count);
+        if new_tracked {
+            // Trigger episode check for newly added shows
+            self.check_for_updates();
+        }
+        Ok(())
+    }
+
+    /// Catch the MAL redirect on 127.0.0.1. Returns the redirect URI to
+    /// send, or `None` to fall back to pasting the code.
+    fn start_redirect_listener(&mut self, port: u16, state: &str) -> Option<String> {
+        self.mal_sync.stop_listener();
+        let listener = match redirect::RedirectListener::bind(port) {
+            Ok(listener) => listener,
+            Err(e) => {
+                warn!(port, error = %e, "Couldn't listen for the MAL redirect, paste the code instead");
+                return None;
+            }
+        };
+        let uri = listener.redirect_uri();
+        let tx = self.msg_tx.clone();
+        let state = state.to_string();
+        let task = tokio::spawn(async move {
+            let result = listener
+                .wait_for_code(&state)
+                .await
+                .map_err(|e| e.to_string());
+            let _ = tx.send(AppMessage::MalAuthRedirect(result));
+        });
+        self.mal_sync.redirect_listener = Some(task.abort_handle());
+        Some(uri)
+    }
+
+    /// Turn what the user pasted into a stored token for the active provider
+    async fn list_login(&mut self) -> Result<ProviderKind> {
+        let input = self.mal_sync.code_input.trim().to_string();
+        let kind = self.config.metadata.provider;
+        let token = match kind {
+            ProviderKind::Mal => {
+                // Accept the whole redirect URL as well as the bare code
+                let code =
+                    redirect::parse_redirect(&input, &self.mal_sync.oauth_state)?.unwrap_or(input);
+                let client = crate::metadata::mal::MalClient::new(
+                    self.config.metadata.mal_client_id.clone(),
+                );
+                let token_resp = client
+                    .exchange_code(
+                        &code,
+                        &self.mal_sync.code_verifier,
+                        self.mal_sync.redirect_uri.as_deref(),
+                    )
+                    .await?;
+                OAuthToken::from_response(token_resp, chrono::Utc::now().timestamp())
+            }
+            // The implicit grant pin page shows the token itself. AniList
+            // tokens last a year and can't be refreshed.
+            ProviderKind::Anilist => OAuthToken {
+                access_token: input,
+                refresh_token: None,
+                expires_at: None,
+            },
+            ProviderKind::Kitsu | ProviderKind::Offline => {
+                return Err(crate::error::Error::Metadata(
+                    "List sync isn't available for this provider".to_string(),
+                ));
+            }
+        };
+        self.tokens.store(kind, token)?;
+        Ok(kind)
+    }
+
+    fn render_score_prompt(&self, frame: &mut Frame) {
+        use ratatui::layout::Rect;
+        use ratatui::style::Style;
+        use ratatui::text::{Line, Span};
+        use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+        let Some(prompt) = &self.score_prompt else {
+            return;
+        };
+
+        let area = frame.area();
+        let popup_width = (area.width as f32 * 0.6) as u16;
+        let popup_height = 7;
+        let popup_x = (area.width - popup_width) / 2;
+        let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+        let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height.min(area.height));
+
+        frame.render_widget(Clear, popup_area);
+
+        let content = vec![
+            Line::from(format!(
+                "Finished {}! Score it on MAL (1-10):",
+                prompt.title
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                format!("{}_", prompt.input),
+                Style::default().fg(Color::Yellow),
+            )),
+        ];
+
+        let block = Block::default()
+            .title(" Rate Show ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.accent));
+
+        frame.render_widget(Paragraph::new(content).block(block), popup_area);
+    }
+
+    fn render_add_torrent_dialog(&self, frame: &mut Frame) {
+        use ratatui::layout::Rect;
+        use ratatui::style::Style;
+        use ratatui::text::{Line, Span};
+        use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+        let area = frame.area();
+        let popup_width = (area.width as f32 * 0.8) as u16;
+        let popup_height = 8;
+        let popup_x = (area.width - popup_width) / 2;
+        let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+        let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height.min(area.height));
+
+        frame.render_widget(Clear, popup_area);
+
+        let mut content = vec![
+            Line::from("Paste a magnet link or the path to a .torrent file:"),
+            Line::from(""),
+            Line::from(Span::styled(
+                format!("{}_", self.add_torrent_dialog.input),
+                Style::default().fg(Color::Yellow),
+            )),
+            Line::from(""),
+        ];
+        if let Some(err) = &self.add_torrent_dialog.error {
+            content.push(Line::from(Span::styled(
+                err.as_str(),
+                Style::default().fg(Color::Red),
+            )));
+        }
+
+        let block = Block::default()
+            .title(" Add Torrent ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.accent));
+
+        frame.render_widget(Paragraph::new(content).block(block), popup_area);
+    }
+
     fn render_mal_sync_dialog(&self, frame: &mut Frame) {
         use ratatui::layout::{Constraint, Layout, Rect};
         use ratatui::style::{Color, Style};
@@ -3219,16 +5609,22 @@ impl App {
 
         let area = frame.area();
         let popup_width = (area.width as f32 * 0.8) as u16;
-        let popup_height = 12;
+        let popup_height = match self.mal_sync.step {
+            // Room for the import diff
+            MalSyncStep::Preview => ((area.height as f32 * 0.8) as u16).max(12),
+            _ => 12,
+        };
         let popup_x = (area.width - popup_width) / 2;
         let popup_y = (area.height - popup_height) / 2;
         let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
 
         frame.render_widget(Clear, popup_area);
 
+        let provider = self.config.metadata.provider;
+        let name = provider.short_name();
         let (title, content) = match &self.mal_sync.step {
             MalSyncStep::ShowAuthUrl => (
-                "MAL Sync - Step 1",
+                format!("{} Sync - Step 1", name),
                 vec![
                     Line::from("Open this URL in your browser:"),
                     Line::from(""),
@@ -3237,7 +5633,13 @@ impl App {
                         Style::default().fg(Color::Cyan),
                     )),
                     Line::from(""),
-                    Line::from("After authorizing, you'll receive a code."),
+                    Line::from(match provider {
+                        ProviderKind::Mal if self.mal_sync.redirect_listener.is_some() => {
+                            "Waiting for the browser to come back... or press Enter to paste the code."
+                        }
+                        ProviderKind::Mal => "After authorizing, you'll receive a code.",
+                        _ => "After authorizing, you'll be shown a token.",
+                    }),
                     Line::from(""),
                     Line::from(Span::styled(
                         "[Enter] Continue  [Esc] Cancel",
@@ -3246,9 +5648,14 @@ impl App {
                 ],
             ),
             MalSyncStep::WaitingForCode => (
-                "MAL Sync - Step 2",
+                format!("{} Sync - Step 2", name),
                 vec![
-                    Line::from("Paste the authorization code:"),
+                    Line::from(match provider {
+                        ProviderKind::Mal => {
+                            "Paste the authorization code (or the whole redirect URL):"
+                        }
+                        _ => "Paste the token:",
+                    }),
                     Line::from(""),
                     Line::from(Span::styled(
                         &self.mal_sync.code_input,
@@ -3261,9 +5668,12 @@ impl App {
                     )),
                 ],
             ),
-            MalSyncStep::Syncing => ("MAL Sync", vec![Line::from("Syncing with MyAnimeList...")]),
+            MalSyncStep::Syncing => (
+                format!("{} Sync", name),
+                vec![Line::from(format!("Syncing with {}...", provider.name()))],
+            ),
             MalSyncStep::Complete(count) => (
-                "MAL Sync - Complete",
+                format!("{} Sync - Complete", name),
                 vec![
                     Line::from(format!("Successfully imported {} series!", count)),
                     Line::from(""),
@@ -3274,7 +5684,7 @@ impl App {
                 ],
             ),
             MalSyncStep::Error(msg) => (
-                "MAL Sync - Error",
+                format!("{} Sync - Error", name),
                 vec![
                     Line::from(Span::styled(msg.as_str(), Style::default().fg(Color::Red))),
                     Line::from(""),
@@ -3284,7 +5694,56 @@ impl App {
                     )),
                 ],
             ),
-            MalSyncStep::Idle => ("MAL Sync", vec![Line::from("Idle")]),
+            MalSyncStep::Preview => {
+                let mut lines = vec![Line::from("Importing your list will:"), Line::from("")];
+                if let Some(plan) = &self.mal_sync.import_plan {
+                    let diff_line = |sign: &str, text: String, color: Color| {
+                        Line::from(Span::styled(
+                            format!("{} {}", sign, text),
+                            Style::default().fg(color),
+                        ))
+                    };
+                    lines.extend(plan.tracked.iter().map(|t| {
+                        diff_line(
+                            "+",
+                            format!("track {} (from ep {})", t.title, t.min_episode),
+                            Color::Green,
+                        )
+                    }));
+                    lines.extend(plan.watchlist.iter().map(|w| {
+                        diff_line(
+                            "+",
+                            format!("watchlist {} ({})", w.title, w.status.name()),
+                            Color::Cyan,
+                        )
+                    }));
+                    lines.extend(plan.backfill.iter().map(|b| {
+                        diff_line(
+                            "~",
+                            format!(
+                                "mark {} watched through ep {} ({} episodes)",
+                                b.title, b.through, b.newly_watched
+                            ),
+                            Color::Yellow,
+                        )
+                    }));
+                    lines.push(Line::from(""));
+                    lines.push(Line::from(Span::styled(
+                        format!(
+                            "{} already up to date, {} to mark watched aren't in the library",
+                            plan.unchanged, plan.unmatched
+                        ),
+                        Style::default().fg(Color::DarkGray),
+                    )));
+                }
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    "[Enter] Import  [j/k] Scroll  [Esc] Cancel",
+                    Style::default().fg(Color::DarkGray),
+                )));
+                (format!("{} Sync - Preview", name), lines)
+            }
+            MalSyncStep::Idle => (format!("{} Sync", name), vec![Line::from("Idle")]),
         };
 
         let block = Block::default()
@@ -3294,7 +5753,8 @@ impl App {
 
         let paragraph = Paragraph::new(content)
             .block(block)
-            .wrap(Wrap { trim: false });
+            .wrap(Wrap { trim: false })
+            .scroll((self.mal_sync.preview_scroll, 0));
 
         frame.render_widget(paragraph, popup_area);
     }
@@ -3470,6 +5930,38 @@ impl App {
     }
 }
 
+fn create_token_manager(config: &Config) -> TokenManager {
+    let metadata = &config.metadata;
+    let backend = CredentialBackend::from_config(metadata.credential_store).unwrap_or_else(|e| {
+        // Never leave tokens somewhere shared like the temp dir
+        warn!("No config dir for credentials, using the keyring: {}", e);
+        CredentialBackend::Keyring
+    });
+    let mal = (!metadata.mal_client_id.is_empty())
+        .then(|| crate::metadata::mal::MalClient::new(metadata.mal_client_id.clone()));
+    let tokens = TokenManager::new(backend, mal);
+    tokens.migrate_legacy(metadata);
+    tokens
+}
+
+/// Client for the user's own list on `kind`
+fn list_client_for(
+    metadata: &crate::config::MetadataConfig,
+    kind: ProviderKind,
+    token: String,
+) -> Box<dyn ListSync + Send + Sync> {
+    match kind {
+        ProviderKind::Anilist => Box::new(
+            crate::metadata::anilist::AnilistClient::new(metadata.anilist_client_id.clone())
+                .with_access_token(token),
+        ),
+        _ => Box::new(
+            crate::metadata::mal::MalClient::new(metadata.mal_client_id.clone())
+                .with_access_token(token),
+        ),
+    }
+}
+
 fn create_torrent_client(config: &Config) -> Option<AnyTorrentClient> {
     let tc = &config.torrent;
 
@@ -3485,6 +5977,7 @@ fn create_torrent_client(config: &Config) -> Option<AnyTorrentClient> {
             tc.port,
             tc.username.as_deref(),
             tc.password.as_deref(),
+            tc.api_key.as_deref(),
         ))),
         _ => {
             error!(client = %tc.client, "Unknown torrent client");
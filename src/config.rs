@@ -1,9 +1,14 @@
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
 
 use crate::error::{Error, Result};
+use crate::indexer::IndexerKind;
+use crate::metadata::credentials::CredentialStore;
+use crate::metadata::{ListStatus, ProviderKind};
+use crate::nyaa::ScoringProfile;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -17,18 +22,149 @@ pub struct Config {
     pub torrent: TorrentConfig,
     #[serde(default)]
     pub metadata: MetadataConfig,
+    /// Where searches and tracking checks look for releases, in order of
+    /// preference
+    #[serde(default = "default_indexers")]
+    pub indexers: Vec<IndexerConfig>,
+    #[serde(default)]
+    pub scoring: ScoringConfig,
+}
+
+/// `[scoring]` is the default profile; `[scoring.profiles.<name>]` adds
+/// named ones that tracked series can pick
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScoringConfig {
+    #[serde(flatten)]
+    pub default: ScoringProfile,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ScoringProfile>,
+}
+
+impl ScoringConfig {
+    /// The named profile, or the default one if there's no such profile
+    pub fn profile(&self, name: Option<&str>) -> &ScoringProfile {
+        name.and_then(|n| self.profiles.get(n))
+            .unwrap_or(&self.default)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexerConfig {
+    pub kind: IndexerKind,
+    /// Shown in logs; defaults to the kind's name
+    #[serde(default)]
+    pub name: Option<String>,
+    /// A Nyaa mirror, or the full Torznab API endpoint
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Torznab only
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_indexers() -> Vec<IndexerConfig> {
+    vec![IndexerConfig {
+        kind: IndexerKind::Nyaa,
+        name: None,
+        url: None,
+        api_key: None,
+        enabled: true,
+    }]
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetadataConfig {
+    /// Service used for metadata lookups and list sync
+    #[serde(default)]
+    pub provider: ProviderKind,
     #[serde(default = "default_mal_client_id")]
     pub mal_client_id: String,
-    #[serde(default)]
+    /// Legacy plaintext tokens. Read once to migrate them into the
+    /// credential store, never written back.
+    #[serde(default, skip_serializing)]
     pub mal_access_token: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing)]
     pub mal_refresh_token: Option<String>,
-    #[serde(default)]
+    #[serde(default, skip_serializing)]
     pub mal_token_expires: Option<i64>,
+    /// Catch the MAL login redirect on http://127.0.0.1:<port>/callback
+    /// instead of pasting the code; must match the app's redirect URL
+    #[serde(default)]
+    pub mal_redirect_port: Option<u16>,
+    /// Where list sync tokens are kept
+    #[serde(default)]
+    pub credential_store: CredentialStore,
+    /// Only needed to log in for AniList list sync; search works without it
+    #[serde(default)]
+    pub anilist_client_id: String,
+    /// anime-offline-database JSON dump, used by the offline provider and
+    /// for mapping IDs between providers. Defaults to the data directory.
+    #[serde(default)]
+    pub offline_db: Option<PathBuf>,
+    /// Where each list status ends up when importing the user's list
+    #[serde(default)]
+    pub import: ListImportConfig,
+}
+
+/// What importing does with an entry on the user's list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportTarget {
+    /// A tracked series that searches for new episodes
+    Tracked,
+    /// Remembered in the watchlist, nothing downloaded
+    Watchlist,
+    /// Mark episodes of the matching library show as watched
+    Backfill,
+    Skip,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListImportConfig {
+    #[serde(default = "default_import_watching")]
+    pub watching: ImportTarget,
+    #[serde(default = "default_import_planned")]
+    pub plan_to_watch: ImportTarget,
+    #[serde(default = "default_import_planned")]
+    pub on_hold: ImportTarget,
+    #[serde(default = "default_import_completed")]
+    pub completed: ImportTarget,
+}
+
+impl ListImportConfig {
+    pub fn target(&self, status: ListStatus) -> ImportTarget {
+        match status {
+            ListStatus::Watching => self.watching,
+            ListStatus::PlanToWatch => self.plan_to_watch,
+            ListStatus::OnHold => self.on_hold,
+            ListStatus::Completed => self.completed,
+        }
+    }
+}
+
+impl Default for ListImportConfig {
+    fn default() -> Self {
+        Self {
+            watching: default_import_watching(),
+            plan_to_watch: default_import_planned(),
+            on_hold: default_import_planned(),
+            completed: default_import_completed(),
+        }
+    }
+}
+
+fn default_import_watching() -> ImportTarget {
+    ImportTarget::Tracked
+}
+
+fn default_import_planned() -> ImportTarget {
+    ImportTarget::Watchlist
+}
+
+fn default_import_completed() -> ImportTarget {
+    ImportTarget::Backfill
 }
 
 fn default_mal_client_id() -> String {
@@ -49,6 +185,9 @@ pub struct GeneralConfig {
     pub archive_mode: String,
     #[serde(default = "default_true")]
     pub notifications: bool,
+    /// Play next unwatched passes over episodes flagged filler or recap
+    #[serde(default)]
+    pub skip_filler: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -85,10 +224,46 @@ pub struct TorrentConfig {
     pub username: Option<String>,
     #[serde(default)]
     pub password: Option<String>,
+    /// qBittorrent Web API key, used instead of username/password
+    #[serde(default)]
+    pub api_key: Option<String>,
     #[serde(default)]
     pub managed_daemon_command: Option<String>,
     #[serde(default)]
     pub managed_daemon_args: Option<Vec<String>>,
+    #[serde(default)]
+    pub seeding: SeedingPolicy,
+    /// Folder polled for `.torrent` files to add
+    #[serde(default)]
+    pub watch_dir: Option<PathBuf>,
+}
+
+/// When a finished torrent should be removed from the client.
+/// Whichever limit is reached first wins; no limits means seed forever.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SeedingPolicy {
+    #[serde(default)]
+    pub ratio_limit: Option<f64>,
+    #[serde(default)]
+    pub seed_days: Option<u32>,
+}
+
+impl SeedingPolicy {
+    pub fn is_unlimited(&self) -> bool {
+        self.ratio_limit.is_none() && self.seed_days.is_none()
+    }
+
+    /// An unknown ratio never meets the ratio limit
+    pub fn is_met(&self, ratio: Option<f64>, seeding_secs: u64) -> bool {
+        let ratio_met = self
+            .ratio_limit
+            .zip(ratio)
+            .is_some_and(|(limit, ratio)| ratio >= limit);
+        let time_met = self
+            .seed_days
+            .is_some_and(|days| seeding_secs >= days as u64 * 24 * 60 * 60);
+        ratio_met || time_met
+    }
 }
 
 fn default_torrent_client() -> String {
@@ -131,6 +306,8 @@ impl Default for Config {
             ui: UiConfig::default(),
             torrent: TorrentConfig::default(),
             metadata: MetadataConfig::default(),
+            indexers: default_indexers(),
+            scoring: ScoringConfig::default(),
         }
     }
 }
@@ -138,10 +315,16 @@ impl Default for Config {
 impl Default for MetadataConfig {
     fn default() -> Self {
         Self {
+            provider: ProviderKind::default(),
             mal_client_id: default_mal_client_id(),
             mal_access_token: None,
             mal_refresh_token: None,
             mal_token_expires: None,
+            mal_redirect_port: None,
+            credential_store: CredentialStore::default(),
+            anilist_client_id: String::new(),
+            offline_db: None,
+            import: ListImportConfig::default(),
         }
     }
 }
@@ -160,6 +343,7 @@ impl Default for GeneralConfig {
             archive_path: default_archive_path(),
             archive_mode: default_archive_mode(),
             notifications: true,
+            skip_filler: false,
         }
     }
 }
@@ -204,8 +388,11 @@ impl Default for TorrentConfig {
             port: default_torrent_port(),
             username: None,
             password: None,
+            api_key: None,
             managed_daemon_command: None,
             managed_daemon_args: None,
+            seeding: SeedingPolicy::default(),
+            watch_dir: None,
         }
     }
 }
@@ -226,6 +413,10 @@ pub fn config_path() -> Result<PathBuf> {
     Ok(config_dir()?.join("config.toml"))
 }
 
+pub fn credentials_path() -> Result<PathBuf> {
+    Ok(config_dir()?.join("credentials.toml"))
+}
+
 pub fn library_path() -> Result<PathBuf> {
     Ok(data_dir()?.join("library.toml"))
 }
@@ -289,6 +480,35 @@ impl Config {
         }
         self.general.archive_path.clone()
     }
+
+    pub fn expanded_offline_db(&self) -> Option<PathBuf> {
+        let Some(path) = self.metadata.offline_db.as_ref() else {
+            return data_dir()
+                .ok()
+                .map(|d| d.join("anime-offline-database.json"));
+        };
+        let path_str = path.to_string_lossy();
+        if path_str.starts_with("~/") || path_str.starts_with("~\\") {
+            if let Some(home) = dirs_home() {
+                return Some(home.join(&path_str[2..]));
+            }
+        }
+        Some(path.clone())
+    }
+
+    pub fn expanded_watch_dir(&self) -> Option<PathBuf> {
+        let dir = self.torrent.watch_dir.as_ref()?;
+        let path_str = dir.to_string_lossy();
+        if path_str.starts_with("~/") || path_str.starts_with("~\\") || path_str == "~" {
+            if let Some(home) = dirs_home() {
+                if path_str == "~" {
+                    return Some(home);
+                }
+                return Some(home.join(&path_str[2..]));
+            }
+        }
+        Some(dir.clone())
+    }
 }
 
 fn dirs_home() -> Option<PathBuf> {
@@ -35,8 +35,30 @@ pub enum Error {
     #[error("Torrent client error: {0}")]
     TorrentClient(String),
 
+    #[error("qBittorrent error: {0}")]
+    QBittorrent(#[from] QBittorrentError),
+
     #[error("Metadata error: {0}")]
     Metadata(String),
+
+    /// The access token was rejected or is missing; refresh or log in again
+    #[error("Not authorized by {0}, log in again")]
+    Unauthorized(String),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum QBittorrentError {
+    #[error("login failed: {0}")]
+    LoginFailed(String),
+
+    #[error("access denied, check the username/password or API key")]
+    Forbidden,
+
+    #[error("{endpoint} returned HTTP {status}")]
+    Api { endpoint: String, status: u16 },
+
+    #[error("unexpected response: {0}")]
+    UnexpectedResponse(String),
+}
@@ -0,0 +1,171 @@
+use humansize::{BINARY, format_size};
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::error::{Error, Result};
+use crate::indexer::Indexer;
+use crate::nyaa::{NyaaCategory, NyaaFilter, NyaaResult, NyaaSort, is_batch_release};
+
+const ANIMETOSHO_FEED_BASE: &str = "https://feed.animetosho.org";
+
+/// AnimeTosho's JSON feed. It mirrors Nyaa, AniDex and TokyoTosho, mostly
+/// English-subtitled releases, but knows nothing of trusted uploaders.
+pub struct AnimeToshoClient {
+    client: reqwest::Client,
+    name: String,
+    base_url: String,
+}
+
+impl AnimeToshoClient {
+    pub fn new() -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .user_agent("miru/0.1")
+                .build()
+                .expect("Failed to create HTTP client"),
+            name: "AnimeTosho".to_string(),
+            base_url: ANIMETOSHO_FEED_BASE.to_string(),
+        }
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Point requests somewhere other than feed.animetosho.org
+    pub fn with_base_url(mut self, base: &str) -> Self {
+        self.base_url = base.trim_end_matches('/').to_string();
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl Indexer for AnimeToshoClient {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn categories(&self) -> &'static [NyaaCategory] {
+        &[NyaaCategory::AllAnime, NyaaCategory::AnimeEnglish]
+    }
+
+    /// Always newest first; the caller sorts
+    async fn search(
+        &self,
+        query: &str,
+        _category: NyaaCategory,
+        _filter: NyaaFilter,
+        _sort: NyaaSort,
+    ) -> Result<Vec<NyaaResult>> {
+        let url = format!("{}/json", self.base_url);
+        debug!(url = %url, query = %query, "Searching AnimeTosho");
+
+        let response = self.client.get(&url).query(&[("q", query)]).send().await?;
+        if !response.status().is_success() {
+            return Err(Error::NyaaSearch(format!(
+                "AnimeTosho HTTP error: {}",
+                response.status()
+            )));
+        }
+
+        let entries: Vec<ToshoEntry> = response.json().await?;
+        Ok(entries
+            .into_iter()
+            .filter_map(ToshoEntry::into_result)
+            .collect())
+    }
+}
+
+impl Default for AnimeToshoClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Deserialize)]
+struct ToshoEntry {
+    title: String,
+    /// Unix seconds
+    timestamp: i64,
+    torrent_url: Option<String>,
+    info_hash: Option<String>,
+    magnet_uri: Option<String>,
+    seeders: Option<u32>,
+    leechers: Option<u32>,
+    torrent_downloaded_count: Option<u32>,
+    /// Bytes
+    total_size: Option<u64>,
+}
+
+impl ToshoEntry {
+    /// Entries without a torrent (Usenet-only) are dropped
+    fn into_result(self) -> Option<NyaaResult> {
+        let magnet_link = self.magnet_uri.filter(|m| !m.is_empty())?;
+        let size = self
+            .total_size
+            .map(|bytes| format_size(bytes, BINARY.decimal_places(1)))
+            .unwrap_or_default();
+        let date = chrono::DateTime::from_timestamp(self.timestamp, 0)
+            .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+
+        Some(NyaaResult {
+            is_batch: is_batch_release(&self.title, &size),
+            category: "Anime".to_string(),
+            seeders: self.seeders.unwrap_or(0),
+            leechers: self.leechers.unwrap_or(0),
+            downloads: self.torrent_downloaded_count.unwrap_or(0),
+            torrent_url: self.torrent_url.unwrap_or_default(),
+            info_hash: self.info_hash.unwrap_or_default().to_lowercase(),
+            is_trusted: None,
+            is_remake: false,
+            title: self.title,
+            magnet_link,
+            size,
+            date,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{mock_server, reply};
+
+    #[tokio::test]
+    async fn test_search_parses_feed() {
+        let (port, log) = mock_server(|req| {
+            assert!(req.head.starts_with("GET /json?q=frieren+05 "));
+            reply(
+                200,
+                r#"[
+                    {"id":608526,"title":"[SubsPlease] Sousou no Frieren - 05 (1080p) [8E2F1A0C].mkv","link":"https://animetosho.org/view/608526","timestamp":1696606325,"status":"complete","nyaa_id":1729403,"torrent_url":"https://animetosho.org/storage/torrent/3a6b/frieren-05.torrent","info_hash":"3A6B1D8E2C0F4B7A9D5E1C3F8A2B6D4E0C9F7A1B","magnet_uri":"magnet:?xt=urn:btih:3a6b1d8e2c0f4b7a9d5e1c3f8a2b6d4e0c9f7a1b&tr=http%3A%2F%2Fnyaa.tracker.wf%3A7777%2Fannounce","seeders":1490,"leechers":38,"torrent_downloaded_count":21004,"total_size":1503238553,"num_files":1},
+                    {"id":608530,"title":"Sousou no Frieren 05 [NZB only]","timestamp":1696607000,"status":"complete","torrent_url":null,"info_hash":null,"magnet_uri":null,"seeders":null,"leechers":null,"total_size":1400000000}
+                ]"#,
+            )
+        })
+        .await;
+        let client = AnimeToshoClient::new().with_base_url(&format!("http://127.0.0.1:{}", port));
+
+        let results = client
+            .search(
+                "frieren 05",
+                NyaaCategory::AnimeEnglish,
+                NyaaFilter::NoFilter,
+                NyaaSort::Seeders,
+            )
+            .await
+            .unwrap();
+        assert_eq!(log.lock().unwrap().len(), 1);
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].info_hash,
+            "3a6b1d8e2c0f4b7a9d5e1c3f8a2b6d4e0c9f7a1b"
+        );
+        assert_eq!(results[0].seeders, 1490);
+        assert_eq!(results[0].size, "1.4 GiB");
+        assert_eq!(results[0].date, "2023-10-06 15:32");
+        assert!(!results[0].is_batch);
+    }
+}
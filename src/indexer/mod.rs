@@ -0,0 +1,459 @@
+//! Torrent indexers: Nyaa (and its mirrors), AnimeTosho and Torznab
+//! endpoints such as Jackett or Prowlarr. Searches go to every enabled
+//! indexer and the results are merged into one list.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::config::IndexerConfig;
+use crate::error::{Error, Result};
+use crate::nyaa::{
+    AdvancedQuery, NyaaCategory, NyaaClient, NyaaFilter, NyaaResult, NyaaSort, ScoringProfile,
+    SearchQuery, parse_size_mb, score_result,
+};
+
+pub mod animetosho;
+pub mod torznab;
+
+pub use animetosho::AnimeToshoClient;
+pub use torznab::TorznabClient;
+
+pub type SharedIndexer = Arc<dyn Indexer + Send + Sync>;
+
+/// One page of search results
+#[derive(Debug, Clone, Default)]
+pub struct SearchPage {
+    pub results: Vec<NyaaResult>,
+    /// Matches across every page, when the indexer says
+    pub total: Option<usize>,
+    /// Whether asking for the next page is worth it
+    pub more: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IndexerKind {
+    /// nyaa.si, or a mirror given by `url`
+    Nyaa,
+    AnimeTosho,
+    /// Jackett, Prowlarr or anything else speaking Torznab
+    Torznab,
+}
+
+impl IndexerKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            IndexerKind::Nyaa => "Nyaa",
+            IndexerKind::AnimeTosho => "AnimeTosho",
+            IndexerKind::Torznab => "Torznab",
+        }
+    }
+}
+
+#[async_trait::async_trait]
+pub trait Indexer {
+    fn name(&self) -> &str;
+
+    /// Categories this indexer can narrow a search to. It's left out of
+    /// searches in any other category.
+    fn categories(&self) -> &'static [NyaaCategory];
+
+    /// One query, as typed; the caller handles alternative spellings
+    async fn search(
+        &self,
+        query: &str,
+        category: NyaaCategory,
+        filter: NyaaFilter,
+        sort: NyaaSort,
+    ) -> Result<Vec<NyaaResult>>;
+
+    /// Page `page` (from 1) of `search`. Indexers that can't page return
+    /// everything on the first and nothing after it.
+    async fn search_page(
+        &self,
+        query: &str,
+        page: u32,
+        category: NyaaCategory,
+        filter: NyaaFilter,
+        sort: NyaaSort,
+    ) -> Result<SearchPage> {
+        if page > 1 {
+            return Ok(SearchPage::default());
+        }
+        Ok(SearchPage {
+            results: self.search(query, category, filter, sort).await?,
+            total: None,
+            more: false,
+        })
+    }
+
+    /// A `smart_search` query. Indexers that take season and episode as
+    /// separate parameters can use the parsed form; the rest try each
+    /// spelling in turn.
+    async fn search_parsed(
+        &self,
+        query: &SearchQuery,
+        page: u32,
+        category: NyaaCategory,
+        filter: NyaaFilter,
+        sort: NyaaSort,
+    ) -> Result<SearchPage> {
+        search_spellings(self, query, page, category, filter, sort).await
+    }
+
+    /// The newest releases matching `query`, for tracking checks
+    async fn rss(
+        &self,
+        query: &str,
+        category: NyaaCategory,
+        filter: NyaaFilter,
+    ) -> Result<Vec<NyaaResult>> {
+        self.search(query, category, filter, NyaaSort::Date).await
+    }
+}
+
+/// The enabled indexers from the config, in the order they're listed
+pub fn from_config(configs: &[IndexerConfig]) -> Vec<SharedIndexer> {
+    let mut indexers: Vec<SharedIndexer> = Vec::new();
+    for config in configs.iter().filter(|c| c.enabled) {
+        let name = config.name.as_deref().unwrap_or(config.kind.name());
+        match config.kind {
+            IndexerKind::Nyaa => {
+                let mut client = NyaaClient::new().with_name(name);
+                if let Some(url) = &config.url {
+                    client = client.with_base_url(url);
+                }
+                indexers.push(Arc::new(client));
+            }
+            IndexerKind::AnimeTosho => {
+                let mut client = AnimeToshoClient::new().with_name(name);
+                if let Some(url) = &config.url {
+                    client = client.with_base_url(url);
+                }
+                indexers.push(Arc::new(client));
+            }
+            IndexerKind::Torznab => {
+                let Some(url) = &config.url else {
+                    warn!(indexer = %name, "Torznab indexer has no url, skipping");
+                    continue;
+                };
+                let client = TorznabClient::new(name, url, config.api_key.as_deref());
+                indexers.push(Arc::new(client));
+            }
+        }
+    }
+    indexers
+}
+
+/// Search every indexer that covers `category`, trying the alternative
+/// spellings `smart_search` comes up with, then merge and rank the lot by
+/// query match plus `profile` score. Releases the profile rejects go last.
+/// Later pages are ranked among themselves, to go after the ones before.
+/// Filters written into `query` (`res:1080p`, `-batch`, ...) drop the
+/// results they don't match.
+pub async fn search_all(
+    indexers: &[SharedIndexer],
+    profile: &ScoringProfile,
+    query: &str,
+    page: u32,
+    category: NyaaCategory,
+    filter: NyaaFilter,
+    sort: NyaaSort,
+) -> Result<SearchPage> {
+    let query = AdvancedQuery::parse(query)?;
+    let search_query = query.search_query();
+    let filter = query.nyaa_filter(filter);
+
+    let mut tasks = tokio::task::JoinSet::new();
+    for (order, indexer) in covering(indexers, category)?.into_iter().enumerate() {
+        let search_query = search_query.clone();
+        tasks.spawn(async move {
+            let page = indexer
+                .search_parsed(&search_query, page, category, filter, sort)
+                .await;
+            (order, indexer.name().to_string(), page)
+        });
+    }
+
+    let pages = collect(tasks).await?;
+    let more = pages.iter().any(|p| p.more);
+    // A lower bound when some indexers don't say
+    let total = pages.iter().filter_map(|p| p.total).reduce(|a, b| a + b);
+    let mut results = merge(pages.into_iter().map(|p| p.results).collect());
+    apply_filter(&mut results, filter);
+    results.retain(|r| query.filters.matches(r));
+    sort_results(&mut results, sort);
+    // Stable, so the sort order breaks ties between equal scores
+    results.sort_by_cached_key(|r| {
+        let breakdown = profile.score(r);
+        let score = score_result(&r.title, &search_query.parsed) + breakdown.total;
+        (breakdown.rejected.is_some(), std::cmp::Reverse(score))
+    });
+    Ok(SearchPage {
+        results,
+        total,
+        more,
+    })
+}
+
+/// Every indexer's feed for `query`, merged
+pub async fn rss_all(
+    indexers: &[SharedIndexer],
+    query: &str,
+    category: NyaaCategory,
+    filter: NyaaFilter,
+) -> Result<Vec<NyaaResult>> {
+    let mut tasks = tokio::task::JoinSet::new();
+    for (order, indexer) in covering(indexers, category)?.into_iter().enumerate() {
+        let query = query.to_string();
+        tasks.spawn(async move {
+            let results = indexer.rss(&query, category, filter).await;
+            (order, indexer.name().to_string(), results)
+        });
+    }
+
+    let mut results = merge(collect(tasks).await?);
+    apply_filter(&mut results, filter);
+    Ok(results)
+}
+
+/// The indexers that can search `category`, or an error if there are none
+fn covering(indexers: &[SharedIndexer], category: NyaaCategory) -> Result<Vec<SharedIndexer>> {
+    let covering: Vec<SharedIndexer> = indexers
+        .iter()
+        .filter(|i| i.categories().contains(&category))
+        .cloned()
+        .collect();
+    if covering.is_empty() {
+        return Err(Error::NyaaSearch(format!(
+            "No enabled indexer searches {}",
+            category.as_display()
+        )));
+    }
+    Ok(covering)
+}
+
+/// Queries one indexer, moving on to alternative spellings until there are
+/// enough results. Only the first page tries alternatives; later ones page
+/// through the primary spelling, which is what the total counts.
+pub async fn search_spellings<I: Indexer + Sync + ?Sized>(
+    indexer: &I,
+    query: &SearchQuery,
+    page: u32,
+    category: NyaaCategory,
+    filter: NyaaFilter,
+    sort: NyaaSort,
+) -> Result<SearchPage> {
+    if page > 1 {
+        return indexer
+            .search_page(&query.primary, page, category, filter, sort)
+            .await;
+    }
+
+    let mut first = SearchPage::default();
+    let mut last_error = None;
+
+    debug!(indexer = %indexer.name(), query = %query.primary, "Trying search query");
+    match indexer
+        .search_page(&query.primary, 1, category, filter, sort)
+        .await
+    {
+        Ok(page) => first = page,
+        Err(e) => {
+            debug!(indexer = %indexer.name(), error = %e, query = %query.primary, "Search query failed");
+            last_error = Some(e);
+        }
+    }
+
+    let mut all_results = std::mem::take(&mut first.results);
+    if !enough(all_results.len(), all_results.len()) {
+        for query in &query.alternatives {
+            debug!(indexer = %indexer.name(), query = %query, "Trying search query");
+            match indexer.search(query, category, filter, sort).await {
+                Ok(results) => {
+                    let before = all_results.len();
+                    all_results = merge(vec![all_results, results]);
+                    if enough(all_results.len() - before, all_results.len()) {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    debug!(indexer = %indexer.name(), error = %e, query = %query, "Search query failed");
+                    last_error = Some(e);
+                }
+            }
+        }
+    }
+
+    match last_error {
+        Some(e) if all_results.is_empty() => Err(e),
+        _ => Ok(SearchPage {
+            results: all_results,
+            ..first
+        }),
+    }
+}
+
+/// Whether a spelling that added `count` results, for `len` in all, is
+/// enough to stop trying others
+fn enough(count: usize, len: usize) -> bool {
+    (count > 0 && len >= 15) || len >= 30
+}
+
+/// Waits for every indexer. One failing is logged and skipped; all of them
+/// failing is an error.
+async fn collect<T>(mut tasks: tokio::task::JoinSet<(usize, String, Result<T>)>) -> Result<Vec<T>>
+where
+    T: Send + 'static,
+{
+    let mut batches = Vec::new();
+    let mut last_error = None;
+    while let Some(joined) = tasks.join_next().await {
+        let Ok((order, name, results)) = joined else {
+            continue;
+        };
+        match results {
+            Ok(results) => batches.push((order, results)),
+            Err(e) => {
+                warn!(indexer = %name, error = %e, "Indexer failed");
+                last_error = Some(e);
+            }
+        }
+    }
+
+    if batches.is_empty()
+        && let Some(e) = last_error
+    {
+        return Err(e);
+    }
+    // Keep the configured order, so earlier indexers win duplicates
+    batches.sort_by_key(|(order, _)| *order);
+    Ok(batches.into_iter().map(|(_, results)| results).collect())
+}
+
+/// Concatenates result lists, dropping releases seen before. Info hashes
+/// identify a release across indexers; results without one fall back to
+/// their magnet or torrent link.
+pub fn merge(batches: Vec<Vec<NyaaResult>>) -> Vec<NyaaResult> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut merged: Vec<NyaaResult> = Vec::new();
+    for result in batches.into_iter().flatten() {
+        let key = if !result.info_hash.is_empty() {
+            result.info_hash.to_lowercase()
+        } else if !result.magnet_link.is_empty() {
+            result.magnet_link.clone()
+        } else {
+            result.torrent_url.clone()
+        };
+        match seen.get(&key) {
+            Some(&idx) => {
+                // Indexers update swarm stats at different times
+                let kept = &mut merged[idx];
+                kept.seeders = kept.seeders.max(result.seeders);
+                kept.leechers = kept.leechers.max(result.leechers);
+                // Known beats unknown, and trusted anywhere wins
+                kept.is_trusted = kept.is_trusted.max(result.is_trusted);
+            }
+            None => {
+                seen.insert(key, merged.len());
+                merged.push(result);
+            }
+        }
+    }
+    merged
+}
+
+/// Filters indexers couldn't apply themselves. Results from indexers that
+/// don't know who's trusted are kept rather than all dropped.
+fn apply_filter(results: &mut Vec<NyaaResult>, filter: NyaaFilter) {
+    match filter {
+        NyaaFilter::NoFilter => {}
+        NyaaFilter::TrustedOnly => results.retain(|r| r.is_trusted != Some(false)),
+        NyaaFilter::NoRemakes => results.retain(|r| !r.is_remake),
+    }
+}
+
+fn sort_results(results: &mut [NyaaResult], sort: NyaaSort) {
+    match sort {
+        // `YYYY-MM-DD HH:MM` sorts as text
+        NyaaSort::Date => results.sort_by(|a, b| b.date.cmp(&a.date)),
+        NyaaSort::Seeders => results.sort_by_key(|r| std::cmp::Reverse(r.seeders)),
+        NyaaSort::Size => {
+            results.sort_by(|a, b| parse_size_mb(&b.size).total_cmp(&parse_size_mb(&a.size)))
+        }
+        NyaaSort::Downloads => results.sort_by_key(|r| std::cmp::Reverse(r.downloads)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(title: &str, info_hash: &str, seeders: u32) -> NyaaResult {
+        NyaaResult {
+            title: title.to_string(),
+            category: "Anime".to_string(),
+            size: "1.4 GiB".to_string(),
+            seeders,
+            magnet_link: format!("magnet:?xt=urn:btih:{}", info_hash),
+            is_trusted: Some(false),
+            info_hash: info_hash.to_string(),
+            ..NyaaResult::default()
+        }
+    }
+
+    #[test]
+    fn test_merge_dedupes_by_info_hash() {
+        let nyaa = vec![
+            result("[SubsPlease] Frieren - 05 (1080p)", "aaaa", 100),
+            result("[SubsPlease] Frieren - 05 (720p)", "bbbb", 40),
+        ];
+        let tosho = vec![
+            result("[SubsPlease] Sousou no Frieren - 05 (1080p)", "AAAA", 120),
+            result("[Erai-raws] Frieren - 05 [1080p]", "cccc", 60),
+        ];
+
+        let merged = merge(vec![nyaa, tosho]);
+        assert_eq!(merged.len(), 3);
+        // The first indexer's copy is kept, with the better swarm count
+        assert_eq!(merged[0].title, "[SubsPlease] Frieren - 05 (1080p)");
+        assert_eq!(merged[0].seeders, 120);
+        assert_eq!(merged[2].info_hash, "cccc");
+    }
+
+    #[test]
+    fn test_trusted_only_keeps_results_without_trust_info() {
+        let mut trusted = result("[SubsPlease] Frieren - 05 (1080p)", "aaaa", 100);
+        trusted.is_trusted = Some(true);
+        let untrusted = result("[Random] Frieren - 05 (1080p)", "bbbb", 40);
+        let mut tosho = result("[Erai-raws] Frieren - 05 [1080p]", "cccc", 60);
+        tosho.is_trusted = None;
+
+        let mut results = vec![trusted, untrusted, tosho];
+        apply_filter(&mut results, NyaaFilter::TrustedOnly);
+        let hashes: Vec<&str> = results.iter().map(|r| r.info_hash.as_str()).collect();
+        assert_eq!(hashes, ["aaaa", "cccc"]);
+    }
+
+    #[test]
+    fn test_from_config_skips_disabled_and_incomplete() {
+        let config = |kind, url: Option<&str>, enabled| IndexerConfig {
+            kind,
+            name: None,
+            url: url.map(str::to_string),
+            api_key: None,
+            enabled,
+        };
+        let indexers = from_config(&[
+            config(IndexerKind::Nyaa, None, true),
+            config(IndexerKind::Nyaa, Some("https://nyaa.land"), false),
+            config(IndexerKind::Torznab, None, true),
+            config(IndexerKind::AnimeTosho, None, true),
+        ]);
+        let names: Vec<_> = indexers.iter().map(|i| i.name()).collect();
+        assert_eq!(names, vec!["Nyaa", "AnimeTosho"]);
+    }
+}
@@ -0,0 +1,787 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+
+use humansize::{BINARY, format_size};
+use tracing::debug;
+
+use crate::error::{Error, Result};
+use crate::indexer::{Indexer, SearchPage, search_spellings};
+use crate::nyaa::rss::{format_date, item_fields, items, unescape};
+use crate::nyaa::{
+    NyaaCategory, NyaaFilter, NyaaResult, NyaaSort, SearchQuery, is_batch_release, magnet_from_hash,
+};
+
+/// Newznab's "TV/Anime" category
+const ANIME_CATEGORY: u32 = 5070;
+
+/// Results per request when caps don't give a default limit
+const PAGE_SIZE: u32 = 100;
+
+/// `<torznab:attr name="..." value="..."/>`, attributes in either order
+static ATTR: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<torznab:attr\b([^>]*?)/?>").unwrap());
+
+/// A `key="value"` pair inside a tag
+static TAG_ATTR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"([\w-]+)\s*=\s*"([^"]*)""#).unwrap());
+
+static SEARCH_MODE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<(search|tv-search)\b([^>]*?)/?>").unwrap());
+
+static CATEGORY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<(?:category|subcat)\b([^>]*?)/?>").unwrap());
+
+static ERROR: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<error\b([^>]*?)/?>").unwrap());
+
+static LIMITS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<limits\b([^>]*?)/?>").unwrap());
+
+/// `<newznab:response offset=".." total=".."/>` at the top of a channel
+static RESPONSE_INFO: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<(?:newznab|torznab):response\b([^>]*?)/?>").unwrap());
+
+fn tag_attrs(tag: &str) -> HashMap<&str, String> {
+    TAG_ATTR
+        .captures_iter(tag)
+        .filter_map(|c| Some((c.get(1)?.as_str(), unescape(c.get(2)?.as_str()))))
+        .collect()
+}
+
+/// Names for the standard Newznab categories, for indexers whose caps
+/// don't list them
+fn standard_category_name(id: u32) -> Option<&'static str> {
+    match id {
+        2000 => Some("Movies"),
+        5000 => Some("TV"),
+        5070 => Some("TV/Anime"),
+        7000 => Some("Books"),
+        8000 => Some("Other"),
+        _ => None,
+    }
+}
+
+/// What an endpoint says it supports (`t=caps`)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TorznabCaps {
+    /// Parameters `t=search` accepts, e.g. `q`
+    pub search_params: Vec<String>,
+    /// Parameters `t=tvsearch` accepts, e.g. `q,season,ep`; empty when
+    /// tv-search isn't available
+    pub tv_search_params: Vec<String>,
+    /// Category IDs with their names, subcategories included
+    pub categories: Vec<(u32, String)>,
+    /// Results a search returns unless told otherwise
+    pub page_size: Option<u32>,
+}
+
+impl TorznabCaps {
+    pub fn parse(xml: &str) -> Result<Self> {
+        if !xml.contains("<caps") {
+            return Err(parse_error(xml)
+                .unwrap_or_else(|| Error::NyaaSearch("Not a Torznab caps response".to_string())));
+        }
+
+        let mut caps = TorznabCaps::default();
+        for mode in SEARCH_MODE.captures_iter(xml) {
+            let attrs = tag_attrs(&mode[2]);
+            if attrs.get("available").is_some_and(|a| a != "yes") {
+                continue;
+            }
+            let params: Vec<String> = attrs
+                .get("supportedParams")
+                .map(|p| p.split(',').map(|s| s.trim().to_string()).collect())
+                .unwrap_or_default();
+            match &mode[1] {
+                "search" => caps.search_params = params,
+                _ => caps.tv_search_params = params,
+            }
+        }
+        caps.page_size = LIMITS
+            .captures(xml)
+            .and_then(|limits| tag_attrs(&limits[1]).get("default")?.parse().ok())
+            .filter(|size| *size > 0);
+        for category in CATEGORY.captures_iter(xml) {
+            let attrs = tag_attrs(&category[1]);
+            if let (Some(id), Some(name)) = (attrs.get("id"), attrs.get("name"))
+                && let Ok(id) = id.parse()
+            {
+                caps.categories.push((id, name.clone()));
+            }
+        }
+        Ok(caps)
+    }
+
+    fn supports_tv_search(&self, param: &str) -> bool {
+        self.tv_search_params.iter().any(|p| p == param)
+    }
+
+    /// TV/Anime if the endpoint has it (or lists no categories at all),
+    /// otherwise the first category named like anime
+    pub fn anime_category(&self) -> u32 {
+        if self.categories.is_empty() || self.categories.iter().any(|(id, _)| *id == ANIME_CATEGORY)
+        {
+            return ANIME_CATEGORY;
+        }
+        self.categories
+            .iter()
+            .find(|(_, name)| name.to_lowercase().contains("anime"))
+            .map_or(ANIME_CATEGORY, |(id, _)| *id)
+    }
+
+    fn category_name(&self, id: u32) -> Option<&str> {
+        self.categories
+            .iter()
+            .find(|(cat, _)| *cat == id)
+            .map(|(_, name)| name.as_str())
+            .or_else(|| standard_category_name(id))
+    }
+}
+
+/// A Torznab API endpoint, e.g. Prowlarr's `http://localhost:9696/1/api`
+/// or Jackett's `.../api/v2.0/indexers/all/results/torznab/api`
+pub struct TorznabClient {
+    client: reqwest::Client,
+    name: String,
+    url: String,
+    api_key: Option<String>,
+    /// Fetched on first use
+    caps: Mutex<Option<Arc<TorznabCaps>>>,
+    /// Whether page 1 of each query came from tv-search, so its later
+    /// pages are asked for the same way
+    tv_searched: Mutex<HashMap<String, bool>>,
+}
+
+impl TorznabClient {
+    pub fn new(name: &str, url: &str, api_key: Option<&str>) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .user_agent("miru/0.1")
+                .build()
+                .expect("Failed to create HTTP client"),
+            name: name.to_string(),
+            url: url.to_string(),
+            api_key: api_key.map(str::to_string),
+            caps: Mutex::new(None),
+            tv_searched: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The endpoint's capabilities, asked for once and then remembered. An
+    /// endpoint that answers without usable caps gets the defaults, so it
+    /// isn't asked again on every search. Failing to reach it is an error,
+    /// and the next call tries again.
+    pub async fn caps(&self) -> Result<Arc<TorznabCaps>> {
+        if let Some(caps) = self.caps.lock().unwrap().clone() {
+            return Ok(caps);
+        }
+        let xml = self.get(vec![("t", "caps".to_string())]).await?;
+        let caps = match TorznabCaps::parse(&xml) {
+            Ok(caps) => caps,
+            Err(e) => {
+                debug!(indexer = %self.name, error = %e, "No usable Torznab caps, assuming defaults");
+                TorznabCaps::default()
+            }
+        };
+        let caps = Arc::new(caps);
+        debug!(indexer = %self.name, caps = ?caps, "Torznab capabilities");
+        *self.caps.lock().unwrap() = Some(caps.clone());
+        Ok(caps)
+    }
+
+    /// Plain search with no capability info to go on if caps can't be had
+    async fn caps_or_default(&self) -> Arc<TorznabCaps> {
+        match self.caps().await {
+            Ok(caps) => caps,
+            Err(e) => {
+                debug!(indexer = %self.name, error = %e, "Torznab caps unreachable, assuming defaults");
+                Arc::new(TorznabCaps::default())
+            }
+        }
+    }
+
+    async fn get(&self, mut params: Vec<(&str, String)>) -> Result<String> {
+        if let Some(key) = &self.api_key {
+            params.push(("apikey", key.clone()));
+        }
+        let response = self.client.get(&self.url).query(&params).send().await?;
+        if !response.status().is_success() {
+            return Err(Error::NyaaSearch(format!(
+                "{} HTTP error: {}",
+                self.name,
+                response.status()
+            )));
+        }
+        Ok(response.text().await?)
+    }
+
+    fn remember_tv_search(&self, query: &SearchQuery, page: u32, tv_searched: bool) {
+        if page == 1 {
+            self.tv_searched
+                .lock()
+                .unwrap()
+                .insert(query.primary.clone(), tv_searched);
+        }
+    }
+
+    /// Page `page` of a search made with `params`
+    async fn get_page(
+        &self,
+        mut params: Vec<(&str, String)>,
+        page: u32,
+        caps: &TorznabCaps,
+    ) -> Result<SearchPage> {
+        let offset = page.saturating_sub(1) * caps.page_size.unwrap_or(PAGE_SIZE);
+        if offset > 0 {
+            params.push(("offset", offset.to_string()));
+        }
+        let xml = self.get(params).await?;
+        parse_page(&xml, caps, offset)
+    }
+}
+
+#[async_trait::async_trait]
+impl Indexer for TorznabClient {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Torznab has no language categories; English-translated searches go
+    /// to the whole anime category
+    fn categories(&self) -> &'static [NyaaCategory] {
+        &[NyaaCategory::AllAnime, NyaaCategory::AnimeEnglish]
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        category: NyaaCategory,
+        filter: NyaaFilter,
+        sort: NyaaSort,
+    ) -> Result<Vec<NyaaResult>> {
+        self.search_page(query, 1, category, filter, sort)
+            .await
+            .map(|page| page.results)
+    }
+
+    async fn search_page(
+        &self,
+        query: &str,
+        page: u32,
+        _category: NyaaCategory,
+        _filter: NyaaFilter,
+        _sort: NyaaSort,
+    ) -> Result<SearchPage> {
+        let caps = self.caps_or_default().await;
+        debug!(indexer = %self.name, query = %query, page, "Searching Torznab");
+        let params = vec![
+            ("t", "search".to_string()),
+            ("cat", caps.anime_category().to_string()),
+            ("q", query.to_string()),
+        ];
+        self.get_page(params, page, &caps).await
+    }
+
+    /// Season and episode go in as tv-search parameters when the endpoint
+    /// takes them. Anime is often numbered differently from what trackers
+    /// file it under, so an empty answer falls back to text searches.
+    async fn search_parsed(
+        &self,
+        query: &SearchQuery,
+        page: u32,
+        category: NyaaCategory,
+        filter: NyaaFilter,
+        sort: NyaaSort,
+    ) -> Result<SearchPage> {
+        let caps = self.caps_or_default().await;
+        let parsed = &query.parsed;
+
+        let season = parsed.season.filter(|_| caps.supports_tv_search("season"));
+        let episode = parsed
+            .episode
+            .filter(|_| caps.supports_tv_search("ep") && !parsed.is_batch_request);
+        let tv_searched = self
+            .tv_searched
+            .lock()
+            .unwrap()
+            .get(&query.primary)
+            .copied();
+        if caps.supports_tv_search("q")
+            && !parsed.show_name.is_empty()
+            && (season.is_some() || episode.is_some())
+            && (page == 1 || tv_searched != Some(false))
+        {
+            let mut params = vec![
+                ("t", "tvsearch".to_string()),
+                ("cat", caps.anime_category().to_string()),
+                ("q", parsed.show_name.clone()),
+            ];
+            if let Some(season) = season {
+                params.push(("season", season.to_string()));
+            }
+            if let Some(episode) = episode {
+                params.push(("ep", episode.to_string()));
+            }
+            debug!(indexer = %self.name, params = ?params, "Torznab tv-search");
+
+            let found = self.get_page(params, page, &caps).await;
+            // Later pages stay with tv-search, even when they come up empty
+            if page > 1 && tv_searched == Some(true) {
+                return found;
+            }
+            match found {
+                Ok(found) if !found.results.is_empty() => {
+                    self.remember_tv_search(query, page, true);
+                    return Ok(found);
+                }
+                Ok(_) => debug!(indexer = %self.name, "Nothing from tv-search, trying text"),
+                Err(e) => debug!(indexer = %self.name, error = %e, "tv-search failed"),
+            }
+        }
+
+        self.remember_tv_search(query, page, false);
+        search_spellings(self, query, page, category, filter, sort).await
+    }
+}
+
+/// `<error code=".." description=".."/>`, which Torznab sends with a 200
+fn parse_error(xml: &str) -> Option<Error> {
+    let error = ERROR.captures(xml)?;
+    let attrs = tag_attrs(&error[1]);
+    Some(Error::NyaaSearch(format!(
+        "Torznab error {}: {}",
+        attrs.get("code").map_or("?", |c| c.as_str()),
+        attrs.get("description").map_or("", |d| d.as_str())
+    )))
+}
+
+/// Items of a search response, with category names from `caps`
+fn parse_results(xml: &str, caps: &TorznabCaps) -> Result<Vec<NyaaResult>> {
+    if let Some(error) = parse_error(xml) {
+        return Err(error);
+    }
+    Ok(items(xml)
+        .filter_map(|item| parse_item(item, caps))
+        .collect())
+}
+
+/// A search response as the page starting at `offset`. Endpoints that
+/// don't give a total have more as long as pages come back full.
+fn parse_page(xml: &str, caps: &TorznabCaps, offset: u32) -> Result<SearchPage> {
+    let results = parse_results(xml, caps)?;
+    let total = RESPONSE_INFO
+        .captures(xml)
+        .and_then(|info| tag_attrs(&info[1]).get("total")?.parse::<usize>().ok());
+    let seen = offset as usize + results.len();
+    let more = match total {
+        Some(total) => seen < total,
+        None => results.len() >= caps.page_size.unwrap_or(PAGE_SIZE) as usize,
+    };
+    Ok(SearchPage {
+        results,
+        total,
+        more,
+    })
+}
+
+fn parse_item(item: &str, caps: &TorznabCaps) -> Option<NyaaResult> {
+    let fields = item_fields(item);
+    let mut attrs: HashMap<String, String> = HashMap::new();
+    let mut categories: Vec<u32> = Vec::new();
+    for attr in ATTR.captures_iter(item) {
+        let attr = tag_attrs(&attr[1]);
+        let (Some(name), Some(value)) = (attr.get("name"), attr.get("value")) else {
+            continue;
+        };
+        // Items usually carry a parent category and a subcategory
+        if name == "category" {
+            categories.extend(value.parse::<u32>().ok());
+        } else {
+            attrs.insert(name.clone(), value.clone());
+        }
+    }
+    categories.extend(fields.get("category").and_then(|c| c.parse::<u32>().ok()));
+    let count = |name: &str| attrs.get(name).and_then(|v| v.parse::<u32>().ok());
+
+    let title = fields.get("title").filter(|t| !t.is_empty())?.clone();
+    let link = fields.get("link").cloned().unwrap_or_default();
+    let info_hash = attrs
+        .get("infohash")
+        .map(|h| h.to_lowercase())
+        .unwrap_or_default();
+    let magnet_link = match attrs.get("magneturl") {
+        Some(magnet) => magnet.clone(),
+        None if link.starts_with("magnet:") => link.clone(),
+        None if !info_hash.is_empty() => magnet_from_hash(&info_hash, &title),
+        None => String::new(),
+    };
+    if magnet_link.is_empty() && link.is_empty() {
+        return None;
+    }
+
+    let size = fields
+        .get("size")
+        .or(attrs.get("size"))
+        .and_then(|s| s.parse::<u64>().ok())
+        .map(|bytes| format_size(bytes, BINARY.decimal_places(1)))
+        .unwrap_or_default();
+    let seeders = count("seeders").unwrap_or(0);
+    // Subcategories and tracker-specific ones have the higher IDs
+    let category = categories
+        .iter()
+        .filter_map(|&id| Some((id, caps.category_name(id)?)))
+        .max_by_key(|(id, _)| *id)
+        .map(|(_, name)| name.to_string())
+        .or_else(|| categories.first().map(|id| id.to_string()))
+        .unwrap_or_else(|| "Unknown".to_string());
+
+    Some(NyaaResult {
+        is_batch: is_batch_release(&title, &size),
+        category,
+        seeders,
+        // `peers` counts seeders too
+        leechers: count("peers").map_or(0, |p| p.saturating_sub(seeders)),
+        downloads: count("grabs").unwrap_or(0),
+        torrent_url: if link.starts_with("magnet:") {
+            String::new()
+        } else {
+            link
+        },
+        date: fields
+            .get("pubDate")
+            .map(|d| format_date(d))
+            .unwrap_or_default(),
+        is_trusted: None,
+        is_remake: false,
+        title,
+        size,
+        magnet_link,
+        info_hash,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::nyaa::smart_search;
+    use crate::test_support::{mock_server, reply};
+
+    const CAPS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<caps>
+  <server title="Prowlarr" />
+  <limits default="100" max="100" />
+  <searching>
+    <search available="yes" supportedParams="q" />
+    <tv-search available="yes" supportedParams="q,season,ep" />
+    <movie-search available="no" supportedParams="q" />
+  </searching>
+  <categories>
+    <category id="5000" name="TV">
+      <subcat id="5070" name="TV/Anime" />
+    </category>
+    <category id="100002" name="Anime - English-translated" />
+  </categories>
+</caps>"#;
+
+    const RESPONSE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom" xmlns:torznab="http://torznab.com/schemas/2015/feed">
+  <channel>
+    <title>Prowlarr</title>
+    <item>
+      <title>[SubsPlease] Sousou no Frieren - 05 (1080p) [8E2F1A0C].mkv</title>
+      <guid>https://nyaa.si/view/1729403</guid>
+      <link>http://localhost:9696/1/download?apikey=secret&amp;link=abc</link>
+      <pubDate>Fri, 06 Oct 2023 15:32:05 +0000</pubDate>
+      <size>1503238553</size>
+      <enclosure url="http://localhost:9696/1/download?apikey=secret&amp;link=abc" length="1503238553" type="application/x-bittorrent" />
+      <torznab:attr name="category" value="5070" />
+      <torznab:attr name="category" value="100002" />
+      <torznab:attr name="seeders" value="1532" />
+      <torznab:attr name="peers" value="1573" />
+      <torznab:attr name="grabs" value="20877" />
+      <torznab:attr name="infohash" value="3A6B1D8E2C0F4B7A9D5E1C3F8A2B6D4E0C9F7A1B" />
+    </item>
+    <item>
+      <title>Sousou no Frieren S01E05 1080p WEB H.264-VARYG</title>
+      <link>magnet:?xt=urn:btih:f00dfeedc0ffee00112233445566778899aabbcc&amp;dn=Frieren</link>
+      <pubDate>Fri, 06 Oct 2023 17:01:44 +0000</pubDate>
+      <torznab:attr value="5070" name="category" />
+      <torznab:attr name="size" value="1468006400" />
+      <torznab:attr name="seeders" value="88" />
+      <torznab:attr name="peers" value="90" />
+    </item>
+  </channel>
+</rss>"#;
+
+    #[test]
+    fn test_parse_caps() {
+        let caps = TorznabCaps::parse(CAPS).unwrap();
+        assert_eq!(caps.search_params, vec!["q"]);
+        assert!(caps.supports_tv_search("season") && caps.supports_tv_search("ep"));
+        assert_eq!(caps.anime_category(), 5070);
+        assert_eq!(caps.page_size, Some(100));
+        assert_eq!(
+            caps.category_name(100002),
+            Some("Anime - English-translated")
+        );
+
+        // No TV/Anime; fall back to the tracker's own anime category
+        let tracker = TorznabCaps::parse(
+            r#"<caps><searching><search available="yes" supportedParams="q"/>
+               <tv-search available="no" supportedParams="q,season,ep"/></searching>
+               <categories><category id="100001" name="Music"/>
+               <category id="100003" name="Anime Raw"/></categories></caps>"#,
+        )
+        .unwrap();
+        assert!(tracker.tv_search_params.is_empty());
+        assert_eq!(tracker.anime_category(), 100003);
+    }
+
+    #[test]
+    fn test_parse_results_attrs() {
+        let caps = TorznabCaps::parse(CAPS).unwrap();
+        let results = parse_results(RESPONSE, &caps).unwrap();
+        assert_eq!(results.len(), 2);
+
+        let first = &results[0];
+        assert_eq!(first.seeders, 1532);
+        assert_eq!(first.leechers, 41);
+        assert_eq!(first.downloads, 20877);
+        assert_eq!(first.size, "1.4 GiB");
+        assert_eq!(first.category, "Anime - English-translated");
+        assert_eq!(first.info_hash, "3a6b1d8e2c0f4b7a9d5e1c3f8a2b6d4e0c9f7a1b");
+        assert!(first.magnet_link.contains(&first.info_hash));
+        assert_eq!(
+            first.torrent_url,
+            "http://localhost:9696/1/download?apikey=secret&link=abc"
+        );
+
+        // Magnet as the link, size as an attr, attrs written value-first
+        let second = &results[1];
+        assert!(
+            second
+                .magnet_link
+                .starts_with("magnet:?xt=urn:btih:f00dfeed")
+        );
+        assert!(second.torrent_url.is_empty());
+        assert_eq!(second.size, "1.4 GiB");
+        assert_eq!(second.category, "TV/Anime");
+        assert_eq!(second.leechers, 2);
+    }
+
+    #[tokio::test]
+    async fn test_tv_search_with_season_and_episode() {
+        let (port, log) = mock_server(|req| {
+            assert!(req.head.contains("apikey=secret"));
+            if req.head.contains("t=caps") {
+                reply(200, CAPS)
+            } else {
+                assert!(req.head.contains("t=tvsearch"));
+                assert!(req.head.contains("q=Frieren"));
+                assert!(req.head.contains("season=1"));
+                assert!(req.head.contains("ep=5"));
+                assert!(req.head.contains("cat=5070"));
+                reply(200, RESPONSE)
+            }
+        })
+        .await;
+        let client = TorznabClient::new(
+            "Prowlarr",
+            &format!("http://127.0.0.1:{}/1/api", port),
+            Some("secret"),
+        );
+
+        let query = smart_search("Frieren S01E05");
+        for _ in 0..2 {
+            let page = client
+                .search_parsed(
+                    &query,
+                    1,
+                    NyaaCategory::AnimeEnglish,
+                    NyaaFilter::NoFilter,
+                    NyaaSort::Seeders,
+                )
+                .await
+                .unwrap();
+            assert_eq!(page.results.len(), 2);
+        }
+        // Caps are asked for once
+        assert_eq!(log.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_text_search_without_tv_search() {
+        let (port, _) = mock_server(|req| {
+            if req.head.contains("t=caps") {
+                reply(
+                    200,
+                    r#"<caps><searching><search available="yes" supportedParams="q"/></searching></caps>"#,
+                )
+            } else {
+                assert!(req.head.contains("t=search"));
+                reply(200, RESPONSE)
+            }
+        })
+        .await;
+        let client = TorznabClient::new("Jackett", &format!("http://127.0.0.1:{}/api", port), None);
+
+        let page = client
+            .search_parsed(
+                &smart_search("Frieren S01E05"),
+                1,
+                NyaaCategory::AllAnime,
+                NyaaFilter::NoFilter,
+                NyaaSort::Seeders,
+            )
+            .await
+            .unwrap();
+        assert_eq!(page.results.len(), 2);
+        assert!(!page.more);
+    }
+
+    #[tokio::test]
+    async fn test_later_pages_keep_the_search_mode() {
+        const EMPTY: &str = r#"<?xml version="1.0"?><rss><channel></channel></rss>"#;
+        let modes = Arc::new(Mutex::new(Vec::new()));
+        let server_modes = modes.clone();
+        let (port, _) = mock_server(move |req| {
+            if req.head.contains("t=caps") {
+                return reply(200, CAPS);
+            }
+            let tv = req.head.contains("t=tvsearch");
+            server_modes
+                .lock()
+                .unwrap()
+                .push(if tv { "tv" } else { "text" });
+            if tv {
+                // Only episode 5 is filed under its season and episode
+                if req.head.contains("ep=5") && !req.head.contains("offset=") {
+                    reply(200, RESPONSE)
+                } else {
+                    reply(200, EMPTY)
+                }
+            } else {
+                reply(200, RESPONSE)
+            }
+        })
+        .await;
+        let client = TorznabClient::new(
+            "Prowlarr",
+            &format!("http://127.0.0.1:{}/1/api", port),
+            None,
+        );
+        let search = |query: &'static str, page| {
+            let client = &client;
+            async move {
+                client
+                    .search_parsed(
+                        &smart_search(query),
+                        page,
+                        NyaaCategory::AllAnime,
+                        NyaaFilter::NoFilter,
+                        NyaaSort::Seeders,
+                    )
+                    .await
+                    .unwrap()
+            }
+        };
+
+        // Page 1 came from tv-search, so page 2 does too, empty or not
+        assert_eq!(search("Frieren S01E05", 1).await.results.len(), 2);
+        assert!(search("Frieren S01E05", 2).await.results.is_empty());
+        assert_eq!(*modes.lock().unwrap(), ["tv", "tv"]);
+
+        // Page 1 fell back to text, so page 2 goes straight to it
+        modes.lock().unwrap().clear();
+        assert_eq!(search("Frieren S01E06", 1).await.results.len(), 2);
+        assert_eq!(search("Frieren S01E06", 2).await.results.len(), 2);
+        let modes = modes.lock().unwrap();
+        assert_eq!(modes.iter().filter(|&&mode| mode == "tv").count(), 1);
+        assert_eq!(modes[0], "tv");
+    }
+
+    #[tokio::test]
+    async fn test_unusable_caps_asked_for_once() {
+        let caps_asked = Arc::new(AtomicUsize::new(0));
+        let server_caps_asked = caps_asked.clone();
+        let (port, _) = mock_server(move |req| {
+            if req.head.contains("t=caps") {
+                // Still starting up the first time, then not Torznab caps
+                match server_caps_asked.fetch_add(1, Ordering::SeqCst) {
+                    0 => reply(503, ""),
+                    _ => reply(200, "<html>Jackett</html>"),
+                }
+            } else {
+                assert!(req.head.contains("t=search"));
+                reply(200, RESPONSE)
+            }
+        })
+        .await;
+        let client = TorznabClient::new("Jackett", &format!("http://127.0.0.1:{}/api", port), None);
+
+        for _ in 0..3 {
+            let results = client
+                .search(
+                    "Frieren",
+                    NyaaCategory::AllAnime,
+                    NyaaFilter::NoFilter,
+                    NyaaSort::Seeders,
+                )
+                .await
+                .unwrap();
+            assert_eq!(results.len(), 2);
+        }
+        assert_eq!(caps_asked.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_search_pages_by_offset() {
+        let (port, log) = mock_server(|req| {
+            if req.head.contains("t=caps") {
+                reply(200, CAPS)
+            } else {
+                assert!(req.head.contains("offset=100"));
+                reply(
+                    200,
+                    &RESPONSE.replace(
+                        "<title>Prowlarr</title>",
+                        r#"<newznab:response offset="100" total="102" />"#,
+                    ),
+                )
+            }
+        })
+        .await;
+        let client = TorznabClient::new(
+            "Prowlarr",
+            &format!("http://127.0.0.1:{}/1/api", port),
+            None,
+        );
+
+        let page = client
+            .search_page(
+                "Frieren",
+                2,
+                NyaaCategory::AllAnime,
+                NyaaFilter::NoFilter,
+                NyaaSort::Seeders,
+            )
+            .await
+            .unwrap();
+        assert_eq!(page.results.len(), 2);
+        assert_eq!(page.total, Some(102));
+        assert!(!page.more);
+        assert_eq!(log.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_error_response() {
+        let caps = TorznabCaps::default();
+        let err = parse_results(
+            r#"<error code="100" description="Incorrect user credentials"/>"#,
+            &caps,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Incorrect user credentials"));
+        assert!(TorznabCaps::parse(r#"<error code="100" description="Bad key"/>"#).is_err());
+    }
+}
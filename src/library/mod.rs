@@ -1,19 +1,25 @@
 pub mod batch;
 pub mod models;
+pub mod numbering;
 pub mod parser;
+pub mod release;
 pub mod scanner;
+pub mod schedule;
 pub mod tracking;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use serde::{Deserialize, Serialize};
 use tracing::{info, warn};
 
 pub use models::{ArchiveMode, ArchivedEpisode, ArchivedShow, Episode, Season, Show};
+pub use numbering::EpisodeNumbering;
 pub use scanner::scan_all_media_dirs;
 
 use crate::config::library_path;
 use crate::error::Result;
+use crate::metadata::mal_sync::ProgressUpdate;
+use crate::metadata::{AnimeMetadata, ProviderKind};
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Library {
@@ -23,6 +29,17 @@ pub struct Library {
     pub tracked_shows: Vec<models::TrackedSeries>,
     #[serde(default)]
     pub archived_shows: Vec<ArchivedShow>,
+    #[serde(default)]
+    pub bindings: Vec<models::DownloadBinding>,
+    /// Lowercase hashes of torrents miru added to the client. Seeding
+    /// policies only ever remove these.
+    #[serde(default)]
+    pub added_torrents: Vec<String>,
+    #[serde(default)]
+    pub watchlist: Vec<models::WatchlistEntry>,
+    /// Shows the user said have no metadata match, so the matcher skips them
+    #[serde(default)]
+    pub dismissed_matches: Vec<String>,
 }
 
 impl Library {
@@ -75,7 +92,19 @@ impl Library {
         let mut merged_shows = Vec::new();
 
         for mut scanned_show in scanned {
-            if let Some(existing_show) = existing.get(&scanned_show.id) {
+            let existing_show = existing.get(&scanned_show.id);
+            scanned_show.numbering = existing_show
+                .and_then(|s| s.numbering.clone())
+                .filter(|n| n.manual)
+                .or_else(|| self.numbering_from_metadata(&scanned_show.title));
+            scanned_show.apply_numbering();
+
+            if let Some(existing_show) = existing_show {
+                scanned_show.metadata = existing_show.metadata.clone();
+                scanned_show.cover_path = existing_show.cover_path.clone();
+                scanned_show.metadata_locked = existing_show.metadata_locked;
+                scanned_show.episode_info = existing_show.episode_info.clone();
+
                 let existing_eps: HashMap<u32, &Episode> = existing_show
                     .episodes
                     .iter()
@@ -83,9 +112,19 @@ impl Library {
                     .collect();
 
                 for ep in &mut scanned_show.episodes {
-                    if let Some(existing_ep) = existing_eps.get(&ep.number) {
+                    // By filename too, for episodes the numbering renumbered
+                    let existing_ep = existing_eps.get(&ep.number).copied().or_else(|| {
+                        existing_show
+                            .episodes
+                            .iter()
+                            .find(|e| e.filename == ep.filename)
+                    });
+                    if let Some(existing_ep) = existing_ep {
                         ep.watched = existing_ep.watched;
                         ep.last_position = existing_ep.last_position;
+                        if existing_ep.filename == ep.filename {
+                            ep.release = existing_ep.release.clone();
+                        }
                     }
                 }
             }
@@ -96,6 +135,61 @@ impl Library {
         Ok(())
     }
 
+    /// Numbering pieced together from the episode counts of the metadata of
+    /// each season of `title` that's tracked
+    pub fn numbering_from_metadata(&self, title: &str) -> Option<EpisodeNumbering> {
+        let title = parser::make_show_title(title).to_lowercase();
+        EpisodeNumbering::from_counts(
+            self.tracked_shows
+                .iter()
+                .filter(|s| parser::make_show_title(&s.title).to_lowercase() == title)
+                .map(|s| {
+                    (
+                        s.season,
+                        s.cached_metadata.as_ref().and_then(|m| m.episodes),
+                    )
+                }),
+        )
+    }
+
+    /// Pin a show to `metadata` chosen by hand, so automatic matching
+    /// never replaces it. Tracked series that pointed at the old match, or
+    /// share the show's title and season, follow it to the new ID.
+    pub fn correct_match(&mut self, show_id: &str, metadata: AnimeMetadata) -> bool {
+        let Some(show) = self.shows.iter_mut().find(|s| s.id == show_id) else {
+            return false;
+        };
+        let old_id = show.metadata.as_ref().map(|m| m.id);
+        let title = parser::make_show_title(&show.title).to_lowercase();
+        let season = parser::parse_season_number(&show.title).unwrap_or(1);
+        for series in &mut self.tracked_shows {
+            // Other seasons tracked under the same title keep their own entry
+            let linked = (old_id.is_some() && series.metadata_id == old_id)
+                || (series.title.to_lowercase() == title && series.season == season);
+            if linked {
+                series.metadata_id = Some(metadata.id);
+                series.cached_metadata = Some(metadata.clone());
+            }
+        }
+
+        show.metadata = Some(metadata);
+        show.metadata_locked = true;
+        // The old entry's episode list doesn't apply any more
+        show.episode_info.clear();
+        self.dismissed_matches.retain(|id| id != show_id);
+        true
+    }
+
+    /// Forget torrents miru added that aren't in the client's full list any
+    /// more, removed by a seeding policy or by hand. Returns whether any
+    /// were dropped.
+    pub fn prune_added_torrents<'a>(&mut self, present: impl IntoIterator<Item = &'a str>) -> bool {
+        let present: HashSet<String> = present.into_iter().map(str::to_lowercase).collect();
+        let before = self.added_torrents.len();
+        self.added_torrents.retain(|hash| present.contains(hash));
+        self.added_torrents.len() != before
+    }
+
     pub fn get_show(&self, id: &str) -> Option<&Show> {
         self.shows.iter().find(|s| s.id == id)
     }
@@ -125,13 +219,66 @@ impl Library {
         }
     }
 
-    pub fn mark_watched(&mut self, show_id: &str, episode_num: u32) {
-        if let Some(show) = self.get_show_mut(show_id) {
-            if let Some(ep) = show.get_episode_mut(episode_num) {
+    /// Mark an episode watched. For shows matched to MAL, returns the
+    /// progress (highest watched episode) to push to the user's list.
+    pub fn mark_watched(&mut self, show_id: &str, episode_num: u32) -> Option<ProgressUpdate> {
+        let show = self.get_show_mut(show_id)?;
+        let ep = show.get_episode_mut(episode_num)?;
+        ep.watched = true;
+        ep.last_position = 0;
+
+        let metadata = show.metadata.as_ref()?;
+        let episodes_watched = show
+            .episodes
+            .iter()
+            .filter(|e| e.watched)
+            .map(|e| e.number)
+            .max()?;
+        Some(ProgressUpdate {
+            provider: metadata.provider,
+            anime_id: metadata.id,
+            episodes_watched,
+            total_episodes: metadata.episodes.or(show.total_episodes),
+            score: None,
+        })
+    }
+
+    /// Catch up with progress made elsewhere: mark every episode up to
+    /// `episodes_watched` as watched
+    pub fn mark_watched_through(
+        &mut self,
+        provider: ProviderKind,
+        anime_id: u64,
+        episodes_watched: u32,
+    ) -> bool {
+        let mut changed = false;
+        for show in &mut self.shows {
+            if show
+                .metadata
+                .as_ref()
+                .is_none_or(|m| m.provider != provider || m.id != anime_id)
+            {
+                continue;
+            }
+            for ep in show
+                .episodes
+                .iter_mut()
+                .filter(|e| e.number <= episodes_watched && !e.watched)
+            {
                 ep.watched = true;
                 ep.last_position = 0;
+                changed = true;
             }
         }
+        changed
+    }
+
+    pub fn binding_for(&self, hash: &str, name: &str) -> Option<&models::DownloadBinding> {
+        self.bindings.iter().find(|b| b.matches(hash, name))
+    }
+
+    pub fn remove_binding(&mut self, hash: &str, name: &str) {
+        self.bindings.retain(|b| !b.matches(hash, name));
     }
 
     pub fn is_archived(&self, show_id: &str) -> bool {
@@ -205,3 +352,95 @@ impl Library {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library::models::TrackedSeries;
+
+    fn metadata(id: u64) -> AnimeMetadata {
+        AnimeMetadata {
+            id,
+            title: format!("Entry {}", id),
+            ..AnimeMetadata::default()
+        }
+    }
+
+    fn series(id: &str, season: u32, metadata_id: Option<u64>) -> TrackedSeries {
+        TrackedSeries {
+            id: id.to_string(),
+            title: "Frieren".to_string(),
+            query: "Frieren".to_string(),
+            season,
+            metadata_id,
+            ..TrackedSeries::default()
+        }
+    }
+
+    #[test]
+    fn test_prune_added_torrents() {
+        let mut library = Library {
+            added_torrents: vec!["aaaa".to_string(), "bbbb".to_string()],
+            ..Library::default()
+        };
+        assert!(!library.prune_added_torrents(["AAAA", "bbbb", "cccc"]));
+        assert!(library.prune_added_torrents(["bbbb"]));
+        assert_eq!(library.added_torrents, ["bbbb"]);
+    }
+
+    #[test]
+    fn test_correct_match_locks_the_show() {
+        let mut show = Show::new("frieren", "Frieren", "/anime/Frieren".into());
+        show.metadata = Some(metadata(1));
+        show.episode_info = vec![Default::default()];
+        let mut library = Library {
+            shows: vec![show],
+            dismissed_matches: vec!["frieren".to_string()],
+            ..Library::default()
+        };
+
+        assert!(library.correct_match("frieren", metadata(52991)));
+        let show = library.get_show("frieren").unwrap();
+        assert_eq!(show.metadata.as_ref().map(|m| m.id), Some(52991));
+        assert!(show.metadata_locked);
+        assert!(show.episode_info.is_empty());
+        assert!(library.dismissed_matches.is_empty());
+
+        assert!(!library.correct_match("missing", metadata(1)));
+    }
+
+    #[test]
+    fn test_correct_match_moves_only_linked_series() {
+        let mut show = Show::new("frieren", "Frieren", "/anime/Frieren".into());
+        show.metadata = Some(metadata(1));
+        let mut library = Library {
+            shows: vec![show],
+            tracked_shows: vec![
+                // Pointed at the old match
+                series("s1", 1, Some(1)),
+                // Same title, not linked to anything yet
+                series("s1-new", 1, None),
+                // Season 2, tracked under the same title
+                series("s2", 2, Some(2)),
+                series("s2-new", 2, None),
+            ],
+            ..Library::default()
+        };
+
+        library.correct_match("frieren", metadata(52991));
+        let ids: Vec<Option<u64>> = library
+            .tracked_shows
+            .iter()
+            .map(|s| s.metadata_id)
+            .collect();
+        assert_eq!(ids, [Some(52991), Some(52991), Some(2), None]);
+        assert_eq!(
+            library.tracked_shows[1]
+                .cached_metadata
+                .as_ref()
+                .map(|m| m.id),
+            Some(52991)
+        );
+        assert!(library.tracked_shows[3].cached_metadata.is_none());
+    }
+}
@@ -1,7 +1,10 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
+use super::numbering::EpisodeNumbering;
+use super::release::ReleaseInfo;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Show {
     pub id: String,
@@ -19,6 +22,14 @@ pub struct Show {
     pub metadata: Option<crate::metadata::AnimeMetadata>,
     #[serde(default)]
     pub cover_path: Option<PathBuf>,
+    /// `metadata` was picked by hand; automatic matching leaves it alone
+    #[serde(default)]
+    pub metadata_locked: bool,
+    #[serde(default)]
+    pub episode_info: Vec<crate::metadata::EpisodeMetadata>,
+    /// How season-relative episode numbers map to absolute ones
+    #[serde(default)]
+    pub numbering: Option<EpisodeNumbering>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +42,10 @@ pub struct Episode {
     pub last_position: u64,
     #[serde(default)]
     pub relative_path: Option<String>,
+    /// The release this file was imported from; file names lose the group
+    /// and quality tags when they're cleaned up on import
+    #[serde(default)]
+    pub release: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,6 +76,60 @@ pub struct TrackedSeries {
     pub metadata_id: Option<u64>,
     #[serde(default)]
     pub cached_metadata: Option<crate::metadata::AnimeMetadata>,
+    /// Overrides `[torrent.seeding]` for this series' downloads
+    #[serde(default)]
+    pub seeding: Option<crate::config::SeedingPolicy>,
+    /// A `[scoring.profiles]` entry to rank this series' releases with
+    #[serde(default)]
+    pub scoring_profile: Option<String>,
+    /// Also download better releases of episodes already in the library
+    #[serde(default)]
+    pub upgrade: bool,
+}
+
+/// What an ingested download belongs to, used to pre-fill the move dialog
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BindTarget {
+    Show(String),
+    Series(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadBinding {
+    /// Torrent name as the client reports it
+    pub name: String,
+    #[serde(default)]
+    pub hash: Option<String>,
+    pub target: BindTarget,
+    /// The season, and episode within it, this download upgrades. Its old
+    /// file is deleted once the new one is imported.
+    #[serde(default)]
+    pub replaces: Option<(u32, u32)>,
+}
+
+impl DownloadBinding {
+    /// Match by hash when we know it, by name otherwise
+    pub fn matches(&self, hash: &str, name: &str) -> bool {
+        match &self.hash {
+            Some(h) if !h.is_empty() => h.eq_ignore_ascii_case(hash),
+            _ => self.name.eq_ignore_ascii_case(name),
+        }
+    }
+}
+
+/// A show from the user's list they mean to get to, imported without
+/// downloading anything
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WatchlistEntry {
+    pub provider: crate::metadata::ProviderKind,
+    pub anime_id: u64,
+    pub title: String,
+    #[serde(default)]
+    pub status: crate::metadata::ListStatus,
+    #[serde(default)]
+    pub num_episodes: Option<u32>,
+    #[serde(default)]
+    pub num_watched: u32,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
@@ -99,6 +168,9 @@ impl Show {
             specials: Vec::new(),
             metadata: None,
             cover_path: None,
+            metadata_locked: false,
+            episode_info: Vec::new(),
+            numbering: None,
         }
     }
 
@@ -137,6 +209,52 @@ impl Show {
         None
     }
 
+    /// Like `next_unwatched`, but passing over episodes flagged as filler
+    /// or recap
+    pub fn next_unwatched_skipping_filler(&self) -> Option<&Episode> {
+        let flat = self.episodes.iter().map(|e| (None, e));
+        let seasonal = self
+            .seasons
+            .iter()
+            .flat_map(|s| s.episodes.iter().map(move |e| (Some(s.number), e)));
+        flat.chain(seasonal)
+            .find(|&(season, e)| {
+                !e.watched
+                    && !self
+                        .episode_info(season, e.number)
+                        .is_some_and(|i| i.is_skippable())
+            })
+            .map(|(_, e)| e)
+    }
+
+    /// Title, air date and flags for an episode, if known. `season` is
+    /// `None` for shows that aren't split into season folders. Providers
+    /// list episodes without seasons, so a season folder's episode falls
+    /// back to its absolute number when the numbering is known.
+    pub fn episode_info(
+        &self,
+        season: Option<u32>,
+        number: u32,
+    ) -> Option<&crate::metadata::EpisodeMetadata> {
+        let exact = self
+            .episode_info
+            .iter()
+            .find(|i| i.number == number && i.season == season);
+        let Some(season) = season else {
+            return exact;
+        };
+        exact.or_else(|| {
+            let absolute = self
+                .numbering
+                .as_ref()
+                .and_then(|n| n.offset(season))
+                .map_or(number, |offset| offset + number);
+            self.episode_info
+                .iter()
+                .find(|i| i.season.is_none() && i.number == absolute)
+        })
+    }
+
     pub fn get_episode(&self, number: u32) -> Option<&Episode> {
         self.episodes.iter().find(|e| e.number == number)
     }
@@ -163,6 +281,58 @@ impl Show {
             .and_then(|s| s.episodes.iter_mut().find(|e| e.number == episode_num))
     }
 
+    /// Episode `number` of `season`, from the season's folder or, numbered
+    /// absolutely, from the show's own episodes
+    pub fn find_episode(&self, season: u32, number: u32) -> Option<&Episode> {
+        if let Some(episode) = self.get_season_episode(season, number) {
+            return Some(episode);
+        }
+        let absolute = self
+            .numbering
+            .as_ref()
+            .and_then(|n| n.offset(season))
+            .map_or(number, |offset| offset + number);
+        self.get_episode(absolute)
+    }
+
+    /// The old file an upgrade of episode `number` of `season`, imported
+    /// at `imported`, replaces. `None` if the show doesn't have the episode,
+    /// the upgrade landed outside the show's folder, or it overwrote the
+    /// old file already.
+    pub fn replaced_file(&self, season: u32, number: u32, imported: &Path) -> Option<PathBuf> {
+        if !imported.starts_with(&self.path) {
+            return None;
+        }
+        let old_path = self.find_episode(season, number)?.full_path(&self.path);
+        (old_path != imported).then_some(old_path)
+    }
+
+    /// Renumber episodes by `numbering` after a scan: files in the show's
+    /// own folder get absolute numbers, files in season folders numbers
+    /// within their season
+    pub fn apply_numbering(&mut self) {
+        let Some(numbering) = &self.numbering else {
+            return;
+        };
+        for episode in &mut self.episodes {
+            let release = ReleaseInfo::parse(&episode.filename);
+            if release.episode() == Some(episode.number) {
+                episode.number = numbering.absolute(release.season, episode.number, 1);
+            }
+        }
+        self.episodes.sort_by_key(|e| e.number);
+        for season in &mut self.seasons {
+            for episode in &mut season.episodes {
+                let (number, relative) =
+                    numbering.season_episode(None, episode.number, season.number);
+                if number == season.number {
+                    episode.number = relative;
+                }
+            }
+            season.episodes.sort_by_key(|e| e.number);
+        }
+    }
+
     pub fn all_episodes(&self) -> impl Iterator<Item = &Episode> {
         self.episodes
             .iter()
@@ -179,6 +349,7 @@ impl Episode {
             watched: false,
             last_position: 0,
             relative_path: None,
+            release: None,
         }
     }
 
@@ -193,6 +364,7 @@ impl Episode {
             watched: false,
             last_position: 0,
             relative_path: Some(relative_path.into()),
+            release: None,
         }
     }
 
@@ -204,3 +376,78 @@ impl Episode {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::EpisodeMetadata;
+
+    fn filler(number: u32) -> EpisodeMetadata {
+        EpisodeMetadata {
+            number,
+            filler: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_skip_filler_in_season_folders() {
+        let mut show = Show::new("show", "Show", PathBuf::from("/anime/Show"));
+        show.seasons = (1..=2)
+            .map(|number| Season {
+                number,
+                folder_name: format!("Season {}", number),
+                path: PathBuf::from(format!("/anime/Show/Season {}", number)),
+                episodes: (1..=3)
+                    .map(|e| Episode::new(e, format!("S{:02}E{:02}.mkv", number, e)))
+                    .collect(),
+            })
+            .collect();
+        for episode in &mut show.seasons[0].episodes {
+            episode.watched = true;
+        }
+        // Provider metadata has no seasons: S02E01 is absolute episode 4
+        show.episode_info = vec![filler(4)];
+        show.numbering = Some(EpisodeNumbering {
+            seasons: vec![3, 3],
+            manual: true,
+        });
+
+        let next = show.next_unwatched_skipping_filler().unwrap();
+        assert_eq!(next.filename, "S02E02.mkv");
+        assert!(show.episode_info(Some(2), 1).is_some_and(|i| i.filler));
+        assert!(show.episode_info(Some(1), 1).is_none());
+
+        // Without numbering the provider's numbers are taken as the season's
+        show.numbering = None;
+        show.episode_info = vec![filler(1)];
+        let next = show.next_unwatched_skipping_filler().unwrap();
+        assert_eq!(next.filename, "S02E02.mkv");
+    }
+
+    #[test]
+    fn test_upgrade_replaces_file_in_season_folder() {
+        let mut show = Show::new("show", "Show", PathBuf::from("/anime/Show"));
+        show.seasons = vec![Season {
+            number: 2,
+            folder_name: "Season 2".to_string(),
+            path: PathBuf::from("/anime/Show/Season 2"),
+            episodes: vec![Episode::with_relative_path(
+                5,
+                "[SubsPlease] Show S02E05 (720p).mkv",
+                "Season 2",
+            )],
+        }];
+
+        let old = PathBuf::from("/anime/Show/Season 2/[SubsPlease] Show S02E05 (720p).mkv");
+        let imported = Path::new("/anime/Show/Season 2/[SubsPlease] Show S02E05 (1080p).mkv");
+        assert_eq!(show.replaced_file(2, 5, imported), Some(old.clone()));
+        // Already overwritten, or landed somewhere else entirely
+        assert_eq!(show.replaced_file(2, 5, &old), None);
+        assert_eq!(
+            show.replaced_file(2, 5, Path::new("/downloads/Show S02E05.mkv")),
+            None
+        );
+        assert_eq!(show.replaced_file(1, 5, imported), None);
+    }
+}
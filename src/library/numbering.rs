@@ -0,0 +1,188 @@
+//! Absolute episode numbers against season-relative ones. Long-running
+//! shows come out as `Show - 1071` from some groups and `Show S21E23` from
+//! others; a show's numbering says how long each season is, so either can
+//! be turned into the other.
+
+use serde::{Deserialize, Serialize};
+
+/// Episode counts of a show's seasons
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EpisodeNumbering {
+    /// Episodes in each season, season 1 first. The season after the last
+    /// one listed runs on indefinitely.
+    pub seasons: Vec<u32>,
+    /// Written by hand in `library.toml`; never replaced from metadata
+    #[serde(default)]
+    pub manual: bool,
+}
+
+impl EpisodeNumbering {
+    /// From the episode counts of each season's metadata. Needs every season
+    /// from 1 up, though the last may still be airing with no count yet.
+    pub fn from_counts(counts: impl IntoIterator<Item = (u32, Option<u32>)>) -> Option<Self> {
+        let mut counts: Vec<(u32, Option<u32>)> = counts.into_iter().collect();
+        counts.sort_by_key(|(season, _)| *season);
+        counts.dedup_by_key(|(season, _)| *season);
+
+        let mut seasons = Vec::new();
+        for (i, (season, count)) in counts.iter().enumerate() {
+            if *season != i as u32 + 1 {
+                return None;
+            }
+            match count {
+                Some(count) if *count > 0 => seasons.push(*count),
+                _ if i + 1 == counts.len() => break,
+                _ => return None,
+            }
+        }
+        // A single season has nothing to map
+        (counts.len() > 1).then_some(Self {
+            seasons,
+            manual: false,
+        })
+    }
+
+    /// Episodes before `season` starts, if its place is known
+    pub fn offset(&self, season: u32) -> Option<u32> {
+        let before = season.checked_sub(1)? as usize;
+        (before <= self.seasons.len()).then(|| self.seasons[..before].iter().sum())
+    }
+
+    /// `absolute` as a season and an episode within it
+    pub fn to_season(&self, absolute: u32) -> (u32, u32) {
+        let mut start = 0;
+        for (i, len) in self.seasons.iter().enumerate() {
+            if absolute <= start + len {
+                return (i as u32 + 1, absolute - start);
+            }
+            start += len;
+        }
+        (self.seasons.len() as u32 + 1, absolute - start)
+    }
+
+    /// Season and episode for a release numbered `episode`, in `season` if
+    /// it says so and in `assumed` (the season tracked, or the folder the
+    /// file is in) otherwise. A number past both the season's start and its
+    /// length is taken to be absolute.
+    pub fn season_episode(&self, season: Option<u32>, episode: u32, assumed: u32) -> (u32, u32) {
+        let season = season.unwrap_or(assumed);
+        let Some(offset) = self.offset(season) else {
+            return (season, episode);
+        };
+        let len = self.seasons.get(season as usize - 1);
+        if episode > offset && len.is_none_or(|len| episode > *len) {
+            self.to_season(episode)
+        } else {
+            (season, episode)
+        }
+    }
+
+    /// The absolute number for `episode`, read as in `season_episode`
+    pub fn absolute(&self, season: Option<u32>, episode: u32, assumed: u32) -> u32 {
+        let (season, episode) = self.season_episode(season, episode, assumed);
+        self.offset(season)
+            .map_or(episode, |offset| offset + episode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbering(seasons: &[u32]) -> EpisodeNumbering {
+        EpisodeNumbering {
+            seasons: seasons.to_vec(),
+            manual: true,
+        }
+    }
+
+    #[test]
+    fn test_absolute_and_season_numbers() {
+        let n = numbering(&[12, 11, 13]);
+        assert_eq!(n.offset(1), Some(0));
+        assert_eq!(n.offset(3), Some(23));
+        assert_eq!(n.offset(4), Some(36));
+        assert_eq!(n.offset(5), None);
+
+        assert_eq!(n.to_season(5), (1, 5));
+        assert_eq!(n.to_season(13), (2, 1));
+        assert_eq!(n.to_season(36), (3, 13));
+        // Past the last listed season: the one still airing
+        assert_eq!(n.to_season(40), (4, 4));
+
+        // `S02 - 05` and `- 17` in season 2 are the same episode
+        assert_eq!(n.season_episode(Some(2), 5, 1), (2, 5));
+        assert_eq!(n.season_episode(None, 17, 2), (2, 5));
+        assert_eq!(n.season_episode(None, 5, 2), (2, 5));
+        assert_eq!(n.absolute(Some(2), 5, 1), 17);
+        assert_eq!(n.absolute(Some(3), 13, 1), 36);
+        // Already absolute, whatever the season says
+        assert_eq!(n.absolute(Some(3), 30, 1), 30);
+        assert_eq!(n.absolute(None, 30, 1), 30);
+
+        // Seasons the map doesn't reach are left alone
+        assert_eq!(n.season_episode(Some(6), 3, 1), (6, 3));
+    }
+
+    #[test]
+    fn test_long_running_show() {
+        let n = numbering(&[
+            61, 16, 14, 39, 13, 52, 33, 35, 73, 45, 26, 14, 100, 57, 62, 49, 118, 33, 98, 124,
+        ]);
+        let offset = n.offset(21).unwrap();
+        assert_eq!(n.season_episode(None, 1071, 21), (21, 1071 - offset));
+        assert_eq!(n.absolute(Some(21), 1071 - offset, 1), 1071);
+        assert_eq!(n.season_episode(None, 23, 21), (21, 23));
+    }
+
+    #[test]
+    fn test_show_episodes_are_renumbered() {
+        use crate::library::{Episode, Season, Show};
+
+        let mut show = Show::new("show", "Show", "/anime/Show".into());
+        show.numbering = Some(numbering(&[12, 11]));
+        show.episodes = vec![
+            Episode::new(3, "[Foo] Show - 03 [1080p].mkv"),
+            Episode::new(4, "Show.S02E04.1080p.WEB.x264-GRP.mkv"),
+        ];
+        show.seasons = vec![Season {
+            number: 3,
+            folder_name: "Season 3".to_string(),
+            path: "/anime/Show/Season 3".into(),
+            episodes: vec![Episode::with_relative_path(
+                25,
+                "[Foo] Show - 25 [1080p].mkv",
+                "Season 3",
+            )],
+        }];
+        show.apply_numbering();
+
+        let numbers: Vec<u32> = show.episodes.iter().map(|e| e.number).collect();
+        assert_eq!(numbers, vec![3, 16]);
+        assert_eq!(show.seasons[0].episodes[0].number, 2);
+
+        assert_eq!(show.find_episode(2, 4).unwrap().number, 16);
+        assert_eq!(show.find_episode(1, 3).unwrap().number, 3);
+        assert_eq!(show.find_episode(3, 2).unwrap().number, 2);
+        assert!(show.find_episode(2, 3).is_none());
+    }
+
+    #[test]
+    fn test_from_metadata_counts() {
+        let n = EpisodeNumbering::from_counts([(2, Some(11)), (1, Some(12)), (3, None)]).unwrap();
+        assert_eq!(n.seasons, vec![12, 11]);
+        assert!(!n.manual);
+        assert_eq!(n.to_season(30), (3, 7));
+
+        // A gap, an unknown count before the end, or just one season
+        assert_eq!(
+            EpisodeNumbering::from_counts([(1, Some(12)), (3, Some(12))]),
+            None
+        );
+        assert_eq!(
+            EpisodeNumbering::from_counts([(1, None), (2, Some(12))]),
+            None
+        );
+        assert_eq!(EpisodeNumbering::from_counts([(1, Some(12))]), None);
+    }
+}
@@ -1,6 +1,8 @@
 use regex::Regex;
 use std::sync::LazyLock;
 
+use super::release::ReleaseInfo;
+
 enum CaptureKind {
     Numeric,
     RomanNumeral,
@@ -32,47 +34,13 @@ static SEASON_PATTERNS: LazyLock<Vec<(Regex, CaptureKind)>> = LazyLock::new(|| {
     ]
 });
 
-static EPISODE_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
-    vec![
-        // [SubGroup] Show Name - 01 [1080p].mkv or Show Name - 01v2.mkv
-        // Matches: " - " followed by episode number, optional version suffix
-        Regex::new(r"- (\d{1,4})(?:v\d)?(?:\s*[\[\(]|\.|\s|$)").unwrap(),
-        // S01E01 format (common for western naming)
-        Regex::new(r"[Ss]\d{1,2}[Ee](\d{1,3})").unwrap(),
-        // Show.Name.01.mkv or Show_Name_01.mkv
-        // Matches: separator followed by bare number before extension
-        Regex::new(r"[._\s](\d{1,3})[._\s]*(?:\[|$|\.)").unwrap(),
-        // Bare number at start: 01.mkv, 01 - title.mkv
-        Regex::new(r"^(\d{1,3})(?:\s*[-._]|\.mkv|\.mp4|\.avi)").unwrap(),
-        // Episode 01 or Ep 01 or EP01
-        Regex::new(r"[Ee][Pp](?:isode)?[\s._]*(\d{1,3})").unwrap(),
-        // E01 format (common short form)
-        Regex::new(r"(?:[-._\s]|^)[Ee](\d{1,4})(?:v\d)?(?:[._\s]|$)").unwrap(),
-    ]
-});
-
 const VIDEO_EXTENSIONS: &[&str] = &["mkv", "mp4", "avi", "webm", "m4v", "mov"];
 
 const COMPRESSED_EXTENSION: &str = ".zst";
 
+/// The episode a file holds; see [`ReleaseInfo`] for the rest of its name
 pub fn parse_episode_number(filename: &str) -> Option<u32> {
-    let filename = filename
-        .strip_suffix(COMPRESSED_EXTENSION)
-        .unwrap_or(filename);
-
-    for pattern in EPISODE_PATTERNS.iter() {
-        if let Some(caps) = pattern.captures(filename) {
-            if let Some(num_match) = caps.get(1) {
-                if let Ok(num) = num_match.as_str().parse::<u32>() {
-                    // Sanity check: episode numbers are usually 1-999
-                    if num > 0 && num < 1000 {
-                        return Some(num);
-                    }
-                }
-            }
-        }
-    }
-    None
+    ReleaseInfo::parse(filename).episode()
 }
 
 pub fn parse_season_number(title: &str) -> Option<u32> {
@@ -156,19 +124,6 @@ pub fn make_show_title(name: &str) -> String {
         .join(" ")
 }
 
-pub fn parse_release_group(filename: &str) -> Option<String> {
-    Regex::new(r"^\[([^\]]+)\]")
-        .unwrap()
-        .captures(filename)
-        .map(|c| c.get(1).unwrap().as_str().to_string())
-}
-
-pub fn parse_quality(filename: &str) -> Option<String> {
-    let re = Regex::new(r"((?:360|480|720|1080|2160)[pP]|4[kK])").unwrap();
-    re.captures(filename)
-        .map(|c| c.get(1).unwrap().as_str().to_lowercase())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,536 @@
+//! Release names as uploaders write them, parsed in one pass:
+//! `[Group] Title - 05v2 (1080p) [ABCD1234].mkv`,
+//! `Title.S01E05.1080p.WEB.x264-GROUP.mkv`, `Title (01-12) [BD 1080p]`.
+//!
+//! Bracketed tags are lifted out first. What's left is split into the title
+//! and everything after it; codecs, sources and languages are only looked
+//! for after the title, so a show called "Web Ghosts" isn't a WEB release.
+
+use regex::Regex;
+use serde::Serialize;
+use std::sync::LazyLock;
+
+use super::parser;
+
+const EXTENSIONS: &[&str] = &[
+    ".zst", ".mkv", ".mp4", ".avi", ".webm", ".m4v", ".mov", ".torrent",
+];
+
+static TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[([^\[\]]*)\]|\(([^()]*)\)|【([^【】]*)】").unwrap());
+
+static SEASON_EPISODE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\bS(\d{1,2})\s*E(\d{1,4})(?:v(\d))?(?:\s*-\s*(?:S\d{1,2})?E?(\d{1,4}))?\b")
+        .unwrap()
+});
+static SEASON_ONLY: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)\bS(\d{1,2})\b").unwrap());
+/// `- 05`, `- 05v2`, `- 01 ~ 12`
+static DASH_EPISODE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|\s)-\s*(\d{1,4})(?:v(\d))?(?:\s*[-~]\s*(\d{1,4})(?:v\d)?)?\b").unwrap()
+});
+/// `E05`, `EP05`, `Ep.05`, `Episode 5`, `#5`
+static PREFIXED_EPISODE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(?:\b(?:episode|ep\.?|e)\s*|#)(\d{1,4})(?:v(\d))?\b").unwrap()
+});
+/// `01-12`, `01 ~ 12`
+static RANGE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?:^|\s)(\d{1,4})\s*[-~]\s*(\d{1,4})(?:\s|$)").unwrap());
+static LEADING_NUMBER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(\d{1,4})(?:v(\d))?(?:\s+-\s+|\s*$)").unwrap());
+static TRAILING_NUMBER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\s(\d{1,4})(?:v(\d))?$").unwrap());
+
+static RESOLUTION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\b(?:\d{3,4}x(\d{3,4})|(\d{3,4})[pi]|(4k|uhd))\b").unwrap());
+static VIDEO_CODEC: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(?:(hevc|x[\s.]?265|h[\s.]?265)|(av1)|(avc|x[\s.]?264|h[\s.]?264))\b")
+        .unwrap()
+});
+static AUDIO_CODEC: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(?:(ddp|e-?ac-?3|dd\+)|(ac-?3|dd\d)|(aac)|(flac)|(opus)|(dts)|(truehd)|(mp3))",
+    )
+    .unwrap()
+});
+static SOURCE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(?:(bd|bdrip|bd-?remux|bdmv|blu-?ray)|(web|web-?dl|web-?rip|cr|amzn|nf|dsnp|hidive|b-global|adn)|(dvd|dvdrip|dvd-?remux)|(hdtv|tv|tvrip))\b",
+    )
+    .unwrap()
+});
+static VERSION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)(?:\d|\b)v(\d)\b").unwrap());
+static REPACK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\b(?:repack|proper)\b").unwrap());
+static BATCH: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\b(?:batch|complete(?:\s+series)?)\b").unwrap());
+static CHECKSUM: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^[0-9A-Fa-f]{8}$").unwrap());
+static BIT_DEPTH: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\b\d{1,2}-?bits?\b").unwrap());
+
+/// Language and subtitle tags, by what they're reported as
+static LANGUAGES: LazyLock<Vec<(Regex, &'static str)>> = LazyLock::new(|| {
+    [
+        (r"dual[\s._-]?audio", "dual-audio"),
+        (r"multi[\s._-]?audio", "multi-audio"),
+        (r"multi[\s._-]?subs?", "multi-subs"),
+        (r"eng(?:lish)?(?:[\s._-]?(?:subs?|dub))?", "eng"),
+        (r"jpn|jap(?:anese)?", "jpn"),
+        (r"ger(?:man)?|deu", "ger"),
+        (r"fre(?:nch)?|fra|vostfr", "fre"),
+        (r"spa(?:nish)?|esp", "spa"),
+        (r"ita(?:lian)?", "ita"),
+        (r"por(?:tuguese)?|pt-?br", "por"),
+        (r"rus(?:sian)?", "rus"),
+        (r"chi(?:nese)?|chs|cht|big5", "chi"),
+        (r"kor(?:ean)?", "kor"),
+        (r"ara(?:bic)?", "ara"),
+    ]
+    .into_iter()
+    .map(|(pattern, tag)| {
+        (
+            Regex::new(&format!(r"(?i)\b(?:{})\b", pattern)).unwrap(),
+            tag,
+        )
+    })
+    .collect()
+});
+
+/// Everything a release name says about itself
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ReleaseInfo {
+    pub title: String,
+    /// Other names given for the show, after a ` / ` or in brackets
+    pub alt_titles: Vec<String>,
+    pub group: Option<String>,
+    pub season: Option<u32>,
+    /// First and last episode; the same number twice for a single episode
+    pub episodes: Option<(u32, u32)>,
+    /// 1 unless the release is marked `v2` and so on
+    pub version: u32,
+    /// Marked REPACK or PROPER
+    pub repack: bool,
+    /// `1080p`, `2160p`, ...
+    pub resolution: Option<String>,
+    /// `hevc`, `av1` or `x264`
+    pub video_codec: Option<&'static str>,
+    /// `aac`, `flac`, `opus`, `ac3`, `eac3`, `dts`, `truehd` or `mp3`
+    pub audio_codec: Option<&'static str>,
+    /// `web`, `bd`, `dvd` or `tv`
+    pub source: Option<&'static str>,
+    /// The CRC32 in `[ABCD1234]`, uppercase
+    pub checksum: Option<String>,
+    /// `eng`, `jpn`, `multi-subs`, `dual-audio`, ...
+    pub languages: Vec<&'static str>,
+    pub is_batch: bool,
+}
+
+/// A bracketed tag and where it sat in the name once tags were taken out
+struct Tag {
+    text: String,
+    offset: usize,
+    leading: bool,
+}
+
+/// Where the episode marker was found in the title part
+struct EpisodeMatch {
+    start: usize,
+    end: usize,
+    episodes: (u32, u32),
+    version: Option<u32>,
+}
+
+impl ReleaseInfo {
+    pub fn parse(name: &str) -> ReleaseInfo {
+        let mut info = ReleaseInfo {
+            version: 1,
+            ..ReleaseInfo::default()
+        };
+        let name = strip_extensions(name.trim());
+
+        let (mut rest, tags) = split_tags(name);
+        if !rest.contains(' ') || rest.matches('.').count() > rest.matches(' ').count() {
+            // Scene style: dots for spaces
+            rest = rest.replace('.', " ");
+        }
+        rest = rest.replace('_', " ");
+
+        let mut tags = tags.into_iter().peekable();
+        if let Some(first) = tags.next_if(|t| t.leading && !is_meta(&t.text)) {
+            info.group = Some(first.text.trim().to_string());
+        }
+        let tags: Vec<Tag> = tags.collect();
+
+        if info.group.is_none()
+            && let Some((kept, group)) = scene_group(&rest)
+        {
+            info.group = Some(group);
+            rest = kept;
+        }
+        let rest = collapse_spaces(&rest);
+
+        let season_episode = SEASON_EPISODE.captures(&rest);
+        let episode = match &season_episode {
+            Some(caps) => {
+                info.season = caps[1].parse().ok();
+                let first: u32 = caps[2].parse().unwrap_or(0);
+                let last = caps
+                    .get(4)
+                    .and_then(|m| m.as_str().parse().ok())
+                    .unwrap_or(first);
+                let whole = caps.get(0).unwrap();
+                Some(EpisodeMatch {
+                    start: whole.start(),
+                    end: whole.end(),
+                    episodes: (first, last),
+                    version: caps.get(3).and_then(|m| m.as_str().parse().ok()),
+                })
+            }
+            // Bare numbers after the metadata starts are codecs and the like
+            None => find_episode(&rest[..first_meta(&rest).unwrap_or(rest.len())]),
+        };
+
+        // The title ends at the first episode, season or metadata marker
+        let mut title_end = rest.len();
+        if let Some(ep) = &episode {
+            title_end = title_end.min(ep.start);
+        }
+        if info.season.is_none()
+            && let Some(m) = SEASON_ONLY.captures(&rest)
+        {
+            let whole = m.get(0).unwrap();
+            title_end = title_end.min(whole.start());
+            info.season = m[1].parse().ok();
+        }
+        if let Some(meta) = first_meta(&rest) {
+            title_end = title_end.min(meta);
+        }
+        if info.season.is_none() {
+            info.season = parser::parse_season_number(&rest[..title_end]).or_else(|| {
+                tags.iter()
+                    .find_map(|t| parser::parse_season_number(&t.text))
+            });
+        }
+
+        let title = rest[..title_end].trim_matches(|c: char| " -~_.:".contains(c));
+        let title = if title.is_empty() {
+            // `12 - Episode Title.mkv`: the name comes after the number
+            let after = episode.as_ref().map_or(title_end, |ep| ep.end);
+            let after = &rest[after.min(rest.len())..];
+            let end = first_meta(after).unwrap_or(after.len());
+            after[..end].trim_matches(|c: char| " -~_.:".contains(c))
+        } else {
+            title
+        };
+        let mut titles = title
+            .split(" / ")
+            .flat_map(|t| t.split(" | "))
+            .map(str::trim)
+            .filter(|t| !t.is_empty());
+        info.title = titles.next().unwrap_or_default().to_string();
+        info.alt_titles = titles.map(str::to_string).collect();
+
+        // Tags: checksums, episode ranges, other titles and metadata
+        let mut meta_zone = rest[title_end..].to_string();
+        for tag in &tags {
+            let text = tag.text.trim();
+            if CHECKSUM.is_match(text) && !looks_like_date(text) {
+                info.checksum = Some(text.to_uppercase());
+            } else if let Some(range) = tag_range(text)
+                && info.episodes.is_none()
+                && episode.is_none()
+            {
+                info.episodes = Some(range);
+            } else if tag.offset <= title_end
+                && !is_meta(text)
+                && !text.chars().all(|c| c.is_ascii_digit())
+                && parser::parse_season_number(text).is_none()
+            {
+                info.alt_titles.push(text.to_string());
+            } else {
+                meta_zone.push(' ');
+                meta_zone.push_str(text);
+            }
+        }
+
+        if let Some(ep) = &episode {
+            info.episodes = Some(ep.episodes);
+            if let Some(v) = ep.version {
+                info.version = v;
+            }
+        }
+        if let Some(caps) = VERSION.captures(&meta_zone)
+            && info.version == 1
+        {
+            info.version = caps[1].parse().unwrap_or(1);
+        }
+
+        info.resolution = RESOLUTION
+            .captures(&meta_zone)
+            .map(|c| match c.get(1).or(c.get(2)) {
+                Some(height) => format!("{}p", height.as_str()),
+                None => "2160p".to_string(),
+            });
+        info.video_codec = first_group(&VIDEO_CODEC, &meta_zone, &["hevc", "av1", "x264"]);
+        info.audio_codec = first_group(
+            &AUDIO_CODEC,
+            &meta_zone,
+            &["eac3", "ac3", "aac", "flac", "opus", "dts", "truehd", "mp3"],
+        );
+        info.source = first_group(&SOURCE, &meta_zone, &["bd", "web", "dvd", "tv"]);
+        info.repack = REPACK.is_match(&meta_zone);
+        for (pattern, language) in LANGUAGES.iter() {
+            if pattern.is_match(&meta_zone) && !info.languages.contains(language) {
+                info.languages.push(language);
+            }
+        }
+
+        let is_range = info.episodes.is_some_and(|(first, last)| last > first);
+        let season_pack = info.episodes.is_none() && info.season.is_some();
+        info.is_batch = is_range || BATCH.is_match(&meta_zone) || season_pack;
+        info
+    }
+
+    /// The episode number, for single-episode releases
+    pub fn episode(&self) -> Option<u32> {
+        self.episodes
+            .filter(|(first, last)| first == last)
+            .map(|(first, _)| first)
+    }
+
+    pub fn is_dual_audio(&self) -> bool {
+        self.languages.contains(&"dual-audio")
+    }
+}
+
+fn strip_extensions(mut name: &str) -> &str {
+    let has_extension = |name: &str, ext: &str| {
+        let cut = name.len().saturating_sub(ext.len());
+        cut > 0 && name.is_char_boundary(cut) && name[cut..].eq_ignore_ascii_case(ext)
+    };
+    while let Some(ext) = EXTENSIONS.iter().find(|ext| has_extension(name, ext)) {
+        name = &name[..name.len() - ext.len()];
+    }
+    name
+}
+
+/// The name without its bracketed tags, and the tags
+fn split_tags(name: &str) -> (String, Vec<Tag>) {
+    let mut rest = String::new();
+    let mut tags = Vec::new();
+    let mut last = 0;
+    for caps in TAG.captures_iter(name) {
+        let whole = caps.get(0).unwrap();
+        rest.push_str(&name[last..whole.start()]);
+        rest.push(' ');
+        let text = caps.get(1).or(caps.get(2)).or(caps.get(3)).unwrap();
+        tags.push(Tag {
+            text: text.as_str().to_string(),
+            offset: collapse_spaces(&rest).len(),
+            leading: name[..whole.start()].trim().is_empty(),
+        });
+        last = whole.end();
+    }
+    rest.push_str(&name[last..]);
+    (rest, tags)
+}
+
+fn collapse_spaces(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `Title S01E05 1080p WEB x264-GROUP`: the group follows the last
+/// metadata token
+fn scene_group(rest: &str) -> Option<(String, String)> {
+    let trimmed = rest.trim_end();
+    let mut tokens = trimmed.rsplit(' ');
+    let (meta, group) = tokens.next()?.rsplit_once('-')?;
+    // `H.264-GROUP` has become `H 264-GROUP` by now
+    let is_meta_token = is_meta(meta)
+        || tokens
+            .next()
+            .is_some_and(|t| is_meta(&format!("{} {}", t, meta)));
+    if group.is_empty() || !group.chars().all(|c| c.is_ascii_alphanumeric()) || !is_meta_token {
+        return None;
+    }
+    let kept = &trimmed[..trimmed.len() - group.len() - 1];
+    Some((kept.to_string(), group.to_string()))
+}
+
+/// Tags and tokens that describe the file rather than name anything
+fn is_meta(text: &str) -> bool {
+    let text = text.trim();
+    text.is_empty()
+        || CHECKSUM.is_match(text)
+        || tag_range(text).is_some()
+        || [
+            &*RESOLUTION,
+            &*VIDEO_CODEC,
+            &*AUDIO_CODEC,
+            &*SOURCE,
+            &*REPACK,
+            &*BATCH,
+            &*BIT_DEPTH,
+            &*VERSION,
+        ]
+        .iter()
+        .any(|re| re.is_match(text))
+        || LANGUAGES.iter().any(|(re, _)| re.is_match(text))
+}
+
+/// Start of the first marker that can't be part of a title. Sources
+/// aren't included: "TV" and "Web" turn up in titles.
+fn first_meta(text: &str) -> Option<usize> {
+    [&*RESOLUTION, &*VIDEO_CODEC, &*REPACK, &*BATCH, &*BIT_DEPTH]
+        .iter()
+        .filter_map(|re| re.find(text).map(|m| m.start()))
+        .min()
+}
+
+fn find_episode(rest: &str) -> Option<EpisodeMatch> {
+    let patterns: [&Regex; 5] = [
+        &DASH_EPISODE,
+        &PREFIXED_EPISODE,
+        &RANGE,
+        &LEADING_NUMBER,
+        &TRAILING_NUMBER,
+    ];
+    for pattern in patterns {
+        for caps in pattern.captures_iter(rest) {
+            let first: u32 = caps[1].parse().unwrap_or(0);
+            if !plausible_episode(&caps[1])
+                || counts_something_else(&rest[..caps.get(1).unwrap().start()])
+            {
+                continue;
+            }
+            let is_range = std::ptr::eq(pattern, &*RANGE);
+            let last_group = if is_range { 2 } else { 3 };
+            let last = caps
+                .get(last_group)
+                .filter(|m| plausible_episode(m.as_str()))
+                .and_then(|m| m.as_str().parse().ok())
+                .filter(|&last| last > first)
+                .unwrap_or(first);
+            if is_range && last == first {
+                continue;
+            }
+            let version = if is_range {
+                None
+            } else {
+                caps.get(2).and_then(|m| m.as_str().parse().ok())
+            };
+            let whole = caps.get(0).unwrap();
+            return Some(EpisodeMatch {
+                start: whole.start(),
+                end: whole.end(),
+                episodes: (first, last),
+                version,
+            });
+        }
+    }
+    None
+}
+
+/// Episode numbers, not years
+fn plausible_episode(digits: &str) -> bool {
+    let Ok(n) = digits.parse::<u32>() else {
+        return false;
+    };
+    n > 0 && !(digits.len() == 4 && (1950..2100).contains(&n))
+}
+
+/// `Season 2`, `Part 2`, `Vol. 2`: a number that isn't an episode
+fn counts_something_else(before: &str) -> bool {
+    let word = before.trim_end().rsplit(' ').next().unwrap_or_default();
+    ["season", "part", "cour", "vol", "vol.", "volume", "no."]
+        .iter()
+        .any(|w| word.eq_ignore_ascii_case(w))
+}
+
+/// `01-12` or `01 ~ 12` on its own in a tag
+fn tag_range(text: &str) -> Option<(u32, u32)> {
+    let caps = RANGE.captures(text.trim())?;
+    let whole = caps.get(0)?;
+    if whole.as_str().trim() != text.trim()
+        || !plausible_episode(&caps[1])
+        || !plausible_episode(&caps[2])
+    {
+        return None;
+    }
+    let (first, last) = (caps[1].parse().ok()?, caps[2].parse().ok()?);
+    (last > first).then_some((first, last))
+}
+
+fn looks_like_date(text: &str) -> bool {
+    text.chars().all(|c| c.is_ascii_digit()) && (text.starts_with("19") || text.starts_with("20"))
+}
+
+/// The name for whichever alternative of `re` matched first
+fn first_group(re: &Regex, text: &str, names: &[&'static str]) -> Option<&'static str> {
+    let caps = re.captures(text)?;
+    (1..caps.len())
+        .find(|&i| caps.get(i).is_some())
+        .and_then(|i| names.get(i - 1).copied())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/release-names.json");
+
+    /// Each case gives a name and the fields it should parse to; fields
+    /// left out aren't checked
+    #[derive(serde::Deserialize)]
+    struct Case {
+        name: String,
+        expect: serde_json::Map<String, serde_json::Value>,
+    }
+
+    #[test]
+    fn test_release_name_fixtures() {
+        let cases: Vec<Case> = serde_json::from_str(FIXTURE).unwrap();
+        let mut failures = Vec::new();
+        for case in &cases {
+            let parsed = serde_json::to_value(ReleaseInfo::parse(&case.name)).unwrap();
+            for (field, expected) in &case.expect {
+                if parsed.get(field) != Some(expected) {
+                    failures.push(format!(
+                        "{}\n    {}: expected {}, got {}",
+                        case.name,
+                        field,
+                        expected,
+                        parsed.get(field).unwrap_or(&serde_json::Value::Null)
+                    ));
+                }
+            }
+        }
+        assert!(
+            failures.is_empty(),
+            "{} of {} fields wrong:\n{}",
+            failures.len(),
+            cases.len(),
+            failures.join("\n")
+        );
+    }
+
+    #[test]
+    fn test_episode_is_none_for_ranges() {
+        let batch = ReleaseInfo::parse("[Judas] Frieren (01-28) [1080p]");
+        assert_eq!(batch.episodes, Some((1, 28)));
+        assert_eq!(batch.episode(), None);
+        assert!(batch.is_batch);
+    }
+
+    #[test]
+    fn test_version_and_repack() {
+        let version = |name| ReleaseInfo::parse(name).version;
+        assert_eq!(version("[SubsPlease] Frieren - 05v2 (1080p).mkv"), 2);
+        assert_eq!(version("[Judas] Frieren - E05v3 [1080p].mkv"), 3);
+        assert_eq!(version("[Foo] Frieren - 05 [v2][720p].mkv"), 2);
+        assert_eq!(version("[SubsPlease] Frieren - 05 (1080p).mkv"), 1);
+        // Not a version: part of a codec tag
+        assert_eq!(version("[Foo] Kaiju No. 8 - 05 [AV1].mkv"), 1);
+        assert!(ReleaseInfo::parse("Frieren.S01E05.REPACK.1080p.WEB.mkv").repack);
+        assert!(!ReleaseInfo::parse("[SubsPlease] Frieren - 05 (1080p).mkv").repack);
+    }
+}
@@ -0,0 +1,214 @@
+//! When episodes of airing shows are expected: for the calendar, and to time
+//! release checks around them instead of polling blindly
+
+use std::collections::HashSet;
+
+use chrono::{DateTime, Datelike, Days, FixedOffset, NaiveTime, Weekday};
+
+use crate::library::Library;
+use crate::metadata::{AnimeMetadata, Broadcast};
+
+const WEEK_SECS: i64 = 7 * 24 * 60 * 60;
+/// Broadcast times are listed in JST, which has no daylight saving
+const JST_OFFSET_SECS: i32 = 9 * 60 * 60;
+
+/// Releases usually reach Nyaa within hours of airing; look a few times
+/// over that window
+const RELEASE_CHECK_OFFSETS: [i64; 5] = [30 * 60, 90 * 60, 3 * 3600, 6 * 3600, 12 * 3600];
+/// Series with no known schedule still get checked this often
+pub const FALLBACK_CHECK_SECS: i64 = 6 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledEpisode {
+    pub title: String,
+    /// Unknown when all we have is the weekly broadcast slot
+    pub episode: Option<u32>,
+    /// Unix timestamp (seconds)
+    pub airs_at: i64,
+    /// Belongs to a tracked series, so it'll be downloaded automatically
+    pub tracked: bool,
+}
+
+/// The first broadcast at or after `from`
+pub fn next_broadcast(broadcast: &Broadcast, from: i64) -> Option<i64> {
+    let day: Weekday = broadcast.day.parse().ok()?;
+    let time = NaiveTime::parse_from_str(broadcast.time.as_deref()?, "%H:%M").ok()?;
+    let jst = FixedOffset::east_opt(JST_OFFSET_SECS)?;
+    let start = DateTime::from_timestamp(from, 0)?.with_timezone(&jst);
+
+    let days_ahead = (day.num_days_from_monday() + 7 - start.weekday().num_days_from_monday()) % 7;
+    let date = start
+        .date_naive()
+        .checked_add_days(Days::new(days_ahead.into()))?;
+    let at = date
+        .and_time(time)
+        .and_local_timezone(jst)
+        .single()?
+        .timestamp();
+    Some(if at < from { at + WEEK_SECS } else { at })
+}
+
+/// Expected airings in `[from, until)`, assuming one episode a week. The
+/// provider's next-episode time is projected forwards (or backwards, for
+/// episodes that already aired in the window); failing that, the weekly
+/// broadcast slot of a show that's airing.
+pub fn airings_between(metadata: &AnimeMetadata, from: i64, until: i64) -> Vec<(Option<u32>, i64)> {
+    let mut airings = Vec::new();
+
+    if let Some(next) = metadata.next_airing {
+        // The first week (relative to `next`) that lands in the window, but
+        // never before episode 1
+        let first_week = (from - next.airing_at + WEEK_SECS - 1)
+            .div_euclid(WEEK_SECS)
+            .max(1 - i64::from(next.episode));
+        for week in first_week.. {
+            let airs_at = next.airing_at + week * WEEK_SECS;
+            let Ok(episode) = u32::try_from(i64::from(next.episode) + week) else {
+                break;
+            };
+            if airs_at >= until || metadata.episodes.is_some_and(|total| episode > total) {
+                break;
+            }
+            airings.push((Some(episode), airs_at));
+        }
+    } else if metadata.status == "currently_airing" {
+        let Some(mut airs_at) = metadata
+            .broadcast
+            .as_ref()
+            .and_then(|b| next_broadcast(b, from))
+        else {
+            return airings;
+        };
+        while airs_at < until {
+            airings.push((None, airs_at));
+            airs_at += WEEK_SECS;
+        }
+    }
+
+    airings
+}
+
+/// Episodes expected in `[from, until)` across tracked series and library
+/// shows, soonest first. A show that's also tracked is listed once.
+pub fn schedule(library: &Library, from: i64, until: i64) -> Vec<ScheduledEpisode> {
+    let tracked = library
+        .tracked_shows
+        .iter()
+        .filter_map(|s| Some((&s.title, s.cached_metadata.as_ref()?, true)));
+    let shows = library
+        .shows
+        .iter()
+        .filter_map(|s| Some((&s.title, s.metadata.as_ref()?, false)));
+
+    let mut seen = HashSet::new();
+    let mut episodes = Vec::new();
+    for (title, metadata, tracked) in tracked.chain(shows) {
+        if !seen.insert((metadata.provider.id_prefix(), metadata.id)) {
+            continue;
+        }
+        for (episode, airs_at) in airings_between(metadata, from, until) {
+            episodes.push(ScheduledEpisode {
+                title: title.clone(),
+                episode,
+                airs_at,
+                tracked,
+            });
+        }
+    }
+    episodes.sort_by_key(|e| e.airs_at);
+    episodes
+}
+
+/// When to look for new releases next: shortly after a tracked episode
+/// airs, and at least every `FALLBACK_CHECK_SECS`
+pub fn next_update_check(library: &Library, now: i64) -> i64 {
+    let fallback = now + FALLBACK_CHECK_SECS;
+    let latest_offset = RELEASE_CHECK_OFFSETS[RELEASE_CHECK_OFFSETS.len() - 1];
+    schedule(library, now - latest_offset, fallback)
+        .iter()
+        .filter(|e| e.tracked)
+        .flat_map(|e| RELEASE_CHECK_OFFSETS.map(|offset| e.airs_at + offset))
+        .filter(|&at| at > now)
+        .min()
+        .map_or(fallback, |at| at.min(fallback))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::NextAiring;
+
+    /// Sat 2023-10-07 23:00 JST
+    const SATURDAY_NIGHT: i64 = 1_696_687_200;
+
+    fn airing(episode: u32, airing_at: i64, total: Option<u32>) -> AnimeMetadata {
+        AnimeMetadata {
+            id: 52991,
+            title: "Sousou no Frieren".to_string(),
+            status: "currently_airing".to_string(),
+            episodes: total,
+            next_airing: Some(NextAiring { episode, airing_at }),
+            ..AnimeMetadata::default()
+        }
+    }
+
+    #[test]
+    fn test_next_broadcast_in_jst() {
+        let broadcast = Broadcast {
+            day: "saturday".to_string(),
+            time: Some("23:00".to_string()),
+        };
+        assert_eq!(
+            next_broadcast(&broadcast, SATURDAY_NIGHT - 3600),
+            Some(SATURDAY_NIGHT)
+        );
+        assert_eq!(
+            next_broadcast(&broadcast, SATURDAY_NIGHT + 1),
+            Some(SATURDAY_NIGHT + WEEK_SECS)
+        );
+        let no_time = Broadcast {
+            day: "saturday".to_string(),
+            time: None,
+        };
+        assert_eq!(next_broadcast(&no_time, SATURDAY_NIGHT), None);
+    }
+
+    #[test]
+    fn test_airings_project_weekly_and_stop_at_the_finale() {
+        // Episode 5 airs in a day, so episode 4 went out six days ago
+        let now = SATURDAY_NIGHT - 24 * 3600;
+        let anime = airing(5, SATURDAY_NIGHT, Some(6));
+        assert_eq!(
+            airings_between(&anime, now - 7 * 24 * 3600, now + 3 * WEEK_SECS),
+            vec![
+                (Some(4), SATURDAY_NIGHT - WEEK_SECS),
+                (Some(5), SATURDAY_NIGHT),
+                (Some(6), SATURDAY_NIGHT + WEEK_SECS),
+            ]
+        );
+
+        // A stale next-episode time still lands on the right week
+        let stale = airing(2, SATURDAY_NIGHT - 2 * WEEK_SECS, None);
+        assert_eq!(
+            airings_between(&stale, SATURDAY_NIGHT - 60, SATURDAY_NIGHT + 60),
+            vec![(Some(4), SATURDAY_NIGHT)]
+        );
+    }
+
+    #[test]
+    fn test_update_checks_follow_tracked_airings() {
+        let mut library = Library::default();
+        let now = SATURDAY_NIGHT - 3600;
+        assert_eq!(next_update_check(&library, now), now + FALLBACK_CHECK_SECS);
+
+        let mut series =
+            crate::metadata::mal_sync::new_tracked(Default::default(), 52991, "Frieren", 4);
+        series.cached_metadata = Some(airing(5, SATURDAY_NIGHT, None));
+        library.tracked_shows.push(series);
+        assert_eq!(next_update_check(&library, now), SATURDAY_NIGHT + 30 * 60);
+        assert_eq!(
+            next_update_check(&library, SATURDAY_NIGHT + 40 * 60),
+            SATURDAY_NIGHT + 90 * 60
+        );
+    }
+}
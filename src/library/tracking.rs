@@ -1,13 +1,32 @@
-use crate::library::{Library, parser};
-use crate::nyaa::{NyaaCategory, NyaaClient, NyaaFilter, NyaaSort};
-use std::collections::HashMap;
+use crate::config::ScoringConfig;
+use crate::error::{Error, Result};
+use crate::indexer::{self, SharedIndexer};
+use crate::library::Library;
+use crate::library::models::TrackedSeries;
+use crate::library::numbering::EpisodeNumbering;
+use crate::library::release::ReleaseInfo;
+use crate::nyaa::{NyaaCategory, NyaaFilter, NyaaResult, ScoringProfile};
+use crate::torrent::{AnyTorrentClient, FilePriority, TorrentFileStatus};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::RangeInclusive;
+use std::time::Duration;
 use tracing::{debug, info};
 
 pub struct UpdateResult {
     pub series_title: String,
+    /// The episode, or for a batch the first one it's downloaded for
     pub episode_number: u32,
-    pub magnet: String,
+    /// Magnet, or `.torrent` link when the indexer gave no magnet
+    pub link: String,
+    /// Lowercase hex, empty if the indexer didn't say
+    pub info_hash: String,
     pub title: String,
+    /// The show, and the season and episode, whose copy this release
+    /// upgrades
+    pub replaces: Option<(String, u32, u32)>,
+    /// For a batch, the episodes it's downloaded for; its other files are
+    /// skipped
+    pub batch: Option<BatchSelection>,
 }
 
 pub struct ExistingTorrent {
@@ -15,10 +34,51 @@ pub struct ExistingTorrent {
     pub name: String,
 }
 
+/// The best release found so far for an episode
+struct Candidate {
+    /// Profile score, then seeders
+    rank: (i32, u32),
+    result: NyaaResult,
+    replaces: Option<(String, u32, u32)>,
+}
+
+/// The episodes of one season wanted from a batch
+#[derive(Debug, Clone)]
+pub struct BatchSelection {
+    pub season: u32,
+    pub episodes: Vec<u32>,
+    /// To read the batch's file names the way the library numbers them
+    pub numbering: Option<EpisodeNumbering>,
+}
+
+impl BatchSelection {
+    /// Indices of the files to skip: those of episodes not wanted. Files
+    /// that aren't episodes (fonts, extras) are kept.
+    pub fn unwanted(&self, files: &[TorrentFileStatus]) -> Vec<usize> {
+        files
+            .iter()
+            .filter(|file| {
+                let name = file.name.rsplit(['/', '\\']).next().unwrap_or(&file.name);
+                let release = ReleaseInfo::parse(name);
+                let Some(number) = release.episode() else {
+                    return false;
+                };
+                let (season, episode) = match &self.numbering {
+                    Some(n) => n.season_episode(release.season, number, self.season),
+                    None => (release.season.unwrap_or(self.season), number),
+                };
+                season != self.season || !self.episodes.contains(&episode)
+            })
+            .map(|file| file.index)
+            .collect()
+    }
+}
+
 pub async fn check_for_updates(
     library: &Library,
-    client: &NyaaClient,
+    indexers: &[SharedIndexer],
     existing_torrents: &[ExistingTorrent],
+    scoring: &ScoringConfig,
 ) -> Vec<UpdateResult> {
     let mut updates = Vec::new();
 
@@ -26,73 +86,106 @@ pub async fn check_for_updates(
 
     for series in tracked {
         info!(series = %series.title, "Checking for updates");
+        let profile = scoring.profile(series.scoring_profile.as_deref());
 
-        match client
-            .search(
-                &series.query,
-                NyaaCategory::AnimeEnglish,
-                NyaaFilter::NoFilter,
-                NyaaSort::Seeders,
-            )
-            .await
+        match indexer::rss_all(
+            indexers,
+            &series.query,
+            NyaaCategory::AnimeEnglish,
+            NyaaFilter::NoFilter,
+        )
+        .await
         {
             Ok(results) => {
-                let mut best_candidates: HashMap<u32, (i32, String, String)> = HashMap::new();
-                // Map: EpisodeNum -> (Score, Magnet, Title)
+                let mut best_candidates: HashMap<u32, Candidate> = HashMap::new();
+                let mut batches: Vec<(Candidate, RangeInclusive<u32>)> = Vec::new();
+                let mut downloading: HashSet<u32> = HashSet::new();
+
+                let existing_show = library.get_show(&series.id).or_else(|| {
+                    library.shows.iter().find(|s| {
+                        let s_title = s.title.to_lowercase();
+                        let q_title = series.title.to_lowercase(); // series.title is the query
+                        s_title.contains(&q_title) || q_title.contains(&s_title)
+                    })
+                });
+                let numbering = existing_show
+                    .and_then(|s| s.numbering.clone())
+                    .or_else(|| library.numbering_from_metadata(&series.title));
+                // Batches of this series already in the client bring their
+                // episodes, so singles for them would be downloaded twice
+                let in_flight: Vec<RangeInclusive<u32>> = existing_torrents
+                    .iter()
+                    .filter_map(|t| {
+                        let release = ReleaseInfo::parse(&t.name);
+                        if release.episode().is_some() || !is_release_of(&release, &series) {
+                            return None;
+                        }
+                        covered_episodes(&release, numbering.as_ref(), series.season)
+                    })
+                    .collect();
+                let batch_brings = |ep: &u32| in_flight.iter().any(|covers| covers.contains(ep));
 
                 for result in results {
                     let title = &result.title;
 
-                    if let Some(result_season) = parser::parse_season_number(title) {
-                        if result_season != series.season {
-                            continue;
-                        }
-                    }
-
-                    let ep_num = match parser::parse_episode_number(title) {
-                        Some(n) => n,
-                        None => continue,
+                    let release = ReleaseInfo::parse(title);
+                    // `- 1071` and `S21E23` both become season 21's episode 23
+                    let Some(covers) =
+                        covered_episodes(&release, numbering.as_ref(), series.season)
+                    else {
+                        continue;
                     };
+                    let is_single = release.episode().is_some();
+                    let ep_num = *covers.start();
 
-                    if ep_num < series.min_episode {
+                    if is_single && ep_num < series.min_episode {
                         continue;
                     }
 
-                    let existing_show = library.get_show(&series.id).or_else(|| {
-                        library.shows.iter().find(|s| {
-                            let s_title = s.title.to_lowercase();
-                            let q_title = series.title.to_lowercase(); // series.title is the query
-                            s_title.contains(&q_title) || q_title.contains(&s_title)
-                        })
+                    let is_downloading = existing_torrents.iter().any(|t| {
+                        if !result.info_hash.is_empty()
+                            && t.hash.eq_ignore_ascii_case(&result.info_hash)
+                        {
+                            return true;
+                        }
+                        let t_name = t.name.to_lowercase();
+                        // Check if torrent name contains series title AND episode number
+                        if t_name == title.to_lowercase() {
+                            return true;
+                        }
+                        // TODO: fix (maybe?), isn't causing issues yet
+                        false
                     });
 
-                    if let Some(show) = existing_show {
-                        if show.get_episode(ep_num).is_some() {
+                    let mut replaces = None;
+                    if !is_single {
+                        if is_downloading {
+                            debug!(release = %title, "Skipping batch (already downloading)");
                             continue;
                         }
-
-                        // Check if we are currently downloading it (fuzzy match on title/name)
-                        let is_downloading = existing_torrents.iter().any(|t| {
-                            let t_name = t.name.to_lowercase();
-                            // Check if torrent name contains series title AND episode number
-                            if t_name == title.to_lowercase() {
-                                return true;
+                    } else if let Some(show) = existing_show {
+                        if let Some(episode) = show.find_episode(series.season, ep_num) {
+                            let current = episode.release.as_deref().unwrap_or(&episode.filename);
+                            if !series.upgrade || !is_upgrade(profile, current, title) {
+                                continue;
                             }
-                            // TODO: fix (maybe?), isn't causing issues yet
-                            false
-                        });
+                            replaces = Some((show.id.clone(), series.season, ep_num));
+                        }
 
-                        if is_downloading {
+                        // A batch skips the files of episodes already had, so
+                        // upgrades still go ahead
+                        if is_downloading || (replaces.is_none() && batch_brings(&ep_num)) {
                             debug!(
                                 "Skipping {} - Episode {} (already downloading)",
                                 series.title, ep_num
                             );
+                            downloading.insert(ep_num);
                             continue;
                         }
                     }
 
                     if let Some(ref group) = series.filter_group {
-                        if let Some(parsed_group) = parser::parse_release_group(title) {
+                        if let Some(parsed_group) = &release.group {
                             if !parsed_group.contains(group) {
                                 // Loose matching?
                                 continue;
@@ -103,8 +196,8 @@ pub async fn check_for_updates(
                     }
 
                     if let Some(ref quality) = series.filter_quality {
-                        if let Some(parsed_qual) = parser::parse_quality(title) {
-                            if parsed_qual != quality.to_lowercase() {
+                        if let Some(parsed_qual) = &release.resolution {
+                            if !same_resolution(parsed_qual, quality) {
                                 continue;
                             }
                         } else {
@@ -112,37 +205,91 @@ pub async fn check_for_updates(
                         }
                     }
 
-                    // Calculate score for selection
-                    // Base score = 10
-                    // Bonus for 1080p = +5 (unless filtered)
-                    // Bonus for matching preferred group (already filtered)
-                    // Tie breaker = seeders (results come sorted by seeders, so first one usually wins if we don't overwrite)
-                    // Tune values later if needed (you, the user, i'm too lazy to make it a config value)
-
-                    let mut score: i32 = 0;
-                    if let Some(q) = parser::parse_quality(title) {
-                        if q == "1080p" {
-                            score += 10;
-                        } else if q == "720p" {
-                            score += 5;
-                        }
+                    let breakdown = profile.score(&result);
+                    if let Some(reason) = breakdown.rejected {
+                        debug!(release = %title, reason = %reason, "Skipping rejected release");
+                        continue;
                     }
 
-                    let current_best =
-                        best_candidates
-                            .entry(ep_num)
-                            .or_insert((-1, String::new(), String::new()));
-                    if score > current_best.0 {
-                        *current_best = (score, result.magnet_link.clone(), result.title.clone());
+                    // The feed is newest first, so seeders break ties
+                    let rank = (breakdown.total, result.seeders);
+                    let candidate = Candidate {
+                        rank,
+                        result,
+                        replaces,
+                    };
+                    if !is_single {
+                        batches.push((candidate, covers));
+                    } else if best_candidates
+                        .get(&ep_num)
+                        .is_none_or(|best| rank > best.rank)
+                    {
+                        best_candidates.insert(ep_num, candidate);
                     }
                 }
 
-                for (ep_num, (_, magnet, title)) in best_candidates {
+                // Missing: episodes out that we don't have, and once the
+                // episode count is known, any before it we never got
+                let mut missing: BTreeSet<u32> = best_candidates
+                    .iter()
+                    .filter(|(_, c)| c.replaces.is_none())
+                    .map(|(ep, _)| *ep)
+                    .collect();
+                if let Some(total) = series.cached_metadata.as_ref().and_then(|m| m.episodes) {
+                    missing.extend((series.min_episode.max(1)..=total).filter(|&ep| {
+                        existing_show.is_none_or(|s| s.find_episode(series.season, ep).is_none())
+                    }));
+                }
+                missing.retain(|ep| !downloading.contains(ep) && !batch_brings(ep));
+
+                if let Some((batch, episodes)) =
+                    pick_batch(batches, &missing, |ep| best_candidates.contains_key(&ep))
+                {
+                    info!(
+                        series = %series.title,
+                        episodes = episodes.len(),
+                        release = %batch.result.title,
+                        "Found a batch of missing episodes"
+                    );
+                    best_candidates.retain(|ep, c| c.replaces.is_some() || !episodes.contains(ep));
+                    updates.push(UpdateResult {
+                        series_title: series.title.clone(),
+                        episode_number: episodes[0],
+                        link: batch.result.download_link().to_string(),
+                        info_hash: batch.result.info_hash,
+                        title: batch.result.title,
+                        replaces: None,
+                        batch: Some(BatchSelection {
+                            season: series.season,
+                            episodes,
+                            numbering: numbering.clone(),
+                        }),
+                    });
+                }
+
+                for (
+                    ep_num,
+                    Candidate {
+                        result, replaces, ..
+                    },
+                ) in best_candidates
+                {
+                    if replaces.is_some() {
+                        info!(
+                            series = %series.title,
+                            episode = ep_num,
+                            release = %result.title,
+                            "Found an upgrade"
+                        );
+                    }
                     updates.push(UpdateResult {
                         series_title: series.title.clone(),
                         episode_number: ep_num,
-                        magnet,
-                        title,
+                        link: result.download_link().to_string(),
+                        info_hash: result.info_hash,
+                        title: result.title,
+                        replaces,
+                        batch: None,
                     });
                 }
             }
@@ -154,3 +301,366 @@ pub async fn check_for_updates(
 
     updates
 }
+
+/// The episodes of `season` a release has, numbered through `numbering`.
+/// Season packs and batches that don't say which episodes have them all.
+fn covered_episodes(
+    release: &ReleaseInfo,
+    numbering: Option<&EpisodeNumbering>,
+    season: u32,
+) -> Option<RangeInclusive<u32>> {
+    let normalize = |number| match numbering {
+        Some(n) => n.season_episode(release.season, number, season),
+        None => (release.season.unwrap_or(season), number),
+    };
+    match release.episodes {
+        Some((first, last)) => {
+            let (first_season, first) = normalize(first);
+            let (last_season, last) = normalize(last);
+            // A batch running across seasons counts for its part of this one
+            let first = match first_season.cmp(&season) {
+                std::cmp::Ordering::Less => 1,
+                std::cmp::Ordering::Equal => first,
+                std::cmp::Ordering::Greater => return None,
+            };
+            let last = match last_season.cmp(&season) {
+                std::cmp::Ordering::Less => return None,
+                std::cmp::Ordering::Equal => last,
+                std::cmp::Ordering::Greater => u32::MAX,
+            };
+            Some(first..=last)
+        }
+        None if release.is_batch && release.season.is_none_or(|s| s == season) => {
+            Some(1..=u32::MAX)
+        }
+        None => None,
+    }
+}
+
+/// Whether a torrent named as `release` is one of `series`, going by the
+/// title, as torrents in the client say nothing else about what they are
+fn is_release_of(release: &ReleaseInfo, series: &TrackedSeries) -> bool {
+    let title = release.title.to_lowercase();
+    if title.is_empty() {
+        return false;
+    }
+    [&series.title, &series.query].into_iter().any(|name| {
+        let name = ReleaseInfo::parse(name).title.to_lowercase();
+        !name.is_empty() && (title.contains(&name) || name.contains(&title))
+    })
+}
+
+/// The batch worth downloading instead of singles: the one covering the
+/// most missing episodes, as long as that's several of them or some no
+/// single release has
+fn pick_batch(
+    batches: Vec<(Candidate, RangeInclusive<u32>)>,
+    missing: &BTreeSet<u32>,
+    has_single: impl Fn(u32) -> bool,
+) -> Option<(Candidate, Vec<u32>)> {
+    batches
+        .into_iter()
+        .map(|(candidate, covers)| {
+            let episodes: Vec<u32> = missing
+                .iter()
+                .copied()
+                .filter(|ep| covers.contains(ep))
+                .collect();
+            (candidate, episodes)
+        })
+        .filter(|(_, episodes)| episodes.len() > 1 || episodes.iter().any(|&ep| !has_single(ep)))
+        .max_by_key(|(candidate, episodes)| (episodes.len(), candidate.rank))
+}
+
+/// Skip the files of a batch's unwanted episodes once the client knows
+/// them. Magnets have to fetch their metadata first, so this waits up to a
+/// few minutes. The client starts on every file as soon as the metadata is
+/// in, so it's checked for often. Returns how many files were skipped.
+pub async fn select_batch_files(
+    client: &AnyTorrentClient,
+    hash: &str,
+    selection: &BatchSelection,
+) -> Result<usize> {
+    for _ in 0..300 {
+        if let Ok(details) = client.details(hash).await
+            && !details.files.is_empty()
+        {
+            let unwanted = selection.unwanted(&details.files);
+            if !unwanted.is_empty() {
+                client
+                    .set_file_priority(hash, &unwanted, FilePriority::Skip)
+                    .await?;
+            }
+            return Ok(unwanted.len());
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    Err(Error::TorrentClient(format!(
+        "No file list for {} after 5 minutes",
+        hash
+    )))
+}
+
+/// Whether `candidate` should replace an episode's local copy, which came
+/// from the release (or file) named `current`: a newer version or repack of
+/// the same release, or anything the profile scores higher. Nothing
+/// replaces a file whose name says nothing about where it came from.
+pub fn is_upgrade(profile: &ScoringProfile, current: &str, candidate: &str) -> bool {
+    let old = ReleaseInfo::parse(current);
+    let new = ReleaseInfo::parse(candidate);
+    if old.group.is_none() && old.resolution.is_none() {
+        return false;
+    }
+
+    let same_group = match (&old.group, &new.group) {
+        (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+        (a, b) => a.is_none() && b.is_none(),
+    };
+    if same_group && old.resolution == new.resolution {
+        if new.version != old.version {
+            return new.version > old.version;
+        }
+        if new.repack != old.repack {
+            return new.repack;
+        }
+    }
+
+    profile.score_title(candidate).total > profile.score_title(current).total
+}
+
+/// `filter_quality` as written in the library file, which may say `4k`
+fn same_resolution(parsed: &str, wanted: &str) -> bool {
+    let wanted = wanted.to_lowercase();
+    parsed == wanted || (parsed == "2160p" && wanted == "4k")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexer::Indexer;
+    use crate::library::models::TrackedSeries;
+    use crate::library::{Episode, Show};
+    use crate::metadata::AnimeMetadata;
+    use crate::nyaa::NyaaSort;
+    use std::sync::Arc;
+
+    /// An indexer whose feed is always the same releases
+    struct Feed(Vec<NyaaResult>);
+
+    #[async_trait::async_trait]
+    impl Indexer for Feed {
+        fn name(&self) -> &str {
+            "Feed"
+        }
+
+        fn categories(&self) -> &'static [NyaaCategory] {
+            &[NyaaCategory::AnimeEnglish]
+        }
+
+        async fn search(
+            &self,
+            _query: &str,
+            _category: NyaaCategory,
+            _filter: NyaaFilter,
+            _sort: NyaaSort,
+        ) -> Result<Vec<NyaaResult>> {
+            Ok(self.0.clone())
+        }
+    }
+
+    fn release(title: &str, info_hash: &str) -> NyaaResult {
+        NyaaResult {
+            title: title.to_string(),
+            category: "Anime".to_string(),
+            size: "1.4 GiB".to_string(),
+            seeders: 50,
+            magnet_link: format!("magnet:?xt=urn:btih:{}", info_hash),
+            is_trusted: Some(false),
+            info_hash: info_hash.to_string(),
+            ..NyaaResult::default()
+        }
+    }
+
+    /// Frieren, six episodes, with the first `have` in the library
+    fn library_with(have: u32) -> Library {
+        let mut show = Show::new("frieren", "Frieren", "/anime/Frieren".into());
+        show.episodes = (1..=have)
+            .map(|ep| Episode::new(ep, format!("Frieren - {:02}.mkv", ep)))
+            .collect();
+        Library {
+            shows: vec![show],
+            tracked_shows: vec![TrackedSeries {
+                id: "frieren".to_string(),
+                title: "Frieren".to_string(),
+                query: "Frieren".to_string(),
+                season: 1,
+                cached_metadata: Some(AnimeMetadata {
+                    episodes: Some(6),
+                    ..AnimeMetadata::default()
+                }),
+                ..TrackedSeries::default()
+            }],
+            ..Library::default()
+        }
+    }
+
+    fn feed() -> Vec<SharedIndexer> {
+        vec![Arc::new(Feed(vec![
+            release("[SubsPlease] Frieren - 06 (1080p) [ABCD1234].mkv", "66"),
+            release("[SubsPlease] Frieren - 05 (1080p) [ABCD1234].mkv", "55"),
+            release("[Judas] Frieren (01-06) [1080p][HEVC x265 10bit]", "b1"),
+            release("[Judas] Frieren (01-03) [1080p][HEVC x265 10bit]", "b2"),
+        ]))]
+    }
+
+    #[tokio::test]
+    async fn test_one_batch_instead_of_many_singles() {
+        let updates =
+            check_for_updates(&library_with(2), &feed(), &[], &ScoringConfig::default()).await;
+        assert_eq!(updates.len(), 1);
+        assert_eq!(
+            updates[0].title,
+            "[Judas] Frieren (01-06) [1080p][HEVC x265 10bit]"
+        );
+        assert_eq!(updates[0].episode_number, 3);
+        let batch = updates[0].batch.as_ref().unwrap();
+        assert_eq!(batch.episodes, vec![3, 4, 5, 6]);
+    }
+
+    #[tokio::test]
+    async fn test_single_missing_episode_stays_single() {
+        let mut updates =
+            check_for_updates(&library_with(5), &feed(), &[], &ScoringConfig::default()).await;
+        assert_eq!(updates.len(), 1);
+        let update = updates.pop().unwrap();
+        assert_eq!(update.episode_number, 6);
+        assert!(update.batch.is_none());
+
+        // Episode 4 has no single release, so a batch brings it and 5, 6
+        let mut library = library_with(3);
+        library.shows[0]
+            .episodes
+            .push(Episode::new(5, "Frieren - 05.mkv"));
+        let updates = check_for_updates(&library, &feed(), &[], &ScoringConfig::default()).await;
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].batch.as_ref().unwrap().episodes, vec![4, 6]);
+    }
+
+    #[tokio::test]
+    async fn test_single_covered_by_batch_in_flight_is_skipped() {
+        // Added last check, under a name that's not in the feed
+        let existing = [ExistingTorrent {
+            hash: "b0".to_string(),
+            name: "[Judas] Frieren (01-06) [1080p]".to_string(),
+        }];
+        let updates = check_for_updates(
+            &library_with(2),
+            &feed(),
+            &existing,
+            &ScoringConfig::default(),
+        )
+        .await;
+        assert!(updates.is_empty());
+
+        // Another show's batch doesn't count
+        let existing = [ExistingTorrent {
+            hash: "b0".to_string(),
+            name: "[Judas] Dungeon Meshi (01-24) [1080p]".to_string(),
+        }];
+        let updates = check_for_updates(
+            &library_with(4),
+            &feed(),
+            &existing,
+            &ScoringConfig::default(),
+        )
+        .await;
+        assert_eq!(updates.len(), 1);
+    }
+
+    #[test]
+    fn test_batch_skips_files_of_episodes_not_wanted() {
+        let file = |index, name: &str| TorrentFileStatus {
+            index,
+            name: name.to_string(),
+            size: 0,
+            progress: 0.0,
+            priority: FilePriority::Normal,
+        };
+        let files = [
+            file(0, "[Judas] Frieren (01-06)/[Judas] Frieren - 01.mkv"),
+            file(1, "[Judas] Frieren (01-06)/[Judas] Frieren - 02.mkv"),
+            file(2, "[Judas] Frieren (01-06)/[Judas] Frieren - 03.mkv"),
+            file(3, "[Judas] Frieren (01-06)/Subs/[Judas] Frieren - 01.ass"),
+            file(4, "[Judas] Frieren (01-06)/Fonts/Roboto.ttf"),
+        ];
+        let selection = BatchSelection {
+            season: 1,
+            episodes: vec![3],
+            numbering: None,
+        };
+        assert_eq!(selection.unwanted(&files), vec![0, 1, 3]);
+
+        // Numbered absolutely, season 2 starting after 12 episodes
+        let files = [
+            file(0, "Show - 13.mkv"),
+            file(1, "Show - 14.mkv"),
+            file(2, "Show S02E03.mkv"),
+        ];
+        let selection = BatchSelection {
+            season: 2,
+            episodes: vec![2, 3],
+            numbering: Some(EpisodeNumbering {
+                seasons: vec![12],
+                manual: true,
+            }),
+        };
+        assert_eq!(selection.unwanted(&files), vec![0]);
+    }
+
+    #[test]
+    fn test_new_version_is_an_upgrade() {
+        let profile = ScoringProfile::default();
+        let v1 = "[SubsPlease] Frieren - 05 (1080p) [8E2F1A0C].mkv";
+        assert!(is_upgrade(
+            &profile,
+            v1,
+            "[SubsPlease] Frieren - 05v2 (1080p) [1B7C9D4E].mkv"
+        ));
+        assert!(!is_upgrade(&profile, v1, v1));
+        assert!(!is_upgrade(
+            &profile,
+            "[SubsPlease] Frieren - 05v2 (1080p).mkv",
+            "[SubsPlease] Frieren - 05 (1080p).mkv"
+        ));
+        assert!(is_upgrade(
+            &profile,
+            "Frieren.S01E05.1080p.WEB.x264-VARYG.mkv",
+            "Frieren.S01E05.REPACK.1080p.WEB.x264-VARYG.mkv"
+        ));
+    }
+
+    #[test]
+    fn test_better_scoring_release_is_an_upgrade() {
+        let profile = ScoringProfile {
+            sources: [("bd".to_string(), 20)].into(),
+            ..ScoringProfile::default()
+        };
+        let web = "[SubsPlease] Frieren - 05 (1080p) [8E2F1A0C].mkv";
+        assert!(is_upgrade(
+            &profile,
+            web,
+            "[Judas] Frieren - 05 [BD 1080p][HEVC].mkv"
+        ));
+        assert!(!is_upgrade(
+            &profile,
+            web,
+            "[SubsPlease] Frieren - 05 (720p).mkv"
+        ));
+        // A cleaned-up file name gives nothing to compare against
+        assert!(!is_upgrade(
+            &profile,
+            "Frieren - 05.mkv",
+            "[Judas] Frieren - 05 [BD 1080p].mkv"
+        ));
+    }
+}
@@ -4,12 +4,15 @@ mod compression;
 mod config;
 mod error;
 mod image_cache;
+mod indexer;
 mod library;
 mod metadata;
 mod notify;
 mod nyaa;
 mod player;
 mod rpc;
+#[cfg(test)]
+mod test_support;
 mod torrent;
 mod ui;
 
@@ -46,6 +49,11 @@ async fn main() -> Result<()> {
     let config = Config::load()?;
     info!("Loaded config");
 
+    // `miru <magnet or file.torrent>` opens the add preview on startup
+    let ingest = std::env::args()
+        .nth(1)
+        .map(|arg| (torrent::preview::load_preview_source(&arg), arg));
+
     // Load existing library or create new
     let mut library = Library::load()?;
     info!(shows = library.shows.len(), "Loaded library");
@@ -69,6 +77,12 @@ async fn main() -> Result<()> {
 
     // Run the app (async)
     let mut app = App::new(config, library, picker);
+    match ingest {
+        Some((Ok(source), _)) => app.ingest(source),
+        // A bad argument shouldn't keep the app from starting
+        Some((Err(e), arg)) => app.ingest_failed(&arg, &e),
+        None => {}
+    }
     let result = app.run(&mut terminal).await;
 
     // Restore terminal on exit
@@ -0,0 +1,461 @@
+use regex::Regex;
+use reqwest::Client;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::sync::LazyLock;
+
+use crate::error::{Error, Result};
+use crate::metadata::{
+    AnimeMetadata, ListStatus, ListSync, MetadataProvider, NextAiring, ProviderKind, UserAnimeEntry,
+};
+
+const ANILIST_API_BASE: &str = "https://graphql.anilist.co";
+const ANILIST_OAUTH_BASE: &str = "https://anilist.co/api/v2/oauth";
+
+const MEDIA_FIELDS: &str = "id title { romaji english } coverImage { large medium } \
+    description(asHtml: false) averageScore status episodes genres seasonYear \
+    nextAiringEpisode { airingAt episode }";
+
+/// Descriptions still carry <br> and <i> tags even with asHtml: false
+static HTML_TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]+>").unwrap());
+
+pub struct AnilistClient {
+    client: Client,
+    client_id: String,
+    access_token: Option<String>,
+    api_base: String,
+}
+
+impl AnilistClient {
+    pub fn new(client_id: String) -> Self {
+        Self {
+            client: Client::new(),
+            client_id,
+            access_token: None,
+            api_base: ANILIST_API_BASE.to_string(),
+        }
+    }
+
+    pub fn with_access_token(mut self, token: String) -> Self {
+        self.access_token = Some(token);
+        self
+    }
+
+    /// Point API calls somewhere other than graphql.anilist.co
+    #[cfg(test)]
+    pub fn with_api_base(mut self, base: &str) -> Self {
+        self.api_base = base.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Implicit grant: AniList shows the access token on its pin page for
+    /// the user to paste back, so no client secret is needed
+    pub fn build_auth_url(&self) -> String {
+        format!(
+            "{}/authorize?client_id={}&response_type=token",
+            ANILIST_OAUTH_BASE, self.client_id
+        )
+    }
+
+    async fn query<T: DeserializeOwned>(&self, query: &str, variables: Value) -> Result<T> {
+        let mut request = self
+            .client
+            .post(&self.api_base)
+            .header("Accept", "application/json")
+            .json(&json!({ "query": query, "variables": variables }));
+        if let Some(token) = &self.access_token {
+            request = request.bearer_auth(token);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        // AniList tokens can't be refreshed, a 401 means logging in again
+        if status == reqwest::StatusCode::UNAUTHORIZED {
+            return Err(Error::Unauthorized("AniList".to_string()));
+        }
+        let body: GraphQlResponse<T> = response
+            .json()
+            .await
+            .map_err(|e| Error::Metadata(format!("AniList API Error: {} ({})", status, e)))?;
+
+        if let Some(err) = body.errors.and_then(|errs| errs.into_iter().next()) {
+            return Err(Error::Metadata(format!(
+                "AniList API Error: {}",
+                err.message
+            )));
+        }
+        body.data
+            .ok_or_else(|| Error::Metadata(format!("AniList API Error: {}", status)))
+    }
+
+    fn require_token(&self) -> Result<()> {
+        if self.access_token.is_none() {
+            return Err(Error::Metadata("No access token set".to_string()));
+        }
+        Ok(())
+    }
+
+    async fn viewer_id(&self) -> Result<u64> {
+        let data: ViewerData = self.query("query { Viewer { id } }", json!({})).await?;
+        Ok(data.viewer.id)
+    }
+}
+
+#[derive(Deserialize)]
+struct GraphQlResponse<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Option<Vec<GraphQlError>>,
+}
+
+#[derive(Deserialize)]
+struct GraphQlError {
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AnilistMedia {
+    id: u64,
+    title: AnilistTitle,
+    cover_image: Option<AnilistCover>,
+    description: Option<String>,
+    average_score: Option<u32>,
+    status: Option<String>,
+    episodes: Option<u32>,
+    #[serde(default)]
+    genres: Vec<String>,
+    next_airing_episode: Option<AnilistAiring>,
+    season_year: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct AnilistTitle {
+    romaji: Option<String>,
+    english: Option<String>,
+}
+
+impl AnilistTitle {
+    // Release names follow the romaji title, like MAL's main title
+    fn preferred(self) -> String {
+        self.romaji.or(self.english).unwrap_or_default()
+    }
+}
+
+#[derive(Deserialize)]
+struct AnilistCover {
+    large: Option<String>,
+    medium: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AnilistAiring {
+    airing_at: i64,
+    episode: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PageData {
+    page: MediaPage,
+}
+
+#[derive(Deserialize)]
+struct MediaPage {
+    media: Vec<AnilistMedia>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct MediaData {
+    media: AnilistMedia,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ViewerData {
+    viewer: AnilistViewer,
+}
+
+#[derive(Deserialize)]
+struct AnilistViewer {
+    id: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListCollectionData {
+    media_list_collection: MediaListCollection,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MediaListCollection {
+    #[serde(default)]
+    lists: Vec<MediaListGroup>,
+    #[serde(default)]
+    has_next_chunk: bool,
+}
+
+#[derive(Deserialize)]
+struct MediaListGroup {
+    #[serde(default)]
+    entries: Vec<MediaListEntry>,
+}
+
+#[derive(Deserialize)]
+struct MediaListEntry {
+    #[serde(default)]
+    progress: u32,
+    media: ListMedia,
+}
+
+#[derive(Deserialize)]
+struct ListMedia {
+    id: u64,
+    title: AnilistTitle,
+    episodes: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListEntryData {
+    media: ListEntryMedia,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListEntryMedia {
+    media_list_entry: Option<ListEntryProgress>,
+}
+
+#[derive(Deserialize)]
+struct ListEntryProgress {
+    #[serde(default)]
+    progress: u32,
+}
+
+/// Map AniList's media status onto the MAL strings the UI already knows
+fn normalize_status(status: Option<&str>) -> String {
+    match status {
+        Some("RELEASING") => "currently_airing".to_string(),
+        Some("FINISHED") => "finished_airing".to_string(),
+        Some("NOT_YET_RELEASED") => "not_yet_aired".to_string(),
+        Some(other) => other.to_lowercase(),
+        None => "Unknown".to_string(),
+    }
+}
+
+impl From<AnilistMedia> for AnimeMetadata {
+    fn from(m: AnilistMedia) -> Self {
+        AnimeMetadata {
+            id: m.id,
+            title: m.title.preferred(),
+            cover_url: m.cover_image.and_then(|c| c.large.or(c.medium)),
+            synopsis: m
+                .description
+                .map(|d| HTML_TAG.replace_all(&d, "").trim().to_string()),
+            score: m.average_score.map(|s| s as f64 / 10.0),
+            status: normalize_status(m.status.as_deref()),
+            episodes: m.episodes,
+            genres: m.genres,
+            provider: ProviderKind::Anilist,
+            next_airing: m.next_airing_episode.map(|a| NextAiring {
+                episode: a.episode,
+                airing_at: a.airing_at,
+            }),
+            year: m.season_year,
+            broadcast: None,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl MetadataProvider for AnilistClient {
+    async fn search(&self, query: &str) -> Result<Vec<AnimeMetadata>> {
+        let gql = format!(
+            "query ($search: String) {{ Page(perPage: 5) {{ media(search: $search, type: ANIME) {{ {} }} }} }}",
+            MEDIA_FIELDS
+        );
+        let data: PageData = self.query(&gql, json!({ "search": query })).await?;
+        Ok(data
+            .page
+            .media
+            .into_iter()
+            .map(AnimeMetadata::from)
+            .collect())
+    }
+
+    async fn get_details(&self, id: u64) -> Result<AnimeMetadata> {
+        let gql = format!(
+            "query ($id: Int) {{ Media(id: $id, type: ANIME) {{ {} }} }}",
+            MEDIA_FIELDS
+        );
+        let data: MediaData = self.query(&gql, json!({ "id": id })).await?;
+        Ok(data.media.into())
+    }
+}
+
+#[async_trait::async_trait]
+impl ListSync for AnilistClient {
+    async fn list_entries(&self, status: ListStatus) -> Result<Vec<UserAnimeEntry>> {
+        self.require_token()?;
+        let user_id = self.viewer_id().await?;
+        let statuses: &[&str] = match status {
+            ListStatus::Watching => &["CURRENT", "REPEATING"],
+            ListStatus::PlanToWatch => &["PLANNING"],
+            ListStatus::OnHold => &["PAUSED"],
+            ListStatus::Completed => &["COMPLETED"],
+        };
+
+        // Big lists come back in chunks
+        let mut entries = Vec::new();
+        for chunk in 1.. {
+            let data: ListCollectionData = self
+                .query(
+                    "query ($userId: Int, $status: [MediaListStatus], $chunk: Int) { MediaListCollection(userId: $userId, type: ANIME, status_in: $status, chunk: $chunk, perChunk: 500) { hasNextChunk lists { entries { progress media { id episodes title { romaji english } } } } } }",
+                    json!({ "userId": user_id, "status": statuses, "chunk": chunk }),
+                )
+                .await?;
+            let collection = data.media_list_collection;
+            entries.extend(
+                collection
+                    .lists
+                    .into_iter()
+                    .flat_map(|list| list.entries)
+                    .map(|entry| UserAnimeEntry {
+                        id: entry.media.id,
+                        title: entry.media.title.preferred(),
+                        num_episodes: entry.media.episodes,
+                        num_watched: entry.progress,
+                        status,
+                    }),
+            );
+            if !collection.has_next_chunk {
+                break;
+            }
+        }
+        Ok(entries)
+    }
+
+    async fn list_progress(&self, anime_id: u64) -> Result<Option<u32>> {
+        self.require_token()?;
+        let data: ListEntryData = self
+            .query(
+                "query ($id: Int) { Media(id: $id) { mediaListEntry { progress } } }",
+                json!({ "id": anime_id }),
+            )
+            .await?;
+        Ok(data.media.media_list_entry.map(|e| e.progress))
+    }
+
+    async fn update_progress(
+        &self,
+        anime_id: u64,
+        episodes_watched: u32,
+        completed: bool,
+        score: Option<u8>,
+    ) -> Result<()> {
+        self.require_token()?;
+        let mut variables = json!({
+            "mediaId": anime_id,
+            "progress": episodes_watched,
+            "status": if completed { "COMPLETED" } else { "CURRENT" },
+        });
+        // scoreRaw is out of 100 whatever scoring format the user picked.
+        // Leaving it out keeps their existing score.
+        if let Some(score) = score {
+            variables["scoreRaw"] = json!(score as u32 * 10);
+        }
+
+        let _: Value = self
+            .query(
+                "mutation ($mediaId: Int, $progress: Int, $status: MediaListStatus, $scoreRaw: Int) { SaveMediaListEntry(mediaId: $mediaId, progress: $progress, status: $status, scoreRaw: $scoreRaw) { id progress } }",
+                variables,
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::test_support::{mock_server, reply};
+
+    #[tokio::test]
+    async fn test_search_maps_media() {
+        let (port, _) = mock_server(|_| {
+            reply(
+                200,
+                r#"{"data":{"Page":{"media":[{"id":154587,"title":{"romaji":"Sousou no Frieren","english":"Frieren: Beyond Journey's End"},"coverImage":{"large":"https://img/l.jpg","medium":null},"description":"An elf<br>\n<i>mage</i>.","averageScore":91,"status":"RELEASING","episodes":28,"genres":["Adventure","Drama"],"nextAiringEpisode":{"airingAt":1700000000,"episode":12}}]}}}"#,
+            )
+        })
+        .await;
+        let client =
+            AnilistClient::new(String::new()).with_api_base(&format!("http://127.0.0.1:{}", port));
+
+        let results = client.search("frieren").await.unwrap();
+        let anime = &results[0];
+        assert_eq!(anime.title, "Sousou no Frieren");
+        assert_eq!(anime.score, Some(9.1));
+        assert_eq!(anime.status, "currently_airing");
+        assert_eq!(anime.synopsis.as_deref(), Some("An elf\nmage."));
+        assert_eq!(anime.provider, ProviderKind::Anilist);
+        assert_eq!(
+            anime.next_airing,
+            Some(NextAiring {
+                episode: 12,
+                airing_at: 1700000000
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_progress_sends_mutation() {
+        let body = Arc::new(Mutex::new(String::new()));
+        let seen = body.clone();
+        let (port, _) = mock_server(move |req| {
+            assert_eq!(req.header("authorization"), Some("Bearer tok"));
+            *seen.lock().unwrap() = req.body.clone();
+            reply(
+                200,
+                r#"{"data":{"SaveMediaListEntry":{"id":1,"progress":28}}}"#,
+            )
+        })
+        .await;
+        let client = AnilistClient::new(String::new())
+            .with_access_token("tok".to_string())
+            .with_api_base(&format!("http://127.0.0.1:{}", port));
+
+        client
+            .update_progress(154587, 28, true, Some(9))
+            .await
+            .unwrap();
+        let sent: Value = serde_json::from_str(&body.lock().unwrap()).unwrap();
+        assert_eq!(sent["variables"]["progress"], 28);
+        assert_eq!(sent["variables"]["status"], "COMPLETED");
+        assert_eq!(sent["variables"]["scoreRaw"], 90);
+    }
+
+    #[tokio::test]
+    async fn test_graphql_error_surfaces() {
+        let (port, _) = mock_server(|_| {
+            reply(
+                400,
+                r#"{"data":null,"errors":[{"message":"Invalid token","status":400}]}"#,
+            )
+        })
+        .await;
+        let client = AnilistClient::new(String::new())
+            .with_access_token("bad".to_string())
+            .with_api_base(&format!("http://127.0.0.1:{}", port));
+
+        let err = client.list_progress(1).await.unwrap_err();
+        assert!(err.to_string().contains("Invalid token"));
+    }
+}
@@ -0,0 +1,406 @@
+//! OAuth tokens for list sync, kept out of config.toml in a 0600
+//! credentials file or the system keyring, and refreshed before they expire
+
+use std::future::Future;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::config::{MetadataConfig, credentials_path};
+use crate::error::{Error, Result};
+use crate::metadata::ProviderKind;
+use crate::metadata::mal::{MalClient, TokenResponse};
+
+/// Refresh this long before the token would expire
+const REFRESH_MARGIN_SECS: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OAuthToken {
+    pub access_token: String,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    /// Unix timestamp (seconds), `None` if the provider didn't say
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+}
+
+impl OAuthToken {
+    pub fn from_response(resp: TokenResponse, now: i64) -> Self {
+        Self {
+            access_token: resp.access_token,
+            refresh_token: Some(resp.refresh_token),
+            expires_at: Some(now + resp.expires_in),
+        }
+    }
+
+    pub fn needs_refresh(&self, now: i64) -> bool {
+        self.expires_at
+            .is_some_and(|expires| now >= expires - REFRESH_MARGIN_SECS)
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Credentials {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mal: Option<OAuthToken>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anilist: Option<OAuthToken>,
+}
+
+impl Credentials {
+    pub fn get(&self, kind: ProviderKind) -> Option<&OAuthToken> {
+        match kind {
+            ProviderKind::Mal => self.mal.as_ref(),
+            ProviderKind::Anilist => self.anilist.as_ref(),
+            ProviderKind::Kitsu | ProviderKind::Offline => None,
+        }
+    }
+
+    fn set(&mut self, kind: ProviderKind, token: OAuthToken) {
+        match kind {
+            ProviderKind::Mal => self.mal = Some(token),
+            ProviderKind::Anilist => self.anilist = Some(token),
+            ProviderKind::Kitsu | ProviderKind::Offline => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CredentialStore {
+    /// `credentials.toml` next to the config, readable only by the user
+    #[default]
+    File,
+    /// The desktop keyring via libsecret's `secret-tool`
+    Keyring,
+}
+
+pub enum CredentialBackend {
+    File(PathBuf),
+    Keyring,
+}
+
+impl CredentialBackend {
+    pub fn from_config(store: CredentialStore) -> Result<Self> {
+        Ok(match store {
+            CredentialStore::File => CredentialBackend::File(credentials_path()?),
+            CredentialStore::Keyring => CredentialBackend::Keyring,
+        })
+    }
+
+    pub fn load(&self) -> Result<Credentials> {
+        let content = match self {
+            CredentialBackend::File(path) => {
+                if !path.exists() {
+                    return Ok(Credentials::default());
+                }
+                std::fs::read_to_string(path)?
+            }
+            CredentialBackend::Keyring => match keyring_lookup()? {
+                Some(content) => content,
+                None => return Ok(Credentials::default()),
+            },
+        };
+        Ok(toml::from_str(&content)?)
+    }
+
+    pub fn save(&self, credentials: &Credentials) -> Result<()> {
+        let content = toml::to_string_pretty(credentials)?;
+        match self {
+            CredentialBackend::File(path) => {
+                let parent = path.parent().ok_or(Error::NoConfigDir)?;
+                std::fs::create_dir_all(parent)?;
+
+                // Write then rename so a crash never leaves a half-written file
+                let mut temp = tempfile::NamedTempFile::new_in(parent)?;
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    temp.as_file()
+                        .set_permissions(std::fs::Permissions::from_mode(0o600))?;
+                }
+                temp.write_all(content.as_bytes())?;
+                temp.persist(path).map_err(|e| e.error)?;
+                Ok(())
+            }
+            CredentialBackend::Keyring => keyring_store(&content),
+        }
+    }
+}
+
+const KEYRING_ATTRS: [&str; 4] = ["service", "miru", "account", "credentials"];
+
+fn keyring_lookup() -> Result<Option<String>> {
+    let output = Command::new("secret-tool")
+        .arg("lookup")
+        .args(KEYRING_ATTRS)
+        .output()
+        .map_err(keyring_error)?;
+    // secret-tool exits non-zero when nothing is stored yet
+    if !output.status.success() || output.stdout.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+fn keyring_store(content: &str) -> Result<()> {
+    let mut child = Command::new("secret-tool")
+        .args(["store", "--label=miru credentials"])
+        .args(KEYRING_ATTRS)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(keyring_error)?;
+    // The secret goes over stdin so it never shows up in the process list
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(content.as_bytes())?;
+    }
+    if !child.wait()?.success() {
+        return Err(Error::Metadata(
+            "secret-tool failed to store credentials".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn keyring_error(e: std::io::Error) -> Error {
+    Error::Metadata(format!(
+        "Keyring unavailable (is secret-tool installed?): {}",
+        e
+    ))
+}
+
+/// Hands out access tokens for list sync, refreshing MAL tokens ahead of
+/// expiry or after a 401, and persisting every change
+pub struct TokenManager {
+    backend: CredentialBackend,
+    credentials: std::sync::Mutex<Credentials>,
+    /// Serializes refreshes so concurrent 401s don't spend the refresh token twice
+    refresh_lock: tokio::sync::Mutex<()>,
+    mal: Option<MalClient>,
+}
+
+impl TokenManager {
+    /// `mal` is the client used for token refreshes, `None` without a client ID
+    pub fn new(backend: CredentialBackend, mal: Option<MalClient>) -> Self {
+        let credentials = backend.load().unwrap_or_else(|e| {
+            warn!("Failed to load credentials: {}", e);
+            Credentials::default()
+        });
+        Self {
+            backend,
+            credentials: std::sync::Mutex::new(credentials),
+            refresh_lock: tokio::sync::Mutex::new(()),
+            mal,
+        }
+    }
+
+    /// Move tokens left in config.toml by older versions into the store
+    pub fn migrate_legacy(&self, metadata: &MetadataConfig) {
+        let Some(access_token) = metadata.mal_access_token.clone() else {
+            return;
+        };
+        if self.has_token(ProviderKind::Mal) {
+            return;
+        }
+        let token = OAuthToken {
+            access_token,
+            refresh_token: metadata.mal_refresh_token.clone(),
+            expires_at: metadata.mal_token_expires,
+        };
+        match self.store(ProviderKind::Mal, token) {
+            Ok(()) => info!(
+                "Moved MAL tokens to the credential store; the mal_*_token entries in config.toml can be deleted"
+            ),
+            Err(e) => warn!("Failed to migrate MAL tokens: {}", e),
+        }
+    }
+
+    pub fn has_token(&self, kind: ProviderKind) -> bool {
+        self.credentials.lock().unwrap().get(kind).is_some()
+    }
+
+    pub fn store(&self, kind: ProviderKind, token: OAuthToken) -> Result<()> {
+        let mut credentials = self.credentials.lock().unwrap();
+        credentials.set(kind, token);
+        self.backend.save(&credentials)
+    }
+
+    fn current(&self, kind: ProviderKind) -> Result<OAuthToken> {
+        self.credentials
+            .lock()
+            .unwrap()
+            .get(kind)
+            .cloned()
+            .ok_or_else(|| Error::Unauthorized(kind.name().to_string()))
+    }
+
+    /// A usable access token, refreshed first if it's about to expire
+    pub async fn access_token(&self, kind: ProviderKind) -> Result<String> {
+        let token = self.current(kind)?;
+        if token.needs_refresh(chrono::Utc::now().timestamp()) && token.refresh_token.is_some() {
+            return self.refresh(kind, &token.access_token).await;
+        }
+        Ok(token.access_token)
+    }
+
+    /// Swap `stale` for a fresh token. Only MAL issues refresh tokens;
+    /// anything else has to log in again.
+    pub async fn refresh(&self, kind: ProviderKind, stale: &str) -> Result<String> {
+        let _guard = self.refresh_lock.lock().await;
+        let current = self.current(kind)?;
+        // Another task refreshed while we waited for the lock
+        if current.access_token != stale {
+            return Ok(current.access_token);
+        }
+
+        let unauthorized = || Error::Unauthorized(kind.name().to_string());
+        let (ProviderKind::Mal, Some(mal)) = (kind, &self.mal) else {
+            return Err(unauthorized());
+        };
+        let refresh_token = current.refresh_token.ok_or_else(unauthorized)?;
+
+        let resp = mal.refresh_access_token(&refresh_token).await?;
+        let token = OAuthToken::from_response(resp, chrono::Utc::now().timestamp());
+        self.store(kind, token.clone())?;
+        info!("Refreshed MAL access token");
+        Ok(token.access_token)
+    }
+
+    /// Run `f` with an access token, refreshing and retrying once if the
+    /// provider rejects it
+    pub async fn with_token<T, F, Fut>(&self, kind: ProviderKind, f: F) -> Result<T>
+    where
+        F: Fn(String) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let token = self.access_token(kind).await?;
+        match f(token.clone()).await {
+            Err(Error::Unauthorized(_)) => {
+                let token = self.refresh(kind, &token).await?;
+                f(token).await
+            }
+            other => other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::test_support::{mock_server, reply};
+
+    const NEW_TOKENS: &str = r#"{"access_token":"fresh","refresh_token":"r2","expires_in":2678400,"token_type":"Bearer"}"#;
+
+    fn token(access: &str, expires_at: Option<i64>) -> OAuthToken {
+        OAuthToken {
+            access_token: access.to_string(),
+            refresh_token: Some("r1".to_string()),
+            expires_at,
+        }
+    }
+
+    async fn manager(
+        dir: &tempfile::TempDir,
+        stored: OAuthToken,
+    ) -> (TokenManager, Arc<Mutex<Vec<String>>>) {
+        let (port, log) = mock_server(|req| {
+            assert!(req.body.contains("grant_type=refresh_token"));
+            assert!(req.body.contains("refresh_token=r1"));
+            reply(200, NEW_TOKENS)
+        })
+        .await;
+        let mal =
+            MalClient::new("id".to_string()).with_oauth_base(&format!("http://127.0.0.1:{}", port));
+        let backend = CredentialBackend::File(dir.path().join("credentials.toml"));
+        let manager = TokenManager::new(backend, Some(mal));
+        manager.store(ProviderKind::Mal, stored).unwrap();
+        (manager, log)
+    }
+
+    #[test]
+    fn test_needs_refresh_within_margin() {
+        let now = 1_000_000;
+        assert!(!token("a", None).needs_refresh(now));
+        assert!(!token("a", Some(now + 2 * REFRESH_MARGIN_SECS)).needs_refresh(now));
+        assert!(token("a", Some(now + 60)).needs_refresh(now));
+        assert!(token("a", Some(now - 60)).needs_refresh(now));
+    }
+
+    #[tokio::test]
+    async fn test_refreshes_before_expiry_and_persists() {
+        let dir = tempfile::tempdir().unwrap();
+        let soon = chrono::Utc::now().timestamp() + 60;
+        let (manager, log) = manager(&dir, token("old", Some(soon))).await;
+
+        assert_eq!(
+            manager.access_token(ProviderKind::Mal).await.unwrap(),
+            "fresh"
+        );
+        assert_eq!(log.lock().unwrap().as_slice(), ["POST /token"]);
+
+        let path = dir.path().join("credentials.toml");
+        let saved = CredentialBackend::File(path.clone()).load().unwrap();
+        assert_eq!(saved.mal.unwrap().refresh_token.as_deref(), Some("r2"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refreshes_and_retries_after_401() {
+        let dir = tempfile::tempdir().unwrap();
+        let (manager, log) = manager(&dir, token("revoked", None)).await;
+
+        let seen = Mutex::new(Vec::new());
+        let result = manager
+            .with_token(ProviderKind::Mal, |token| {
+                seen.lock().unwrap().push(token.clone());
+                async move {
+                    if token == "revoked" {
+                        Err(Error::Unauthorized("MyAnimeList".to_string()))
+                    } else {
+                        Ok(token)
+                    }
+                }
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(result, "fresh");
+        assert_eq!(*seen.lock().unwrap(), ["revoked", "fresh"]);
+        assert_eq!(log.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_anilist_cannot_refresh() {
+        let dir = tempfile::tempdir().unwrap();
+        let backend = CredentialBackend::File(dir.path().join("credentials.toml"));
+        let manager = TokenManager::new(backend, None);
+        manager
+            .store(
+                ProviderKind::Anilist,
+                OAuthToken {
+                    access_token: "tok".to_string(),
+                    refresh_token: None,
+                    expires_at: None,
+                },
+            )
+            .unwrap();
+
+        let err = manager
+            .with_token(ProviderKind::Anilist, |_| async {
+                Err::<(), _>(Error::Unauthorized("AniList".to_string()))
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::Unauthorized(_)));
+    }
+}
@@ -0,0 +1,95 @@
+//! Per-episode metadata: titles, air dates, filler and recap flags. Comes
+//! from the show folder's `episodes.toml` when there is one, otherwise from
+//! Jikan (MAL IDs) or Kitsu.
+
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+use crate::metadata::jikan::JikanClient;
+use crate::metadata::kitsu::KitsuClient;
+use crate::metadata::offline::OfflineDatabase;
+use crate::metadata::{AnimeMetadata, EpisodeMetadata, ProviderKind};
+
+/// Hand-written episode list kept next to the video files
+pub const LOCAL_FILE: &str = "episodes.toml";
+
+#[derive(Deserialize)]
+struct LocalEpisodeList {
+    #[serde(default)]
+    episode: Vec<EpisodeMetadata>,
+}
+
+/// The show folder's own episode list, if it has one
+pub fn load_local(show_dir: &Path) -> Result<Option<Vec<EpisodeMetadata>>> {
+    let path = show_dir.join(LOCAL_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path)?;
+    let list: LocalEpisodeList = toml::from_str(&content)?;
+    Ok(Some(list.episode))
+}
+
+/// Fetch the episode list for a series. MAL IDs go to Jikan, which knows
+/// filler; Kitsu IDs to Kitsu. Anything else is mapped to one of those
+/// through the offline database.
+pub async fn fetch(
+    metadata: &AnimeMetadata,
+    offline_db: Option<&OfflineDatabase>,
+) -> Result<Vec<EpisodeMetadata>> {
+    let id_on = |to: ProviderKind| {
+        if metadata.provider == to {
+            Some(metadata.id)
+        } else {
+            offline_db?.map_id(metadata.provider, metadata.id, to)
+        }
+    };
+
+    if let Some(mal_id) = id_on(ProviderKind::Mal) {
+        return JikanClient::new().episodes(mal_id).await;
+    }
+    if let Some(kitsu_id) = id_on(ProviderKind::Kitsu) {
+        return KitsuClient::new().episodes(kitsu_id).await;
+    }
+    Err(Error::Metadata(format!(
+        "No episode list source for {} ID {}",
+        metadata.provider.short_name(),
+        metadata.id
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_local_list() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(load_local(dir.path()).unwrap(), None);
+
+        std::fs::write(
+            dir.path().join(LOCAL_FILE),
+            r#"
+[[episode]]
+number = 1
+title = "The Journey's End"
+aired = "2023-09-29"
+synopsis = "The party returns to the capital."
+
+[[episode]]
+number = 2
+season = 2
+recap = true
+"#,
+        )
+        .unwrap();
+        let episodes = load_local(dir.path()).unwrap().unwrap();
+        assert_eq!(episodes.len(), 2);
+        assert_eq!(episodes[0].title.as_deref(), Some("The Journey's End"));
+        assert!(!episodes[0].is_skippable());
+        assert_eq!(episodes[1].season, Some(2));
+        assert!(episodes[1].is_skippable());
+    }
+}
@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+use reqwest::Client;
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+use crate::metadata::EpisodeMetadata;
+
+const JIKAN_API_BASE: &str = "https://api.jikan.moe/v4";
+
+/// Jikan allows 3 requests a second
+const PAGE_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Jikan, a read-only mirror of MyAnimeList. Used for episode lists, which
+/// MAL's own API doesn't offer. Takes MAL IDs, no client ID needed.
+pub struct JikanClient {
+    client: Client,
+    api_base: String,
+}
+
+impl JikanClient {
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+            api_base: JIKAN_API_BASE.to_string(),
+        }
+    }
+
+    /// Point API calls somewhere other than api.jikan.moe
+    #[cfg(test)]
+    pub fn with_api_base(mut self, base: &str) -> Self {
+        self.api_base = base.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Every episode of a MAL entry, following Jikan's pages (100 a page)
+    pub async fn episodes(&self, mal_id: u64) -> Result<Vec<EpisodeMetadata>> {
+        let url = format!("{}/anime/{}/episodes", self.api_base, mal_id);
+        let mut episodes = Vec::new();
+        let mut page = 1u32;
+        loop {
+            let response = self
+                .client
+                .get(&url)
+                .query(&[("page", page.to_string())])
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                return Err(Error::Metadata(format!(
+                    "Jikan API Error: {}",
+                    response.status()
+                )));
+            }
+            let body: JikanEpisodesResponse = response.json().await?;
+            episodes.extend(body.data.into_iter().map(JikanEpisode::into_metadata));
+
+            if !body.pagination.has_next_page {
+                return Ok(episodes);
+            }
+            page += 1;
+            tokio::time::sleep(PAGE_INTERVAL).await;
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct JikanEpisodesResponse {
+    pagination: JikanPagination,
+    #[serde(default)]
+    data: Vec<JikanEpisode>,
+}
+
+#[derive(Deserialize)]
+struct JikanPagination {
+    #[serde(default)]
+    has_next_page: bool,
+}
+
+#[derive(Deserialize)]
+struct JikanEpisode {
+    /// The episode number within the series
+    mal_id: u32,
+    title: Option<String>,
+    /// e.g. "2023-09-29T00:00:00+00:00"
+    aired: Option<String>,
+    #[serde(default)]
+    filler: bool,
+    #[serde(default)]
+    recap: bool,
+}
+
+impl JikanEpisode {
+    fn into_metadata(self) -> EpisodeMetadata {
+        EpisodeMetadata {
+            number: self.mal_id,
+            season: None,
+            title: self.title,
+            aired: self.aired.and_then(|a| a.get(..10).map(str::to_string)),
+            filler: self.filler,
+            recap: self.recap,
+            synopsis: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{mock_server, reply};
+
+    #[tokio::test]
+    async fn test_episodes_follow_pages() {
+        let (port, log) = mock_server(|req| {
+            if req.head.starts_with("GET /anime/20/episodes?page=1 ") {
+                reply(
+                    200,
+                    r#"{"pagination":{"last_visible_page":2,"has_next_page":true},"data":[{"mal_id":1,"title":"Enter: Naruto Uzumaki!","aired":"2002-10-03T00:00:00+00:00","filler":false,"recap":false}]}"#,
+                )
+            } else {
+                reply(
+                    200,
+                    r#"{"pagination":{"last_visible_page":2,"has_next_page":false},"data":[{"mal_id":26,"title":"Special Report: Live from the Forest of Death!","aired":null,"filler":false,"recap":true},{"mal_id":136,"title":"Deep Cover!? A Super S-Ranked Mission!","aired":"2006-10-12T00:00:00+00:00","filler":true,"recap":false}]}"#,
+                )
+            }
+        })
+        .await;
+        let client = JikanClient::new().with_api_base(&format!("http://127.0.0.1:{}", port));
+
+        let episodes = client.episodes(20).await.unwrap();
+        assert_eq!(log.lock().unwrap().len(), 2);
+        assert_eq!(episodes.len(), 3);
+        assert_eq!(episodes[0].aired.as_deref(), Some("2002-10-03"));
+        assert!(episodes[1].recap && episodes[1].is_skippable());
+        assert!(episodes[2].filler);
+        assert_eq!(episodes[2].number, 136);
+    }
+}
@@ -0,0 +1,307 @@
+use std::collections::HashMap;
+
+use reqwest::{Client, header};
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+use crate::metadata::{
+    AnimeMetadata, EpisodeMetadata, MetadataProvider, ProviderKind, year_from_date,
+};
+
+const KITSU_API_BASE: &str = "https://kitsu.io/api/edge";
+
+/// Kitsu's public JSON:API. Metadata only, no client ID needed.
+pub struct KitsuClient {
+    client: Client,
+    api_base: String,
+}
+
+impl KitsuClient {
+    pub fn new() -> Self {
+        let mut headers = header::HeaderMap::new();
+        headers.insert(
+            header::ACCEPT,
+            header::HeaderValue::from_static("application/vnd.api+json"),
+        );
+
+        let client = Client::builder()
+            .default_headers(headers)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            client,
+            api_base: KITSU_API_BASE.to_string(),
+        }
+    }
+
+    /// Point API calls somewhere other than kitsu.io
+    #[cfg(test)]
+    pub fn with_api_base(mut self, base: &str) -> Self {
+        self.api_base = base.trim_end_matches('/').to_string();
+        self
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(
+        &self,
+        url: &str,
+        query: &[(&str, &str)],
+    ) -> Result<T> {
+        let response = self.client.get(url).query(query).send().await?;
+        if !response.status().is_success() {
+            return Err(Error::Metadata(format!(
+                "Kitsu API Error: {}",
+                response.status()
+            )));
+        }
+        Ok(response.json().await?)
+    }
+
+    /// Every episode of a Kitsu entry, following its page links
+    pub async fn episodes(&self, id: u64) -> Result<Vec<EpisodeMetadata>> {
+        let mut url = format!("{}/anime/{}/episodes", self.api_base, id);
+        let mut query = vec![("page[limit]", "20"), ("sort", "number")];
+        let mut episodes = Vec::new();
+        loop {
+            let resp: KitsuEpisodesResponse = self.get(&url, &query).await?;
+            episodes.extend(resp.data.into_iter().filter_map(|e| {
+                let a = e.attributes;
+                Some(EpisodeMetadata {
+                    number: a.number?,
+                    season: None,
+                    title: a.canonical_title.filter(|t| !t.is_empty()),
+                    aired: a.airdate,
+                    filler: false,
+                    recap: false,
+                    synopsis: a.synopsis.or(a.description).filter(|s| !s.is_empty()),
+                })
+            }));
+
+            // The next link carries the paging query itself
+            match resp.links.and_then(|l| l.next) {
+                Some(next) if next.starts_with(&self.api_base) => {
+                    url = next;
+                    query.clear();
+                }
+                _ => return Ok(episodes),
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct KitsuListResponse {
+    data: Vec<KitsuAnime>,
+    #[serde(default)]
+    included: Vec<KitsuIncluded>,
+}
+
+#[derive(Deserialize)]
+struct KitsuSingleResponse {
+    data: KitsuAnime,
+    #[serde(default)]
+    included: Vec<KitsuIncluded>,
+}
+
+#[derive(Deserialize)]
+struct KitsuEpisodesResponse {
+    #[serde(default)]
+    data: Vec<KitsuEpisode>,
+    links: Option<KitsuLinks>,
+}
+
+#[derive(Deserialize)]
+struct KitsuLinks {
+    next: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct KitsuEpisode {
+    attributes: KitsuEpisodeAttributes,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KitsuEpisodeAttributes {
+    number: Option<u32>,
+    canonical_title: Option<String>,
+    airdate: Option<String>,
+    synopsis: Option<String>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct KitsuAnime {
+    id: String,
+    attributes: KitsuAttributes,
+    #[serde(default)]
+    relationships: Option<KitsuRelationships>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KitsuAttributes {
+    canonical_title: String,
+    #[serde(default)]
+    titles: HashMap<String, Option<String>>,
+    synopsis: Option<String>,
+    /// Percentage as a string, e.g. "82.47"
+    average_rating: Option<String>,
+    status: Option<String>,
+    episode_count: Option<u32>,
+    poster_image: Option<KitsuImage>,
+    start_date: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct KitsuImage {
+    large: Option<String>,
+    medium: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct KitsuRelationships {
+    categories: Option<KitsuRelation>,
+}
+
+#[derive(Deserialize)]
+struct KitsuRelation {
+    #[serde(default)]
+    data: Vec<KitsuRef>,
+}
+
+#[derive(Deserialize)]
+struct KitsuRef {
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct KitsuIncluded {
+    id: String,
+    #[serde(rename = "type")]
+    kind: String,
+    attributes: KitsuIncludedAttributes,
+}
+
+#[derive(Deserialize)]
+struct KitsuIncludedAttributes {
+    title: Option<String>,
+}
+
+/// Map Kitsu's status onto the MAL strings the UI already knows
+fn normalize_status(status: Option<&str>) -> String {
+    match status {
+        Some("current") => "currently_airing".to_string(),
+        Some("finished") => "finished_airing".to_string(),
+        Some("upcoming" | "unreleased" | "tba") => "not_yet_aired".to_string(),
+        Some(other) => other.to_string(),
+        None => "Unknown".to_string(),
+    }
+}
+
+fn to_metadata(anime: KitsuAnime, included: &[KitsuIncluded]) -> Option<AnimeMetadata> {
+    let a = anime.attributes;
+    let genres = anime
+        .relationships
+        .and_then(|r| r.categories)
+        .map(|c| {
+            c.data
+                .iter()
+                .filter_map(|r| {
+                    included
+                        .iter()
+                        .find(|i| i.kind == "categories" && i.id == r.id)
+                        .and_then(|i| i.attributes.title.clone())
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Release names follow the romanized title, like MAL's main title
+    let title = a
+        .titles
+        .get("en_jp")
+        .cloned()
+        .flatten()
+        .unwrap_or(a.canonical_title);
+
+    Some(AnimeMetadata {
+        id: anime.id.parse().ok()?,
+        title,
+        cover_url: a.poster_image.and_then(|p| p.large.or(p.medium)),
+        synopsis: a.synopsis,
+        score: a
+            .average_rating
+            .and_then(|r| r.parse::<f64>().ok())
+            .map(|r| (r / 10.0 * 100.0).round() / 100.0),
+        status: normalize_status(a.status.as_deref()),
+        episodes: a.episode_count,
+        genres,
+        provider: ProviderKind::Kitsu,
+        next_airing: None,
+        year: a.start_date.as_deref().and_then(year_from_date),
+        broadcast: None,
+    })
+}
+
+#[async_trait::async_trait]
+impl MetadataProvider for KitsuClient {
+    async fn search(&self, query: &str) -> Result<Vec<AnimeMetadata>> {
+        let url = format!("{}/anime", self.api_base);
+        let resp: KitsuListResponse = self
+            .get(
+                &url,
+                &[
+                    ("filter[text]", query),
+                    ("page[limit]", "5"),
+                    ("include", "categories"),
+                ],
+            )
+            .await?;
+
+        Ok(resp
+            .data
+            .into_iter()
+            .filter_map(|a| to_metadata(a, &resp.included))
+            .collect())
+    }
+
+    async fn get_details(&self, id: u64) -> Result<AnimeMetadata> {
+        let url = format!("{}/anime/{}", self.api_base, id);
+        let resp: KitsuSingleResponse = self.get(&url, &[("include", "categories")]).await?;
+        to_metadata(resp.data, &resp.included)
+            .ok_or_else(|| Error::Metadata(format!("Kitsu returned a bad ID for {}", id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{mock_server, reply};
+
+    #[tokio::test]
+    async fn test_search_maps_anime_and_categories() {
+        let (port, log) = mock_server(|_| {
+            reply(
+                200,
+                r#"{"data":[{"id":"46474","type":"anime","attributes":{"canonicalTitle":"Frieren: Beyond Journey's End","titles":{"en":"Frieren","en_jp":"Sousou no Frieren"},"synopsis":"An elf mage.","averageRating":"88.47","status":"finished","episodeCount":28,"posterImage":{"large":"https://media.kitsu.io/l.jpg","medium":"https://media.kitsu.io/m.jpg"}},"relationships":{"categories":{"data":[{"type":"categories","id":"150"}]}}}],"included":[{"id":"150","type":"categories","attributes":{"title":"Fantasy"}}]}"#,
+            )
+        })
+        .await;
+        let client = KitsuClient::new().with_api_base(&format!("http://127.0.0.1:{}", port));
+
+        let results = client.search("frieren").await.unwrap();
+        assert_eq!(log.lock().unwrap()[0], "GET /anime");
+        let anime = &results[0];
+        assert_eq!(anime.id, 46474);
+        assert_eq!(anime.title, "Sousou no Frieren");
+        assert_eq!(anime.score, Some(8.85));
+        assert_eq!(anime.status, "finished_airing");
+        assert_eq!(anime.genres, vec!["Fantasy".to_string()]);
+        assert_eq!(
+            anime.cover_url.as_deref(),
+            Some("https://media.kitsu.io/l.jpg")
+        );
+        assert_eq!(anime.provider, ProviderKind::Kitsu);
+    }
+}
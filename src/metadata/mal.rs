@@ -1,15 +1,24 @@
 use crate::error::{Error, Result};
-use crate::metadata::{AnimeMetadata, MetadataProvider};
+use crate::metadata::{
+    AnimeMetadata, Broadcast, ListStatus, ListSync, MetadataProvider, ProviderKind, UserAnimeEntry,
+    year_from_date,
+};
 use reqwest::{Client, header};
 use serde::{Deserialize, Serialize};
 
 const MAL_API_BASE: &str = "https://api.myanimelist.net/v2";
 const MAL_OAUTH_BASE: &str = "https://myanimelist.net/v1/oauth2";
 
+/// Fields requested for search results and details
+const ANIME_FIELDS: &str =
+    "start_date,end_date,mean,status,num_episodes,synopsis,main_picture,genres,broadcast";
+
 pub struct MalClient {
     client: Client,
     client_id: String,
     access_token: Option<String>,
+    api_base: String,
+    oauth_base: String,
 }
 
 impl MalClient {
@@ -29,16 +38,28 @@ impl MalClient {
             client,
             client_id,
             access_token: None,
+            api_base: MAL_API_BASE.to_string(),
+            oauth_base: MAL_OAUTH_BASE.to_string(),
         }
     }
 
-    pub fn with_access_token(mut self, token: String) -> Self {
-        self.access_token = Some(token);
+    /// Point API calls somewhere other than api.myanimelist.net
+    #[cfg(test)]
+    pub fn with_api_base(mut self, base: &str) -> Self {
+        self.api_base = base.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Point token requests somewhere other than myanimelist.net
+    #[cfg(test)]
+    pub fn with_oauth_base(mut self, base: &str) -> Self {
+        self.oauth_base = base.trim_end_matches('/').to_string();
         self
     }
 
-    pub fn set_access_token(&mut self, token: String) {
+    pub fn with_access_token(mut self, token: String) -> Self {
         self.access_token = Some(token);
+        self
     }
 
     pub fn generate_pkce_pair() -> (String, String) {
@@ -55,23 +76,46 @@ impl MalClient {
         (code_verifier, code_challenge)
     }
 
-    pub fn build_auth_url(&self, code_challenge: &str) -> String {
-        format!(
-            "{}/authorize?response_type=code&client_id={}&code_challenge={}",
-            MAL_OAUTH_BASE, self.client_id, code_challenge
-        )
+    /// `redirect_uri` is only needed when it isn't the app's sole
+    /// registered redirect URL; MAL then expects it again on exchange
+    pub fn build_auth_url(
+        &self,
+        code_challenge: &str,
+        state: &str,
+        redirect_uri: Option<&str>,
+    ) -> String {
+        let mut url = reqwest::Url::parse(&format!("{}/authorize", self.oauth_base))
+            .expect("OAuth base is a valid URL");
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.client_id)
+            .append_pair("code_challenge", code_challenge)
+            .append_pair("state", state);
+        if let Some(uri) = redirect_uri {
+            url.query_pairs_mut().append_pair("redirect_uri", uri);
+        }
+        url.to_string()
     }
 
-    pub async fn exchange_code(&self, code: &str, code_verifier: &str) -> Result<TokenResponse> {
+    pub async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        redirect_uri: Option<&str>,
+    ) -> Result<TokenResponse> {
+        let mut form = vec![
+            ("client_id", self.client_id.as_str()),
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("code_verifier", code_verifier),
+        ];
+        if let Some(uri) = redirect_uri {
+            form.push(("redirect_uri", uri));
+        }
         let response = self
             .client
-            .post(&format!("{}/token", MAL_OAUTH_BASE))
-            .form(&[
-                ("client_id", self.client_id.as_str()),
-                ("grant_type", "authorization_code"),
-                ("code", code),
-                ("code_verifier", code_verifier),
-            ])
+            .post(&format!("{}/token", self.oauth_base))
+            .form(&form)
             .send()
             .await?;
 
@@ -91,7 +135,7 @@ impl MalClient {
     pub async fn refresh_access_token(&self, refresh_token: &str) -> Result<TokenResponse> {
         let response = self
             .client
-            .post(&format!("{}/token", MAL_OAUTH_BASE))
+            .post(&format!("{}/token", self.oauth_base))
             .form(&[
                 ("client_id", self.client_id.as_str()),
                 ("grant_type", "refresh_token"),
@@ -113,52 +157,159 @@ impl MalClient {
         Ok(token)
     }
 
-    pub async fn get_user_animelist(&self, status: &str) -> Result<Vec<UserAnimeEntry>> {
-        let access_token = self
-            .access_token
-            .as_ref()
-            .ok_or_else(|| Error::Metadata("No access token set".to_string()))?;
+    /// Every entry with `status` on the user's list, following `paging.next`
+    pub async fn get_user_animelist(&self, status: ListStatus) -> Result<Vec<UserAnimeEntry>> {
+        let access_token = self.require_token()?;
+        let status_param = match status {
+            ListStatus::Watching => "watching",
+            ListStatus::PlanToWatch => "plan_to_watch",
+            ListStatus::OnHold => "on_hold",
+            ListStatus::Completed => "completed",
+        };
+
+        let mut entries = Vec::new();
+        let mut request = self
+            .client
+            .get(&format!("{}/users/@me/animelist", self.api_base))
+            .query(&[
+                ("status", status_param),
+                ("limit", "1000"),
+                ("fields", "list_status{num_episodes_watched},num_episodes"),
+            ]);
+        loop {
+            let response = request
+                .header("Authorization", format!("Bearer {}", access_token))
+                .send()
+                .await?;
+
+            check_authorized(&response)?;
+            if !response.status().is_success() {
+                let status_code = response.status();
+                let body = response.text().await.unwrap_or_default();
+                return Err(Error::Metadata(format!(
+                    "Animelist fetch failed: {} - {}",
+                    status_code, body
+                )));
+            }
+
+            let resp: UserAnimeListResponse = response.json().await?;
+            entries.extend(resp.data.into_iter().map(|node| {
+                UserAnimeEntry {
+                    id: node.node.id,
+                    title: node.node.title,
+                    num_episodes: node.node.num_episodes,
+                    num_watched: node
+                        .list_status
+                        .map(|s| s.num_episodes_watched)
+                        .unwrap_or(0),
+                    status,
+                }
+            }));
+
+            // `next` is a full URL; never send the token anywhere but the API
+            match resp.paging.and_then(|p| p.next) {
+                Some(next) if next.starts_with(&self.api_base) => {
+                    request = self.client.get(&next);
+                }
+                Some(next) => {
+                    return Err(Error::Metadata(format!(
+                        "Unexpected animelist page URL: {}",
+                        next
+                    )));
+                }
+                None => break,
+            }
+        }
+        Ok(entries)
+    }
 
-        let url = format!("{}/users/@me/animelist", MAL_API_BASE);
+    /// The user's list entry for one anime, `None` if it isn't on their list
+    pub async fn get_my_list_status(&self, anime_id: u64) -> Result<Option<MyListStatus>> {
+        let access_token = self.require_token()?;
 
         let response = self
             .client
-            .get(&url)
+            .get(&format!("{}/anime/{}", self.api_base, anime_id))
             .header("Authorization", format!("Bearer {}", access_token))
-            .query(&[
-                ("status", status),
-                ("limit", "100"),
-                ("fields", "list_status{num_episodes_watched},num_episodes"),
-            ])
+            .query(&[("fields", "my_list_status,num_episodes")])
             .send()
             .await?;
 
+        check_authorized(&response)?;
         if !response.status().is_success() {
             let status_code = response.status();
             let body = response.text().await.unwrap_or_default();
             return Err(Error::Metadata(format!(
-                "Animelist fetch failed: {} - {}",
+                "List status fetch failed: {} - {}",
                 status_code, body
             )));
         }
 
-        let resp: UserAnimeListResponse = response.json().await?;
-        Ok(resp
-            .data
-            .into_iter()
-            .map(|node| UserAnimeEntry {
-                mal_id: node.node.id,
-                title: node.node.title,
-                num_episodes: node.node.num_episodes,
-                num_watched: node
-                    .list_status
-                    .map(|s| s.num_episodes_watched)
-                    .unwrap_or(0),
-            })
-            .collect())
+        let resp: MyListStatusResponse = response.json().await?;
+        Ok(resp.my_list_status)
+    }
+
+    /// Update the user's list entry for one anime. Fields left `None` are
+    /// not sent, so MAL keeps their current values.
+    pub async fn update_my_list_status(
+        &self,
+        anime_id: u64,
+        status: Option<&str>,
+        num_watched_episodes: u32,
+        score: Option<u8>,
+    ) -> Result<MyListStatus> {
+        let access_token = self.require_token()?;
+
+        let watched = num_watched_episodes.to_string();
+        let score = score.map(|s| s.to_string());
+        let mut form = vec![("num_watched_episodes", watched.as_str())];
+        if let Some(status) = status {
+            form.push(("status", status));
+        }
+        if let Some(score) = &score {
+            form.push(("score", score.as_str()));
+        }
+
+        let response = self
+            .client
+            .patch(&format!(
+                "{}/anime/{}/my_list_status",
+                self.api_base, anime_id
+            ))
+            .header("Authorization", format!("Bearer {}", access_token))
+            .form(&form)
+            .send()
+            .await?;
+
+        check_authorized(&response)?;
+        if !response.status().is_success() {
+            let status_code = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(Error::Metadata(format!(
+                "List status update failed: {} - {}",
+                status_code, body
+            )));
+        }
+
+        Ok(response.json().await?)
+    }
+
+    fn require_token(&self) -> Result<&str> {
+        self.access_token
+            .as_deref()
+            .ok_or_else(|| Error::Metadata("No access token set".to_string()))
     }
 }
 
+/// Expired or revoked tokens get a 401, which the token manager answers
+/// with a refresh
+fn check_authorized(response: &reqwest::Response) -> Result<()> {
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        return Err(Error::Unauthorized("MyAnimeList".to_string()));
+    }
+    Ok(())
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct TokenResponse {
     pub access_token: String,
@@ -167,23 +318,35 @@ pub struct TokenResponse {
     pub token_type: String,
 }
 
-#[derive(Debug)]
-pub struct UserAnimeEntry {
-    pub mal_id: u64,
-    pub title: String,
-    pub num_episodes: Option<u32>,
-    pub num_watched: u32,
+/// An anime's entry on the user's own list
+#[derive(Debug, Deserialize)]
+pub struct MyListStatus {
+    #[serde(default)]
+    pub num_episodes_watched: u32,
+}
+
+#[derive(Deserialize)]
+struct MyListStatusResponse {
+    #[serde(default)]
+    my_list_status: Option<MyListStatus>,
 }
 
 #[derive(Deserialize)]
 struct UserAnimeListResponse {
     data: Vec<UserAnimeNode>,
+    #[serde(default)]
+    paging: Option<Paging>,
+}
+
+#[derive(Deserialize)]
+struct Paging {
+    next: Option<String>,
 }
 
 #[derive(Deserialize)]
 struct UserAnimeNode {
     node: UserAnimeData,
-    list_status: Option<ListStatus>,
+    list_status: Option<UserListStatus>,
 }
 
 #[derive(Deserialize)]
@@ -194,7 +357,7 @@ struct UserAnimeData {
 }
 
 #[derive(Deserialize)]
-struct ListStatus {
+struct UserListStatus {
     num_episodes_watched: u32,
 }
 
@@ -207,12 +370,47 @@ struct MalNode {
 struct MalAnimeData {
     id: u64,
     title: String,
+    start_date: Option<String>,
     main_picture: Option<MalPicture>,
     synopsis: Option<String>,
     mean: Option<f64>,
     status: Option<String>,
     num_episodes: Option<u32>,
     genres: Option<Vec<MalGenre>>,
+    broadcast: Option<MalBroadcast>,
+}
+
+impl MalAnimeData {
+    fn into_metadata(self) -> AnimeMetadata {
+        AnimeMetadata {
+            id: self.id,
+            title: self.title,
+            cover_url: self.main_picture.and_then(|p| p.large.or(p.medium)),
+            synopsis: self.synopsis,
+            score: self.mean,
+            status: self.status.unwrap_or_else(|| "Unknown".to_string()),
+            episodes: self.num_episodes,
+            genres: self
+                .genres
+                .map(|g| g.into_iter().map(|ge| ge.name).collect())
+                .unwrap_or_default(),
+            provider: ProviderKind::Mal,
+            next_airing: None,
+            year: self.start_date.as_deref().and_then(year_from_date),
+            broadcast: self.broadcast.map(|b| Broadcast {
+                day: b.day_of_the_week,
+                time: b.start_time,
+            }),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct MalBroadcast {
+    /// e.g. "saturday"
+    day_of_the_week: String,
+    /// JST, e.g. "23:00"
+    start_time: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -236,19 +434,12 @@ struct MalSearchResponse {
 #[async_trait::async_trait]
 impl MetadataProvider for MalClient {
     async fn search(&self, query: &str) -> Result<Vec<AnimeMetadata>> {
-        let url = format!("{}/anime", MAL_API_BASE);
+        let url = format!("{}/anime", self.api_base);
 
         let response = self
             .client
             .get(&url)
-            .query(&[
-                ("q", query),
-                ("limit", "5"),
-                (
-                    "fields",
-                    "start_date,end_date,mean,status,num_episodes,synopsis,main_picture,genres",
-                ),
-            ])
+            .query(&[("q", query), ("limit", "5"), ("fields", ANIME_FIELDS)])
             .send()
             .await?;
 
@@ -264,36 +455,18 @@ impl MetadataProvider for MalClient {
         let results = resp_json
             .data
             .into_iter()
-            .map(|node| {
-                let a = node.node;
-                AnimeMetadata {
-                    id: a.id,
-                    title: a.title,
-                    cover_url: a.main_picture.and_then(|p| p.large.or(p.medium)),
-                    synopsis: a.synopsis,
-                    score: a.mean,
-                    status: a.status.unwrap_or_else(|| "Unknown".to_string()),
-                    episodes: a.num_episodes,
-                    genres: a
-                        .genres
-                        .map(|g| g.into_iter().map(|ge| ge.name).collect())
-                        .unwrap_or_default(),
-                }
-            })
+            .map(|node| node.node.into_metadata())
             .collect();
 
         Ok(results)
     }
 
     async fn get_details(&self, id: u64) -> Result<AnimeMetadata> {
-        let url = format!("{}/anime/{}", MAL_API_BASE, id);
+        let url = format!("{}/anime/{}", self.api_base, id);
         let response = self
             .client
             .get(&url)
-            .query(&[(
-                "fields",
-                "start_date,end_date,mean,status,num_episodes,synopsis,main_picture,genres",
-            )])
+            .query(&[("fields", ANIME_FIELDS)])
             .send()
             .await?;
 
@@ -305,18 +478,33 @@ impl MetadataProvider for MalClient {
         }
 
         let a: MalAnimeData = response.json().await?;
-        Ok(AnimeMetadata {
-            id: a.id,
-            title: a.title,
-            cover_url: a.main_picture.and_then(|p| p.large.or(p.medium)),
-            synopsis: a.synopsis,
-            score: a.mean,
-            status: a.status.unwrap_or_else(|| "Unknown".to_string()),
-            episodes: a.num_episodes,
-            genres: a
-                .genres
-                .map(|g| g.into_iter().map(|ge| ge.name).collect())
-                .unwrap_or_default(),
-        })
+        Ok(a.into_metadata())
+    }
+}
+
+#[async_trait::async_trait]
+impl ListSync for MalClient {
+    async fn list_entries(&self, status: ListStatus) -> Result<Vec<UserAnimeEntry>> {
+        self.get_user_animelist(status).await
+    }
+
+    async fn list_progress(&self, anime_id: u64) -> Result<Option<u32>> {
+        Ok(self
+            .get_my_list_status(anime_id)
+            .await?
+            .map(|s| s.num_episodes_watched))
+    }
+
+    async fn update_progress(
+        &self,
+        anime_id: u64,
+        episodes_watched: u32,
+        completed: bool,
+        score: Option<u8>,
+    ) -> Result<()> {
+        let status = if completed { "completed" } else { "watching" };
+        self.update_my_list_status(anime_id, Some(status), episodes_watched, score)
+            .await?;
+        Ok(())
     }
 }
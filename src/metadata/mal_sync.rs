@@ -1,51 +1,506 @@
-//! MAL Sync - Import "Currently Watching" anime from MyAnimeList
+//! MAL Sync - Import the user's MyAnimeList or AniList list and push local
+//! watch progress back
 
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::config::{ImportTarget, ListImportConfig, data_dir};
 use crate::error::Result;
-use crate::library::models::TrackedSeries;
-use crate::metadata::mal::{MalClient, UserAnimeEntry};
+use crate::library::Library;
+use crate::library::models::{TrackedSeries, WatchlistEntry};
+use crate::metadata::{ListStatus, ListSync, ProviderKind, UserAnimeEntry};
+
+/// Local episodes an import would mark watched
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backfill {
+    pub show_id: String,
+    pub title: String,
+    /// Mark everything up to and including this episode
+    pub through: u32,
+    /// How many episodes that flips from unwatched
+    pub newly_watched: usize,
+}
+
+/// Everything an import would change, shown to the user before it's applied
+#[derive(Debug, Default)]
+pub struct ImportPlan {
+    pub tracked: Vec<TrackedSeries>,
+    pub watchlist: Vec<WatchlistEntry>,
+    pub backfill: Vec<Backfill>,
+    /// Already in the library, nothing to do
+    pub unchanged: usize,
+    /// Backfill entries with no matching show in the library
+    pub unmatched: usize,
+}
+
+impl ImportPlan {
+    pub fn is_empty(&self) -> bool {
+        self.tracked.is_empty() && self.watchlist.is_empty() && self.backfill.is_empty()
+    }
+
+    /// Commit the plan, returning how many entries it touched
+    pub fn apply(self, library: &mut Library) -> usize {
+        let count = self.tracked.len() + self.watchlist.len() + self.backfill.len();
+        library.tracked_shows.extend(self.tracked);
+        library.watchlist.extend(self.watchlist);
+        for backfill in self.backfill {
+            let Some(show) = library.get_show_mut(&backfill.show_id) else {
+                continue;
+            };
+            for ep in show
+                .episodes
+                .iter_mut()
+                .filter(|e| e.number <= backfill.through && !e.watched)
+            {
+                ep.watched = true;
+                ep.last_position = 0;
+            }
+        }
+        count
+    }
+}
+
+/// A tracked series for an anime from the user's list, searching from the
+/// next unwatched episode
+pub fn new_tracked(
+    provider: ProviderKind,
+    anime_id: u64,
+    title: &str,
+    num_watched: u32,
+) -> TrackedSeries {
+    TrackedSeries {
+        id: format!("{}-{}", provider.id_prefix(), anime_id),
+        title: title.to_string(),
+        query: title.to_string(), // Use list title as Nyaa search query
+        filter_group: None,
+        filter_quality: None,
+        min_episode: num_watched + 1,
+        season: 1,
+        metadata_id: Some(anime_id),
+        cached_metadata: None,
+        seeding: None,
+        scoring_profile: None,
+        upgrade: false,
+    }
+}
 
-/// Import user's "Currently Watching" anime list from MAL into TrackedSeries
+/// Fetch every list status that the import config doesn't skip
+pub async fn fetch_list<C: ListSync + ?Sized>(
+    client: &C,
+    import: &ListImportConfig,
+) -> Result<Vec<UserAnimeEntry>> {
+    let mut entries = Vec::new();
+    for status in ListStatus::ALL {
+        if import.target(status) != ImportTarget::Skip {
+            entries.extend(client.list_entries(status).await?);
+        }
+    }
+    Ok(entries)
+}
+
+/// Work out what importing `entries` would do, without touching the library
+///
+/// - Tracked: a TrackedSeries searching from the next unwatched episode
+/// - Watchlist: remembered without downloading
+/// - Backfill: episodes of the matching library show marked watched
 ///
-/// For each anime:
-/// - Creates a TrackedSeries with title as the search query
-/// - Sets min_episode to num_watched + 1 (skip already watched)
-/// - Skips anime already in existing_tracked
-pub fn import_watching_list(
+/// Anything already tracked or on the watchlist is left alone.
+pub fn plan_import(
     entries: Vec<UserAnimeEntry>,
-    existing_tracked: &[TrackedSeries],
-) -> Vec<TrackedSeries> {
-    entries
-        .into_iter()
-        .filter(|entry| {
-            // Skip if already tracked (by MAL ID or title match)
-            !existing_tracked.iter().any(|t| {
-                t.metadata_id == Some(entry.mal_id)
-                    || t.title.to_lowercase() == entry.title.to_lowercase()
-            })
-        })
-        .map(|entry| {
-            let id = format!("mal-{}", entry.mal_id);
-            TrackedSeries {
-                id,
-                title: entry.title.clone(),
-                query: entry.title, // Use MAL title as Nyaa search query
-                filter_group: None,
-                filter_quality: None,
-                min_episode: entry.num_watched + 1, // Start from next unwatched
-                season: 1,
-                metadata_id: Some(entry.mal_id),
-                cached_metadata: None,
+    library: &Library,
+    import: &ListImportConfig,
+    provider: ProviderKind,
+) -> ImportPlan {
+    let mut plan = ImportPlan::default();
+    for entry in entries {
+        let tracked = library.tracked_shows.iter().any(|t| {
+            t.metadata_id == Some(entry.id) || t.title.to_lowercase() == entry.title.to_lowercase()
+        });
+        match import.target(entry.status) {
+            ImportTarget::Skip => {}
+            ImportTarget::Tracked if tracked => plan.unchanged += 1,
+            ImportTarget::Tracked => plan.tracked.push(new_tracked(
+                provider,
+                entry.id,
+                &entry.title,
+                entry.num_watched,
+            )),
+            ImportTarget::Watchlist => {
+                let listed = library
+                    .watchlist
+                    .iter()
+                    .any(|w| w.provider == provider && w.anime_id == entry.id);
+                if tracked || listed {
+                    plan.unchanged += 1;
+                    continue;
+                }
+                plan.watchlist.push(WatchlistEntry {
+                    provider,
+                    anime_id: entry.id,
+                    title: entry.title,
+                    status: entry.status,
+                    num_episodes: entry.num_episodes,
+                    num_watched: entry.num_watched,
+                });
             }
-        })
-        .collect()
+            ImportTarget::Backfill => {
+                // Completed entries sometimes keep a stale episode count
+                let through = match entry.status {
+                    ListStatus::Completed => entry.num_watched.max(entry.num_episodes.unwrap_or(0)),
+                    _ => entry.num_watched,
+                };
+                let show = library.shows.iter().find(|show| match &show.metadata {
+                    Some(m) => m.provider == provider && m.id == entry.id,
+                    None => show.title.eq_ignore_ascii_case(&entry.title),
+                });
+                let Some(show) = show else {
+                    plan.unmatched += 1;
+                    continue;
+                };
+                let newly_watched = show
+                    .episodes
+                    .iter()
+                    .filter(|e| e.number <= through && !e.watched)
+                    .count();
+                if newly_watched == 0 {
+                    plan.unchanged += 1;
+                    continue;
+                }
+                plan.backfill.push(Backfill {
+                    show_id: show.id.clone(),
+                    title: show.title.clone(),
+                    through,
+                    newly_watched,
+                });
+            }
+        }
+    }
+    plan
+}
+
+/// Local watch progress for one anime, waiting to be pushed to the list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProgressUpdate {
+    #[serde(default)]
+    pub provider: ProviderKind,
+    pub anime_id: u64,
+    pub episodes_watched: u32,
+    #[serde(default)]
+    pub total_episodes: Option<u32>,
+    #[serde(default)]
+    pub score: Option<u8>,
+}
+
+impl ProgressUpdate {
+    pub fn is_complete(&self) -> bool {
+        self.total_episodes
+            .is_some_and(|total| total > 0 && self.episodes_watched >= total)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PushOutcome {
+    Updated,
+    /// The list already had this many episodes or more; nothing was sent
+    RemoteAhead(u32),
+}
+
+/// Push progress to the user's list. Conflicts resolve to the higher
+/// episode count: if the remote list is ahead it is left alone and its
+/// count is returned so the library can catch up.
+pub async fn push_progress<C: ListSync + ?Sized>(
+    client: &C,
+    update: &ProgressUpdate,
+) -> Result<PushOutcome> {
+    let remote_watched = client.list_progress(update.anime_id).await?.unwrap_or(0);
+    if remote_watched > update.episodes_watched {
+        return Ok(PushOutcome::RemoteAhead(remote_watched));
+    }
+
+    client
+        .update_progress(
+            update.anime_id,
+            update.episodes_watched,
+            update.is_complete(),
+            update.score,
+        )
+        .await?;
+    Ok(PushOutcome::Updated)
+}
+
+/// Progress updates that couldn't be pushed yet (offline, not logged in),
+/// persisted so they are retried on the next run
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MalSyncQueue {
+    #[serde(default)]
+    pub pending: Vec<ProgressUpdate>,
+}
+
+impl MalSyncQueue {
+    pub fn load() -> Result<Self> {
+        let path = data_dir()?.join("mal_queue.toml");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let dir = data_dir()?;
+        std::fs::create_dir_all(&dir)?;
+        let content = toml::to_string_pretty(self)?;
+        std::fs::write(dir.join("mal_queue.toml"), content)?;
+        Ok(())
+    }
+
+    /// Queue an update, merging with any pending one for the same anime.
+    /// The higher episode count wins and a score is never dropped.
+    pub fn enqueue(&mut self, update: ProgressUpdate) {
+        match self
+            .pending
+            .iter_mut()
+            .find(|p| p.provider == update.provider && p.anime_id == update.anime_id)
+        {
+            Some(existing) => {
+                existing.episodes_watched = existing.episodes_watched.max(update.episodes_watched);
+                existing.total_episodes = update.total_episodes.or(existing.total_episodes);
+                existing.score = update.score.or(existing.score);
+            }
+            None => self.pending.push(update),
+        }
+    }
+
+    /// Drop pending updates a successful push covered: same anime, no more
+    /// episodes, and no score that wasn't sent. Anything queued later stays.
+    pub fn remove(&mut self, update: &ProgressUpdate) {
+        self.pending.retain(|p| {
+            let covered = p.provider == update.provider
+                && p.anime_id == update.anime_id
+                && p.episodes_watched <= update.episodes_watched
+                && (p.score.is_none() || p.score == update.score);
+            !covered
+        });
+    }
+
+    pub fn persist(&self) {
+        if let Err(e) = self.save() {
+            warn!("Failed to save list sync queue: {}", e);
+        }
+    }
 }
 
-/// Perform full MAL sync: fetch watching list and convert to TrackedSeries
-pub async fn sync_from_mal(
-    client: &MalClient,
-    existing_tracked: &[TrackedSeries],
-) -> Result<Vec<TrackedSeries>> {
-    let watching = client.get_user_animelist("watching").await?;
-    let new_tracked = import_watching_list(watching, existing_tracked);
-    Ok(new_tracked)
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::metadata::mal::MalClient;
+    use crate::test_support::{mock_server, reply};
+
+    fn update(episodes_watched: u32, total: Option<u32>, score: Option<u8>) -> ProgressUpdate {
+        ProgressUpdate {
+            provider: ProviderKind::Mal,
+            anime_id: 52991,
+            episodes_watched,
+            total_episodes: total,
+            score,
+        }
+    }
+
+    async fn client_for(
+        remote_watched: u32,
+    ) -> (MalClient, Arc<Mutex<Vec<String>>>, Arc<Mutex<String>>) {
+        let patch_body = Arc::new(Mutex::new(String::new()));
+        let seen = patch_body.clone();
+        let (port, log) = mock_server(move |req| {
+            assert_eq!(req.header("authorization"), Some("Bearer tok"));
+            if req.method == "PATCH" {
+                *seen.lock().unwrap() = req.body.clone();
+                reply(200, r#"{"status":"watching","score":0,"num_episodes_watched":0}"#)
+            } else {
+                reply(
+                    200,
+                    &format!(
+                        r#"{{"id":52991,"my_list_status":{{"status":"watching","score":0,"num_episodes_watched":{}}}}}"#,
+                        remote_watched
+                    ),
+                )
+            }
+        })
+        .await;
+        let client = MalClient::new("id".to_string())
+            .with_access_token("tok".to_string())
+            .with_api_base(&format!("http://127.0.0.1:{}", port));
+        (client, log, patch_body)
+    }
+
+    #[tokio::test]
+    async fn test_push_progress_when_behind() {
+        let (client, log, body) = client_for(3).await;
+        let outcome = push_progress(&client, &update(5, Some(28), None))
+            .await
+            .unwrap();
+        assert_eq!(outcome, PushOutcome::Updated);
+        assert_eq!(
+            log.lock().unwrap().last().unwrap(),
+            "PATCH /anime/52991/my_list_status"
+        );
+        let body = body.lock().unwrap();
+        assert!(body.contains("num_watched_episodes=5"));
+        assert!(body.contains("status=watching"));
+        assert!(!body.contains("score"));
+    }
+
+    #[tokio::test]
+    async fn test_push_final_episode_completes_with_score() {
+        let (client, _, body) = client_for(27).await;
+        let outcome = push_progress(&client, &update(28, Some(28), Some(9)))
+            .await
+            .unwrap();
+        assert_eq!(outcome, PushOutcome::Updated);
+        let body = body.lock().unwrap();
+        assert!(body.contains("status=completed"));
+        assert!(body.contains("score=9"));
+    }
+
+    #[tokio::test]
+    async fn test_push_skipped_when_remote_ahead() {
+        let (client, log, _) = client_for(10).await;
+        let outcome = push_progress(&client, &update(4, Some(28), None))
+            .await
+            .unwrap();
+        assert_eq!(outcome, PushOutcome::RemoteAhead(10));
+        assert!(log.lock().unwrap().iter().all(|l| !l.starts_with("PATCH")));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_list_follows_paging() {
+        let port = Arc::new(std::sync::OnceLock::<u16>::new());
+        let server_port = port.clone();
+        let (mock_port, log) = mock_server(move |req| {
+            let target = req.head.split_whitespace().nth(1).unwrap_or("");
+            assert!(!target.contains("status=on_hold"), "skipped status was fetched");
+            let body = if target.contains("offset=1") {
+                r#"{"data":[{"node":{"id":2,"title":"Two","num_episodes":12},"list_status":{"num_episodes_watched":3}}],"paging":{}}"#.to_string()
+            } else if target.contains("status=watching") {
+                format!(
+                    r#"{{"data":[{{"node":{{"id":1,"title":"One","num_episodes":24}},"list_status":{{"num_episodes_watched":5}}}}],"paging":{{"next":"http://127.0.0.1:{}/users/@me/animelist?offset=1&status=watching"}}}}"#,
+                    server_port.get().unwrap()
+                )
+            } else {
+                r#"{"data":[]}"#.to_string()
+            };
+            reply(200, &body)
+        })
+        .await;
+        port.set(mock_port).unwrap();
+        let client = MalClient::new("id".to_string())
+            .with_access_token("tok".to_string())
+            .with_api_base(&format!("http://127.0.0.1:{}", mock_port));
+
+        let import = ListImportConfig {
+            on_hold: ImportTarget::Skip,
+            ..ListImportConfig::default()
+        };
+        let entries = fetch_list(&client, &import).await.unwrap();
+
+        let ids: Vec<_> = entries.iter().map(|e| (e.id, e.status)).collect();
+        assert_eq!(ids, [(1, ListStatus::Watching), (2, ListStatus::Watching)]);
+        // Two watching pages, then plan_to_watch and completed
+        assert_eq!(log.lock().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_plan_import_routes_statuses() {
+        use crate::library::models::{Episode, Show};
+        use crate::metadata::AnimeMetadata;
+
+        let episode = |number, watched| Episode {
+            number,
+            filename: format!("{:02}.mkv", number),
+            watched,
+            last_position: 0,
+            relative_path: None,
+            release: None,
+        };
+        let mut library = Library::default();
+        let mut show = Show::new("frieren", "Frieren", "/anime/Frieren".into());
+        show.episodes = (1..=4).map(|n| episode(n, n == 1)).collect();
+        show.metadata = Some(AnimeMetadata {
+            id: 52991,
+            provider: ProviderKind::Mal,
+            ..AnimeMetadata::default()
+        });
+        library.shows.push(show);
+        library.tracked_shows.push(TrackedSeries {
+            title: "Dandadan".to_string(),
+            metadata_id: Some(57334),
+            ..TrackedSeries::default()
+        });
+
+        let entry = |id, title: &str, status, num_watched, num_episodes| UserAnimeEntry {
+            id,
+            title: title.to_string(),
+            num_episodes,
+            num_watched,
+            status,
+        };
+        let entries = vec![
+            entry(57334, "Dandadan", ListStatus::Watching, 6, Some(12)),
+            entry(1, "New Show", ListStatus::Watching, 2, None),
+            entry(2, "Someday", ListStatus::PlanToWatch, 0, Some(13)),
+            entry(
+                52991,
+                "Sousou no Frieren",
+                ListStatus::Completed,
+                0,
+                Some(3),
+            ),
+            entry(3, "Not Downloaded", ListStatus::Completed, 12, Some(12)),
+        ];
+
+        let plan = plan_import(
+            entries,
+            &library,
+            &ListImportConfig::default(),
+            ProviderKind::Mal,
+        );
+        assert_eq!(plan.tracked.len(), 1);
+        assert_eq!(plan.tracked[0].min_episode, 3);
+        assert_eq!(plan.watchlist[0].title, "Someday");
+        assert_eq!(
+            plan.backfill,
+            vec![Backfill {
+                show_id: "frieren".to_string(),
+                title: "Frieren".to_string(),
+                through: 3,
+                newly_watched: 2,
+            }]
+        );
+        assert_eq!((plan.unchanged, plan.unmatched), (1, 1));
+
+        assert_eq!(plan.apply(&mut library), 3);
+        let watched: Vec<_> = library.shows[0]
+            .episodes
+            .iter()
+            .map(|e| e.watched)
+            .collect();
+        assert_eq!(watched, [true, true, true, false]);
+        assert_eq!(library.watchlist.len(), 1);
+        assert_eq!(library.tracked_shows.len(), 2);
+    }
+
+    #[test]
+    fn test_queue_merges_to_highest_count() {
+        let mut queue = MalSyncQueue::default();
+        queue.enqueue(update(6, Some(28), None));
+        queue.enqueue(update(4, Some(28), Some(8)));
+        assert_eq!(queue.pending, vec![update(6, Some(28), Some(8))]);
+
+        queue.remove(&update(6, Some(28), None));
+        assert_eq!(queue.pending.len(), 1);
+        queue.remove(&update(6, Some(28), Some(8)));
+        assert!(queue.pending.is_empty());
+    }
 }
@@ -0,0 +1,424 @@
+//! Background matching of library shows to provider metadata. Candidates
+//! are scored on title, season, episode count and year; confident matches
+//! are taken as-is and the rest go to the review queue.
+
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use crate::config::data_dir;
+use crate::error::Result;
+use crate::library::models::Show;
+use crate::library::parser::{make_show_title, parse_season_number};
+use crate::metadata::offline::title_similarity;
+use crate::metadata::{AnimeMetadata, MetadataProvider, ProviderKind};
+
+/// Accept without asking at or above this score...
+const AUTO_ACCEPT: f64 = 0.85;
+/// ...if the runner-up trails by at least this much
+const AUTO_ACCEPT_MARGIN: f64 = 0.15;
+/// Candidates below this aren't worth showing for review
+const REVIEW_MIN: f64 = 0.35;
+/// Search results are reused for a week
+const CACHE_TTL_SECS: i64 = 7 * 24 * 60 * 60;
+
+static YEAR_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\b((?:19|20)\d{2})\b").unwrap());
+static BRACKETS_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[[^\]]*\]|\([^)]*\)").unwrap());
+
+static ID_URL_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(?:https?://)?(?:www\.)?(myanimelist\.net|anilist\.co|kitsu\.(?:io|app))/anime/(\d+)",
+    )
+    .unwrap()
+});
+static PREFIXED_ID_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^(mal|anilist|kitsu)\s*[:\s]\s*(\d+)$").unwrap());
+
+/// An ID typed in place of a search: a bare number for the active provider,
+/// `mal:1535` / `anilist:21`, or a MAL, AniList or Kitsu page URL. The provider is
+/// `None` for a bare number.
+pub fn parse_id_input(input: &str) -> Option<(Option<ProviderKind>, u64)> {
+    let input = input.trim();
+    if let Ok(id) = input.parse() {
+        return Some((None, id));
+    }
+    let (kind, id) = if let Some(caps) = ID_URL_RE.captures(input) {
+        let kind = match &caps[1] {
+            "myanimelist.net" => ProviderKind::Mal,
+            "anilist.co" => ProviderKind::Anilist,
+            _ => ProviderKind::Kitsu,
+        };
+        (kind, caps[2].parse().ok()?)
+    } else {
+        let caps = PREFIXED_ID_RE.captures(input)?;
+        let kind = match caps[1].to_lowercase().as_str() {
+            "mal" => ProviderKind::Mal,
+            "anilist" => ProviderKind::Anilist,
+            _ => ProviderKind::Kitsu,
+        };
+        (kind, caps[2].parse().ok()?)
+    };
+    Some((Some(kind), id))
+}
+
+/// What the matcher knows about a library show
+#[derive(Debug, Clone)]
+pub struct ShowQuery {
+    pub show_id: String,
+    pub title: String,
+    /// Cleaned title sent to the provider
+    pub query: String,
+    pub season: Option<u32>,
+    pub year: Option<u32>,
+    /// Regular episodes on disk, specials left out
+    pub episode_count: usize,
+}
+
+impl ShowQuery {
+    pub fn from_show(show: &Show) -> Self {
+        let title = make_show_title(&show.title);
+        let year = YEAR_RE.captures(&title).and_then(|c| c[1].parse().ok());
+        let unbracketed = BRACKETS_RE.replace_all(&title, " ");
+        let query = YEAR_RE.replace_all(&unbracketed, " ");
+        Self {
+            show_id: show.id.clone(),
+            title: show.title.clone(),
+            query: query.split_whitespace().collect::<Vec<_>>().join(" "),
+            season: parse_season_number(&title),
+            year,
+            episode_count: show.episode_count() - show.specials.len(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub metadata: AnimeMetadata,
+    pub score: f64,
+}
+
+#[derive(Debug)]
+pub enum MatchOutcome {
+    Accepted(Box<AnimeMetadata>),
+    /// Plausible candidates, best first, for the user to pick from
+    Ambiguous(Vec<Candidate>),
+    NoMatch,
+}
+
+/// How well `candidate` fits the show, 0..=1. `rank` is its position in
+/// the provider's results, which already reflect popularity and aliases.
+pub fn score_candidate(query: &ShowQuery, candidate: &AnimeMetadata, rank: usize) -> f64 {
+    let mut score = title_similarity(&query.query, &candidate.title);
+    // Agreeing on the rest doesn't rescue an unrelated title
+    if score < REVIEW_MIN {
+        return score;
+    }
+    score += match rank {
+        0 => 0.15,
+        1 => 0.05,
+        _ => 0.0,
+    };
+
+    let wanted_season = query.season.unwrap_or(1);
+    let season = parse_season_number(&candidate.title).unwrap_or(1);
+    score += if season == wanted_season { 0.1 } else { -0.3 };
+
+    // Fewer episodes than listed is normal mid-season; more means the wrong entry
+    if let Some(total) = candidate.episodes.filter(|&t| t > 0) {
+        let local = query.episode_count as u32;
+        if local == total {
+            score += 0.15;
+        } else if local > total {
+            score -= 0.25;
+        } else if local > 0 {
+            score += 0.05;
+        }
+    }
+
+    if let (Some(wanted), Some(year)) = (query.year, candidate.year) {
+        score += match wanted.abs_diff(year) {
+            0 => 0.1,
+            // Shows starting around New Year get listed either way
+            1 => 0.0,
+            _ => -0.3,
+        };
+    }
+
+    score.clamp(0.0, 1.0)
+}
+
+/// Every result scored against the show, best first
+pub fn rank_candidates(query: &ShowQuery, results: Vec<AnimeMetadata>) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = results
+        .into_iter()
+        .enumerate()
+        .map(|(rank, metadata)| Candidate {
+            score: score_candidate(query, &metadata, rank),
+            metadata,
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates
+}
+
+pub fn decide(query: &ShowQuery, results: Vec<AnimeMetadata>) -> MatchOutcome {
+    let mut candidates = rank_candidates(query, results);
+    candidates.retain(|c| c.score >= REVIEW_MIN);
+
+    let Some(best) = candidates.first() else {
+        return MatchOutcome::NoMatch;
+    };
+    let runner_up = candidates.get(1).map_or(0.0, |c| c.score);
+    if best.score >= AUTO_ACCEPT && best.score - runner_up >= AUTO_ACCEPT_MARGIN {
+        return MatchOutcome::Accepted(Box::new(candidates.swap_remove(0).metadata));
+    }
+    MatchOutcome::Ambiguous(candidates)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedSearch {
+    /// Unix timestamp (seconds)
+    fetched_at: i64,
+    #[serde(default)]
+    results: Vec<AnimeMetadata>,
+}
+
+/// Provider search results, kept so restarts don't search everything again
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MatchCache {
+    #[serde(default)]
+    searches: HashMap<String, CachedSearch>,
+}
+
+impl MatchCache {
+    pub fn load() -> Result<Self> {
+        let path = data_dir()?.join("match_cache.toml");
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        Ok(toml::from_str(&content)?)
+    }
+
+    pub fn save(&mut self) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        self.searches
+            .retain(|_, s| now - s.fetched_at < CACHE_TTL_SECS);
+        let dir = data_dir()?;
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("match_cache.toml"), toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    fn get(&self, key: &str, now: i64) -> Option<&[AnimeMetadata]> {
+        self.searches
+            .get(key)
+            .filter(|s| now - s.fetched_at < CACHE_TTL_SECS)
+            .map(|s| s.results.as_slice())
+    }
+}
+
+/// Searches the provider on behalf of the matcher, spacing requests out and
+/// answering repeats from the cache
+pub struct AutoMatcher {
+    provider: Arc<dyn MetadataProvider + Send + Sync>,
+    kind: ProviderKind,
+    cache: MatchCache,
+    last_request: Option<Instant>,
+}
+
+impl AutoMatcher {
+    pub fn new(
+        provider: Arc<dyn MetadataProvider + Send + Sync>,
+        kind: ProviderKind,
+        cache: MatchCache,
+    ) -> Self {
+        Self {
+            provider,
+            kind,
+            cache,
+            last_request: None,
+        }
+    }
+
+    async fn search(&mut self, query: &str) -> Result<Vec<AnimeMetadata>> {
+        let key = format!("{}:{}", self.kind.id_prefix(), query.to_lowercase());
+        let now = chrono::Utc::now().timestamp();
+        if let Some(results) = self.cache.get(&key, now) {
+            return Ok(results.to_vec());
+        }
+
+        if let Some(last) = self.last_request {
+            tokio::time::sleep_until(last + self.kind.min_request_interval()).await;
+        }
+        self.last_request = Some(Instant::now());
+        let results = self.provider.search(query).await?;
+        self.cache.searches.insert(
+            key,
+            CachedSearch {
+                fetched_at: now,
+                results: results.clone(),
+            },
+        );
+        Ok(results)
+    }
+
+    pub async fn match_show(&mut self, query: &ShowQuery) -> Result<MatchOutcome> {
+        if query.query.is_empty() {
+            return Ok(MatchOutcome::NoMatch);
+        }
+        let results = self.search(&query.query).await?;
+        Ok(decide(query, results))
+    }
+
+    pub fn into_cache(self) -> MatchCache {
+        self.cache
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::library::models::Episode;
+
+    fn query(title: &str, episodes: usize) -> ShowQuery {
+        let mut show = Show::new("id", title, "/anime".into());
+        show.episodes = (1..=episodes as u32)
+            .map(|number| Episode::new(number, format!("{:02}.mkv", number)))
+            .collect();
+        ShowQuery::from_show(&show)
+    }
+
+    fn anime(id: u64, title: &str, episodes: u32, year: u32) -> AnimeMetadata {
+        AnimeMetadata {
+            id,
+            title: title.to_string(),
+            episodes: Some(episodes),
+            year: Some(year),
+            ..AnimeMetadata::default()
+        }
+    }
+
+    #[test]
+    fn test_query_strips_year_and_brackets() {
+        let q = query("Hunter.x.Hunter.(2011)", 148);
+        assert_eq!(q.query, "Hunter x Hunter");
+        assert_eq!(q.year, Some(2011));
+        assert_eq!(q.season, None);
+    }
+
+    #[test]
+    fn test_year_and_episode_count_pick_the_remake() {
+        let q = query("Hunter x Hunter (2011)", 148);
+        let outcome = decide(
+            &q,
+            vec![
+                anime(136, "Hunter x Hunter", 62, 1999),
+                anime(11061, "Hunter x Hunter (2011)", 148, 2011),
+            ],
+        );
+        let MatchOutcome::Accepted(m) = outcome else {
+            panic!("expected a confident match, got {:?}", outcome);
+        };
+        assert_eq!(m.id, 11061);
+    }
+
+    #[test]
+    fn test_season_number_picks_the_sequel() {
+        let q = query("Sousou no Frieren Season 2", 4);
+        let outcome = decide(
+            &q,
+            vec![
+                anime(52991, "Sousou no Frieren", 28, 2023),
+                anime(59978, "Sousou no Frieren 2nd Season", 10, 2026),
+            ],
+        );
+        assert!(matches!(outcome, MatchOutcome::Accepted(ref m) if m.id == 59978));
+    }
+
+    #[test]
+    fn test_close_candidates_go_to_review() {
+        // Nothing local says which adaptation this is
+        let q = query("Fruits Basket", 25);
+        let outcome = decide(
+            &q,
+            vec![
+                anime(120, "Fruits Basket", 26, 2001),
+                anime(38680, "Fruits Basket (2019)", 25, 2019),
+            ],
+        );
+        let MatchOutcome::Ambiguous(candidates) = outcome else {
+            panic!("expected review, got {:?}", outcome);
+        };
+        assert_eq!(candidates.len(), 2);
+
+        let outcome = decide(
+            &query("Totally Different", 12),
+            vec![anime(1, "Death Note", 37, 2006)],
+        );
+        assert!(matches!(outcome, MatchOutcome::NoMatch));
+    }
+
+    #[test]
+    fn test_parse_id_input() {
+        assert_eq!(parse_id_input(" 52991 "), Some((None, 52991)));
+        assert_eq!(
+            parse_id_input("AniList:154587"),
+            Some((Some(ProviderKind::Anilist), 154587))
+        );
+        assert_eq!(
+            parse_id_input("https://myanimelist.net/anime/52991/Sousou_no_Frieren"),
+            Some((Some(ProviderKind::Mal), 52991))
+        );
+        assert_eq!(
+            parse_id_input("anilist.co/anime/154587/"),
+            Some((Some(ProviderKind::Anilist), 154587))
+        );
+        assert_eq!(
+            parse_id_input("https://kitsu.io/anime/46474"),
+            Some((Some(ProviderKind::Kitsu), 46474))
+        );
+        assert_eq!(
+            parse_id_input("kitsu.app/anime/46474/episodes"),
+            Some((Some(ProviderKind::Kitsu), 46474))
+        );
+        assert_eq!(parse_id_input("Frieren 2"), None);
+    }
+
+    struct CountingProvider(AtomicUsize);
+
+    #[async_trait::async_trait]
+    impl MetadataProvider for CountingProvider {
+        async fn search(&self, _query: &str) -> Result<Vec<AnimeMetadata>> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(vec![anime(1535, "Death Note", 37, 2006)])
+        }
+
+        async fn get_details(&self, _id: u64) -> Result<AnimeMetadata> {
+            unreachable!()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repeat_searches_hit_the_cache() {
+        let provider = Arc::new(CountingProvider(AtomicUsize::new(0)));
+        let mut matcher = AutoMatcher::new(
+            provider.clone(),
+            ProviderKind::Offline,
+            MatchCache::default(),
+        );
+
+        let q = query("Death Note", 37);
+        for _ in 0..2 {
+            let outcome = matcher.match_show(&q).await.unwrap();
+            assert!(matches!(outcome, MatchOutcome::Accepted(ref m) if m.id == 1535));
+        }
+        assert_eq!(provider.0.load(Ordering::SeqCst), 1);
+    }
+}
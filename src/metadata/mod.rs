@@ -1,8 +1,73 @@
+use std::time::Duration;
+
 use crate::error::Result;
 use serde::{Deserialize, Serialize};
 
+pub mod anilist;
+pub mod credentials;
+pub mod episodes;
+pub mod jikan;
+pub mod kitsu;
 pub mod mal;
 pub mod mal_sync;
+pub mod matcher;
+pub mod offline;
+pub mod redirect;
+
+/// The service metadata lookups and list sync go through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    #[default]
+    Mal,
+    Anilist,
+    /// Metadata only, no list sync
+    Kitsu,
+    /// Local anime-offline-database dump. Metadata only, no list sync.
+    Offline,
+}
+
+impl ProviderKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProviderKind::Mal => "MyAnimeList",
+            ProviderKind::Anilist => "AniList",
+            ProviderKind::Kitsu => "Kitsu",
+            ProviderKind::Offline => "offline database",
+        }
+    }
+
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            ProviderKind::Mal => "MAL",
+            ProviderKind::Anilist => "AniList",
+            ProviderKind::Kitsu => "Kitsu",
+            ProviderKind::Offline => "Offline",
+        }
+    }
+
+    /// Gap between searches during bulk matching
+    pub fn min_request_interval(&self) -> Duration {
+        match self {
+            // No published limit; about one a second stays clear of 403s
+            ProviderKind::Mal => Duration::from_secs(1),
+            // 90 a minute normally, 30 while AniList runs degraded
+            ProviderKind::Anilist => Duration::from_secs(2),
+            ProviderKind::Kitsu => Duration::from_millis(500),
+            ProviderKind::Offline => Duration::ZERO,
+        }
+    }
+
+    /// Prefix for IDs of series imported from this provider's list
+    pub fn id_prefix(&self) -> &'static str {
+        match self {
+            ProviderKind::Mal => "mal",
+            ProviderKind::Anilist => "anilist",
+            ProviderKind::Kitsu => "kitsu",
+            ProviderKind::Offline => "offline",
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct AnimeMetadata {
@@ -14,6 +79,104 @@ pub struct AnimeMetadata {
     pub status: String,
     pub episodes: Option<u32>,
     pub genres: Vec<String>,
+    /// Which provider `id` belongs to
+    #[serde(default)]
+    pub provider: ProviderKind,
+    #[serde(default)]
+    pub next_airing: Option<NextAiring>,
+    /// Year it started airing
+    #[serde(default)]
+    pub year: Option<u32>,
+    #[serde(default)]
+    pub broadcast: Option<Broadcast>,
+}
+
+/// Weekly broadcast slot in Japan Standard Time, as MAL lists it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Broadcast {
+    /// English day name, e.g. "saturday"
+    pub day: String,
+    /// "HH:MM"; missing when only the day is known
+    #[serde(default)]
+    pub time: Option<String>,
+}
+
+/// One episode of a series, from a provider or the show's `episodes.toml`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EpisodeMetadata {
+    pub number: u32,
+    /// Season folder it belongs to, for shows split into seasons
+    #[serde(default)]
+    pub season: Option<u32>,
+    #[serde(default)]
+    pub title: Option<String>,
+    /// ISO date, e.g. "2023-09-29"
+    #[serde(default)]
+    pub aired: Option<String>,
+    #[serde(default)]
+    pub filler: bool,
+    #[serde(default)]
+    pub recap: bool,
+    #[serde(default)]
+    pub synopsis: Option<String>,
+}
+
+impl EpisodeMetadata {
+    /// Filler or recap, the episodes "skip filler" passes over
+    pub fn is_skippable(&self) -> bool {
+        self.filler || self.recap
+    }
+}
+
+/// The year out of an ISO date like "2023-09-29" (or just "2023")
+pub fn year_from_date(date: &str) -> Option<u32> {
+    date.get(..4)?.parse().ok()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NextAiring {
+    pub episode: u32,
+    /// Unix timestamp (seconds)
+    pub airing_at: i64,
+}
+
+/// Which of the user's lists an anime is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ListStatus {
+    #[default]
+    Watching,
+    PlanToWatch,
+    OnHold,
+    Completed,
+}
+
+impl ListStatus {
+    pub const ALL: [ListStatus; 4] = [
+        ListStatus::Watching,
+        ListStatus::PlanToWatch,
+        ListStatus::OnHold,
+        ListStatus::Completed,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ListStatus::Watching => "watching",
+            ListStatus::PlanToWatch => "plan to watch",
+            ListStatus::OnHold => "on hold",
+            ListStatus::Completed => "completed",
+        }
+    }
+}
+
+/// An anime on the user's own list
+#[derive(Debug, Clone)]
+pub struct UserAnimeEntry {
+    pub id: u64,
+    pub title: String,
+    pub num_episodes: Option<u32>,
+    pub num_watched: u32,
+    pub status: ListStatus,
 }
 
 #[async_trait::async_trait]
@@ -21,3 +184,19 @@ pub trait MetadataProvider {
     async fn search(&self, query: &str) -> Result<Vec<AnimeMetadata>>;
     async fn get_details(&self, id: u64) -> Result<AnimeMetadata>;
 }
+
+/// Reading and updating the logged-in user's anime list
+#[async_trait::async_trait]
+pub trait ListSync {
+    /// Every entry with this status, across all pages
+    async fn list_entries(&self, status: ListStatus) -> Result<Vec<UserAnimeEntry>>;
+    /// Episodes watched according to the list, `None` if it isn't on it
+    async fn list_progress(&self, anime_id: u64) -> Result<Option<u32>>;
+    async fn update_progress(
+        &self,
+        anime_id: u64,
+        episodes_watched: u32,
+        completed: bool,
+        score: Option<u8>,
+    ) -> Result<()>;
+}
@@ -0,0 +1,355 @@
+//! Offline metadata from the anime-offline-database JSON dump
+//! (https://github.com/manami-project/anime-offline-database). Gives
+//! title search, covers and MAL/AniList/Kitsu/AniDB ID mapping without
+//! any API access.
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+use crate::metadata::{AnimeMetadata, MetadataProvider, ProviderKind};
+
+/// Results below this title similarity are dropped
+const MIN_SIMILARITY: f64 = 0.5;
+
+#[derive(Deserialize)]
+pub struct OfflineDatabase {
+    data: Vec<OfflineEntry>,
+    /// Each entry's title and synonyms, ready to compare. Built on load so
+    /// searches don't redo it for every one of the ~30k entries.
+    #[serde(skip)]
+    titles: Vec<Vec<TitleKey>>,
+}
+
+/// A title normalized and split into bigrams
+struct TitleKey {
+    normalized: String,
+    bigrams: HashSet<(char, char)>,
+}
+
+impl TitleKey {
+    fn new(title: &str) -> Self {
+        let normalized = normalize_title(title);
+        let bigrams = bigrams(&normalized);
+        Self {
+            normalized,
+            bigrams,
+        }
+    }
+
+    /// Dice coefficient against another key, 0..=1
+    fn similarity(&self, other: &TitleKey) -> f64 {
+        if self.normalized == other.normalized {
+            return 1.0;
+        }
+        let (a, b) = (&self.bigrams, &other.bigrams);
+        if a.is_empty() || b.is_empty() {
+            return 0.0;
+        }
+        let shared = a.intersection(b).count();
+        2.0 * shared as f64 / (a.len() + b.len()) as f64
+    }
+
+    /// The best `similarity` could be, going by bigram counts alone
+    fn similarity_bound(&self, other: &TitleKey) -> f64 {
+        let (a, b) = (self.bigrams.len(), other.bigrams.len());
+        if a + b == 0 {
+            return 1.0;
+        }
+        2.0 * a.min(b) as f64 / (a + b) as f64
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineEntry {
+    #[serde(default)]
+    pub sources: Vec<String>,
+    pub title: String,
+    #[serde(default)]
+    pub episodes: Option<u32>,
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub picture: Option<String>,
+    #[serde(default)]
+    pub synonyms: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub score: Option<OfflineScore>,
+    #[serde(default)]
+    pub anime_season: Option<OfflineSeason>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OfflineSeason {
+    #[serde(default)]
+    pub year: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineScore {
+    pub arithmetic_mean: f64,
+}
+
+/// The same anime's ID on each site the dump knows about
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct AnimeIds {
+    pub mal: Option<u64>,
+    pub anilist: Option<u64>,
+    pub kitsu: Option<u64>,
+    pub anidb: Option<u64>,
+}
+
+impl AnimeIds {
+    pub fn get(&self, provider: ProviderKind) -> Option<u64> {
+        match provider {
+            ProviderKind::Mal => self.mal,
+            ProviderKind::Anilist => self.anilist,
+            ProviderKind::Kitsu => self.kitsu,
+            ProviderKind::Offline => None,
+        }
+    }
+}
+
+impl OfflineEntry {
+    pub fn ids(&self) -> AnimeIds {
+        let mut ids = AnimeIds::default();
+        for source in &self.sources {
+            let Some((site, id)) = source.rsplit_once('/') else {
+                continue;
+            };
+            let Ok(id) = id.parse() else {
+                continue;
+            };
+            if site.ends_with("myanimelist.net/anime") {
+                ids.mal = Some(id);
+            } else if site.ends_with("anilist.co/anime") {
+                ids.anilist = Some(id);
+            } else if site.ends_with("kitsu.app/anime") || site.ends_with("kitsu.io/anime") {
+                ids.kitsu = Some(id);
+            } else if site.ends_with("anidb.net/anime") {
+                ids.anidb = Some(id);
+            }
+        }
+        ids
+    }
+
+    fn title_keys(&self) -> Vec<TitleKey> {
+        std::iter::once(&self.title)
+            .chain(&self.synonyms)
+            .map(|t| TitleKey::new(t))
+            .collect()
+    }
+
+    /// The ID metadata is keyed by: MAL's, falling back to AniList's then
+    /// Kitsu's for entries MAL doesn't list
+    fn primary_id(&self) -> Option<(ProviderKind, u64)> {
+        let ids = self.ids();
+        [
+            ProviderKind::Mal,
+            ProviderKind::Anilist,
+            ProviderKind::Kitsu,
+        ]
+        .into_iter()
+        .find_map(|p| ids.get(p).map(|id| (p, id)))
+    }
+
+    /// Metadata keyed by `primary_id`, so progress sync can use it as-is
+    fn to_metadata(&self) -> Option<AnimeMetadata> {
+        let (provider, id) = self.primary_id()?;
+
+        Some(AnimeMetadata {
+            id,
+            title: self.title.clone(),
+            cover_url: self.picture.clone(),
+            synopsis: None,
+            score: self
+                .score
+                .as_ref()
+                .map(|s| (s.arithmetic_mean * 100.0).round() / 100.0),
+            status: normalize_status(self.status.as_deref()),
+            episodes: self.episodes.filter(|&e| e > 0),
+            genres: self.tags.iter().take(5).cloned().collect(),
+            provider,
+            next_airing: None,
+            year: self.anime_season.as_ref().and_then(|s| s.year),
+            broadcast: None,
+        })
+    }
+}
+
+fn normalize_status(status: Option<&str>) -> String {
+    match status {
+        Some("ONGOING") => "currently_airing".to_string(),
+        Some("FINISHED") => "finished_airing".to_string(),
+        Some("UPCOMING") => "not_yet_aired".to_string(),
+        _ => "Unknown".to_string(),
+    }
+}
+
+fn normalize_title(title: &str) -> String {
+    title
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn bigrams(s: &str) -> HashSet<(char, char)> {
+    let chars: Vec<char> = s.chars().collect();
+    chars.windows(2).map(|w| (w[0], w[1])).collect()
+}
+
+/// Dice coefficient over character bigrams of the normalized titles, 0..=1
+pub fn title_similarity(a: &str, b: &str) -> f64 {
+    TitleKey::new(a).similarity(&TitleKey::new(b))
+}
+
+impl OfflineDatabase {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Self::from_json(&content)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        let mut db: Self = serde_json::from_str(json)
+            .map_err(|e| Error::Metadata(format!("Bad offline database: {}", e)))?;
+        db.titles = db.data.iter().map(OfflineEntry::title_keys).collect();
+        Ok(db)
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Entries whose title or synonyms resemble the query, best first
+    pub fn search(&self, query: &str, limit: usize) -> Vec<&OfflineEntry> {
+        let query = TitleKey::new(query);
+        let mut scored: Vec<(f64, &OfflineEntry)> = self
+            .data
+            .iter()
+            .zip(&self.titles)
+            .filter_map(|(entry, titles)| {
+                let best = titles
+                    .iter()
+                    // Titles far longer or shorter than the query can't reach it
+                    .filter(|t| query.similarity_bound(t) >= MIN_SIMILARITY)
+                    .map(|t| query.similarity(t))
+                    .fold(0.0, f64::max);
+                (best >= MIN_SIMILARITY).then_some((best, entry))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored.into_iter().take(limit).map(|(_, e)| e).collect()
+    }
+
+    pub fn find_by_id(&self, provider: ProviderKind, id: u64) -> Option<&OfflineEntry> {
+        self.data.iter().find(|e| e.ids().get(provider) == Some(id))
+    }
+
+    /// Metadata for `provider`'s anime `id`
+    pub fn details(&self, provider: ProviderKind, id: u64) -> Option<AnimeMetadata> {
+        self.find_by_id(provider, id)?.to_metadata()
+    }
+
+    /// Translate an ID from one provider to another
+    pub fn map_id(&self, from: ProviderKind, id: u64, to: ProviderKind) -> Option<u64> {
+        self.find_by_id(from, id)?.ids().get(to)
+    }
+}
+
+#[async_trait::async_trait]
+impl MetadataProvider for OfflineDatabase {
+    async fn search(&self, query: &str) -> Result<Vec<AnimeMetadata>> {
+        Ok(OfflineDatabase::search(self, query, 5)
+            .into_iter()
+            .filter_map(OfflineEntry::to_metadata)
+            .collect())
+    }
+
+    /// `id` as this provider hands it out: the ID of the site each entry's
+    /// metadata is keyed by, never another site's that happens to match
+    async fn get_details(&self, id: u64) -> Result<AnimeMetadata> {
+        self.data
+            .iter()
+            .find(|e| e.primary_id().is_some_and(|(_, primary)| primary == id))
+            .and_then(OfflineEntry::to_metadata)
+            .ok_or_else(|| Error::Metadata(format!("No offline entry for ID {}", id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/anime-offline-database.json");
+
+    #[test]
+    fn test_cross_map_ids() {
+        let db = OfflineDatabase::from_json(FIXTURE).unwrap();
+        assert_eq!(db.len(), 3);
+
+        let entry = db.find_by_id(ProviderKind::Mal, 52991).unwrap();
+        assert_eq!(
+            entry.ids(),
+            AnimeIds {
+                mal: Some(52991),
+                anilist: Some(154587),
+                kitsu: Some(46474),
+                anidb: Some(17617),
+            }
+        );
+        assert_eq!(
+            db.map_id(ProviderKind::Anilist, 1535, ProviderKind::Mal),
+            Some(1535)
+        );
+        assert_eq!(db.map_id(ProviderKind::Mal, 1, ProviderKind::Kitsu), None);
+    }
+
+    #[test]
+    fn test_fuzzy_search_matches_synonyms() {
+        let db = OfflineDatabase::from_json(FIXTURE).unwrap();
+
+        let hits = db.search("frieren beyond journeys end", 5);
+        assert_eq!(hits[0].title, "Sousou no Frieren");
+
+        let hits = db.search("Death note", 5);
+        assert_eq!(hits[0].title, "Death Note");
+        assert_eq!(hits.len(), 1);
+
+        assert!(db.search("completely unrelated", 5).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_provider_prefers_mal_ids() {
+        let db = OfflineDatabase::from_json(FIXTURE).unwrap();
+
+        let results = MetadataProvider::search(&db, "Sousou no Frieren")
+            .await
+            .unwrap();
+        let anime = &results[0];
+        assert_eq!((anime.provider, anime.id), (ProviderKind::Mal, 52991));
+        assert_eq!(anime.status, "finished_airing");
+        assert_eq!(anime.episodes, Some(28));
+        assert_eq!(anime.score, Some(9.12));
+
+        // No MAL source, so it falls back to the AniList ID
+        let details = db.get_details(999001).await.unwrap();
+        assert_eq!(details.provider, ProviderKind::Anilist);
+        assert_eq!(details.title, "Kitsu Only Short");
+
+        // Other sites' IDs only by saying which site
+        assert!(db.get_details(46474).await.is_err());
+        let details = db.details(ProviderKind::Kitsu, 46474).unwrap();
+        assert_eq!((details.provider, details.id), (ProviderKind::Mal, 52991));
+        assert!(db.details(ProviderKind::Kitsu, 52991).is_none());
+    }
+}
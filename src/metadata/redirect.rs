@@ -0,0 +1,251 @@
+//! Loopback listener that catches the OAuth redirect, so logging in doesn't
+//! need the code copied out of the browser
+
+use std::time::Duration;
+
+use reqwest::Url;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinSet;
+use tracing::warn;
+
+use crate::error::{Error, Result};
+
+const CALLBACK_PATH: &str = "/callback";
+
+/// Requests larger than this aren't a browser redirect
+const MAX_REQUEST_BYTES: usize = 8192;
+
+/// Browsers open speculative connections they may never send on; give up
+/// on a connection that's said nothing by then
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Random value tying the redirect back to the login we started
+pub fn generate_state() -> String {
+    use rand::Rng;
+    rand::rng()
+        .sample_iter(&rand::distr::Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+/// What a request to the redirect URL carried
+enum Callback {
+    Code(String),
+    Denied(String),
+    /// State missing or not ours, possibly a forged request, so neither a
+    /// code nor a denial is trusted
+    WrongState,
+    /// No OAuth parameters, like a favicon request
+    Stray,
+}
+
+fn parse_callback(url: &Url, expected_state: &str) -> Callback {
+    let (mut code, mut state, mut error) = (None, None, None);
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "code" => code = Some(value.into_owned()),
+            "state" => state = Some(value.into_owned()),
+            "error" => error = Some(value.into_owned()),
+            _ => {}
+        }
+    }
+
+    match (code, error) {
+        (None, None) => Callback::Stray,
+        _ if state.as_deref() != Some(expected_state) => Callback::WrongState,
+        (_, Some(error)) => Callback::Denied(error),
+        (Some(code), None) => Callback::Code(code),
+    }
+}
+
+/// Pull the authorization code out of a redirect URL pasted by the user.
+/// `Ok(None)` if it has no OAuth parameters, e.g. it's a bare code.
+pub fn parse_redirect(input: &str, expected_state: &str) -> Result<Option<String>> {
+    let Ok(url) = Url::parse(input) else {
+        return Ok(None);
+    };
+    match parse_callback(&url, expected_state) {
+        Callback::Code(code) => Ok(Some(code)),
+        Callback::Denied(error) => Err(Error::Metadata(format!("Authorization denied: {}", error))),
+        Callback::WrongState => Err(Error::Metadata(
+            "Redirect state doesn't match this login, try again".to_string(),
+        )),
+        Callback::Stray => Ok(None),
+    }
+}
+
+pub struct RedirectListener {
+    listener: TcpListener,
+    port: u16,
+}
+
+impl RedirectListener {
+    /// Listen on 127.0.0.1 only. Port 0 picks a free one.
+    /// Must be called from within the tokio runtime.
+    pub fn bind(port: u16) -> Result<Self> {
+        let listener = std::net::TcpListener::bind(("127.0.0.1", port))?;
+        listener.set_nonblocking(true)?;
+        let port = listener.local_addr()?.port();
+        Ok(Self {
+            listener: TcpListener::from_std(listener)?,
+            port,
+        })
+    }
+
+    /// What to register as the app's redirect URL. The loopback IP
+    /// rather than `localhost`, which may resolve to ::1 where nothing
+    /// listens.
+    pub fn redirect_uri(&self) -> String {
+        format!("http://127.0.0.1:{}{}", self.port, CALLBACK_PATH)
+    }
+
+    /// Wait for the browser to bring back a code for `state`. Stray
+    /// requests (favicons, mismatched state) are answered and ignored so
+    /// they can't cut the login short. Each connection is handled on its
+    /// own, so one that never sends a request doesn't hold up the rest.
+    pub async fn wait_for_code(self, state: &str) -> Result<String> {
+        let mut connections = JoinSet::new();
+        loop {
+            tokio::select! {
+                accepted = self.listener.accept() => {
+                    let (stream, _) = accepted?;
+                    connections.spawn(answer_request(stream, state.to_string()));
+                }
+                Some(Ok(callback)) = connections.join_next() => match callback {
+                    Callback::Code(code) => return Ok(code),
+                    Callback::Denied(error) => {
+                        return Err(Error::Metadata(format!("Authorization denied: {}", error)));
+                    }
+                    Callback::WrongState => warn!("Ignoring OAuth redirect with the wrong state"),
+                    Callback::Stray => {}
+                },
+            }
+        }
+    }
+}
+
+/// Read one request, reply to the browser and say what it carried
+async fn answer_request(mut stream: TcpStream, state: String) -> Callback {
+    let Ok(Some(target)) =
+        tokio::time::timeout(READ_TIMEOUT, read_request_target(&mut stream)).await
+    else {
+        return Callback::Stray;
+    };
+
+    let callback = match Url::parse(&format!("http://127.0.0.1{}", target)) {
+        Ok(url) if url.path() == CALLBACK_PATH => parse_callback(&url, &state),
+        _ => Callback::Stray,
+    };
+
+    let (status, message) = match &callback {
+        Callback::Code(_) => (
+            "200 OK",
+            "Logged in. You can close this tab and return to miru.",
+        ),
+        Callback::Denied(_) => ("200 OK", "Login cancelled. You can close this tab."),
+        Callback::WrongState => (
+            "400 Bad Request",
+            "This login link doesn't match; start again from miru.",
+        ),
+        Callback::Stray => ("404 Not Found", "Not found."),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        message.len(),
+        message
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+    callback
+}
+
+/// The request target of a GET, e.g. `/callback?code=...`
+async fn read_request_target(stream: &mut TcpStream) -> Option<String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 1024];
+    while !buf.windows(4).any(|w| w == b"\r\n\r\n") {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 || buf.len() + n > MAX_REQUEST_BYTES {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    let head = String::from_utf8_lossy(&buf);
+    let mut request_line = head.lines().next()?.split_whitespace();
+    if request_line.next()? != "GET" {
+        return None;
+    }
+    request_line.next().map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_redirect_checks_state() {
+        let base = "http://localhost:8765/callback";
+        assert_eq!(
+            parse_redirect(&format!("{}?code=abc%2F1&state=s1", base), "s1").unwrap(),
+            Some("abc/1".to_string())
+        );
+        assert!(parse_redirect(&format!("{}?code=abc&state=other", base), "s1").is_err());
+        assert!(parse_redirect(&format!("{}?code=abc", base), "s1").is_err());
+        assert!(parse_redirect(&format!("{}?error=access_denied", base), "s1").is_err());
+        // A bare code pasted by hand
+        assert_eq!(parse_redirect("def50200abc", "s1").unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_listener_receives_redirect() {
+        let listener = RedirectListener::bind(0).unwrap();
+        let uri = listener.redirect_uri();
+        assert!(uri.starts_with("http://127.0.0.1:"));
+        let task = tokio::spawn(async move { listener.wait_for_code("s1").await });
+
+        // A preconnect that never sends anything mustn't block the redirect
+        let _idle = TcpStream::connect(
+            uri.trim_start_matches("http://")
+                .trim_end_matches("/callback"),
+        )
+        .await
+        .unwrap();
+
+        let client = reqwest::Client::new();
+        let forged = client
+            .get(format!("{}?code=evil&state=wrong", uri))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(forged.status(), 400);
+        let stray = client
+            .get(uri.replace("/callback", "/favicon.ico"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(stray.status(), 404);
+
+        let ok = client
+            .get(format!("{}?code=good&state=s1", uri))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(ok.status(), 200);
+        assert_eq!(task.await.unwrap().unwrap(), "good");
+    }
+
+    #[tokio::test]
+    async fn test_listener_stops_when_denied() {
+        let listener = RedirectListener::bind(0).unwrap();
+        let uri = listener.redirect_uri();
+        let task = tokio::spawn(async move { listener.wait_for_code("s1").await });
+
+        reqwest::get(format!("{}?error=access_denied&state=s1", uri))
+            .await
+            .unwrap();
+        assert!(task.await.unwrap().is_err());
+    }
+}
@@ -27,6 +27,15 @@ impl Notifier {
         self.send(series_title, &body);
     }
 
+    pub fn missed_episodes(&self, series_title: &str, count: usize) {
+        if !self.enabled {
+            return;
+        }
+
+        let body = format!("Downloading {} missed episodes from a batch", count);
+        self.send(series_title, &body);
+    }
+
     pub fn download_complete(&self, name: &str) {
         if !self.enabled {
             return;
@@ -1,30 +1,55 @@
-use regex::Regex;
 use scraper::{Html, Selector};
-use std::sync::OnceLock;
+use std::collections::HashMap;
+use std::sync::Mutex;
 use tracing::debug;
 
 use crate::error::{Error, Result};
+use crate::indexer::{Indexer, SearchPage};
+use crate::library::release::ReleaseInfo;
+use crate::torrent::preview::magnet_hash;
 
+pub mod query;
+pub mod rss;
+pub mod scoring;
 mod smart_search;
-pub use smart_search::{rank_results, smart_search};
+pub use query::AdvancedQuery;
+pub use scoring::{ScoreBreakdown, ScoringProfile};
+pub use smart_search::{SearchQuery, score_result, smart_search};
 
 const NYAA_BASE_URL: &str = "https://nyaa.si";
 
-static BATCH_PATTERNS: OnceLock<Vec<Regex>> = OnceLock::new();
+/// Results on each page of a Nyaa search
+const PAGE_SIZE: usize = 75;
+
+/// The trackers Nyaa puts in its own magnet links
+const TRACKERS: [&str; 5] = [
+    "http://nyaa.tracker.wf:7777/announce",
+    "udp://open.stealth.si:80/announce",
+    "udp://tracker.opentrackr.org:1337/announce",
+    "udp://exodus.desync.com:6969/announce",
+    "udp://tracker.torrent.eu.org:451/announce",
+];
+
+/// A magnet link for sources that only give the info hash, built like the
+/// search page's
+pub fn magnet_from_hash(info_hash: &str, title: &str) -> String {
+    let mut magnet = format!(
+        "magnet:?xt=urn:btih:{}&dn={}",
+        info_hash,
+        urlencoding::encode(title)
+    );
+    for tracker in TRACKERS {
+        magnet.push_str("&tr=");
+        magnet.push_str(&urlencoding::encode(tracker));
+    }
+    magnet
+}
 
-fn get_batch_patterns() -> &'static Vec<Regex> {
-    BATCH_PATTERNS.get_or_init(|| {
-        vec![
-            Regex::new(r"(?i)\[batch\]").unwrap(),
-            Regex::new(r"(?i)\bcomplete\b").unwrap(),
-            Regex::new(r"(?i)\bseason\s+\d+\b").unwrap(),
-            Regex::new(r"\bS\d{2}\b").unwrap(),
-            Regex::new(r"(?i)\d+-\d+\s*(?:END|FINAL)").unwrap(),
-        ]
-    })
+pub fn is_batch_release(title: &str, size: &str) -> bool {
+    ReleaseInfo::parse(title).is_batch || parse_size_mb(size) > 5120.0
 }
 
-fn parse_size_mb(size_str: &str) -> f64 {
+pub fn parse_size_mb(size_str: &str) -> f64 {
     let parts: Vec<&str> = size_str.split_whitespace().collect();
     if parts.len() != 2 {
         return 0.0;
@@ -40,7 +65,7 @@ fn parse_size_mb(size_str: &str) -> f64 {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct NyaaResult {
     pub title: String,
     pub category: String,
@@ -51,8 +76,24 @@ pub struct NyaaResult {
     pub torrent_url: String,
     pub magnet_link: String,
     pub date: String,
-    pub is_trusted: bool,
+    /// `None` from indexers that don't know who's trusted
+    pub is_trusted: Option<bool>,
+    pub is_remake: bool,
     pub is_batch: bool,
+    /// Lowercase hex
+    pub info_hash: String,
+}
+
+impl NyaaResult {
+    /// What to hand the torrent client: the magnet, or the `.torrent` link
+    /// from indexers that give no magnet
+    pub fn download_link(&self) -> &str {
+        if self.magnet_link.is_empty() {
+            &self.torrent_url
+        } else {
+            &self.magnet_link
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -180,8 +221,18 @@ impl Default for NyaaSort {
     }
 }
 
+/// The last response for a feed URL, replayed when Nyaa answers 304
+struct CachedFeed {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    results: Vec<NyaaResult>,
+}
+
 pub struct NyaaClient {
     client: reqwest::Client,
+    name: String,
+    base_url: String,
+    feed_cache: Mutex<HashMap<String, CachedFeed>>,
     pub category: NyaaCategory,
     pub filter: NyaaFilter,
     pub sort: NyaaSort,
@@ -194,6 +245,9 @@ impl NyaaClient {
                 .user_agent("miru/0.1")
                 .build()
                 .expect("Failed to create HTTP client"),
+            name: "Nyaa".to_string(),
+            base_url: NYAA_BASE_URL.to_string(),
+            feed_cache: Mutex::new(HashMap::new()),
             category: NyaaCategory::AnimeEnglish,
             filter: NyaaFilter::NoFilter,
             sort: NyaaSort::default(),
@@ -223,68 +277,106 @@ impl NyaaClient {
         self
     }
 
-    pub async fn search(
+    /// Point requests at a mirror instead of nyaa.si
+    pub fn with_base_url(mut self, base: &str) -> Self {
+        self.base_url = base.trim_end_matches('/').to_string();
+        self
+    }
+
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Newest releases matching `query`, from the RSS feed. A single request
+    /// (no alternative queries), made conditional on the last response for
+    /// the same query so an unchanged feed costs next to nothing.
+    pub async fn search_feed(
         &self,
         query: &str,
         category: NyaaCategory,
         filter: NyaaFilter,
-        sort: NyaaSort,
     ) -> Result<Vec<NyaaResult>> {
-        let search_query = smart_search(query);
-        let mut all_results = Vec::new();
-        let mut seen_magnets = std::collections::HashSet::new();
-
-        let queries =
-            std::iter::once(&search_query.primary).chain(search_query.alternatives.iter());
-
-        for query_str in queries {
-            debug!(query = %query_str, "Trying search query");
-
-            match self
-                .search_with_options(query_str, category, filter, sort)
-                .await
-            {
-                Ok(results) => {
-                    let mut count = 0;
-                    for result in results {
-                        if seen_magnets.insert(result.magnet_link.clone()) {
-                            all_results.push(result);
-                            count += 1;
-                        }
-                    }
+        let url = format!(
+            "{}/?page=rss&f={}&c={}&q={}",
+            self.base_url,
+            filter.as_query_param(),
+            category.as_query_param(),
+            urlencoding::encode(query)
+        );
 
-                    if (count > 0 && all_results.len() >= 15) || all_results.len() >= 30 {
-                        break;
-                    }
-                }
-                Err(e) => {
-                    debug!(error = %e, query = %query_str, "Search query failed");
-                    continue;
-                }
+        let mut request = self.client.get(&url);
+        if let Some(cached) = self.feed_cache.lock().unwrap().get(&url) {
+            if let Some(etag) = &cached.etag {
+                request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+            }
+            if let Some(modified) = &cached.last_modified {
+                request = request.header(reqwest::header::IF_MODIFIED_SINCE, modified);
             }
         }
 
-        rank_results(&mut all_results, &search_query.parsed, |r| &r.title);
+        debug!(url = %url, "Fetching nyaa.si feed");
+        let response = request.send().await?;
+
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED
+            && let Some(cached) = self.feed_cache.lock().unwrap().get(&url)
+        {
+            debug!(url = %url, "Feed unchanged");
+            return Ok(cached.results.clone());
+        }
+        if !response.status().is_success() {
+            return Err(Error::NyaaSearch(format!(
+                "HTTP error: {}",
+                response.status()
+            )));
+        }
 
-        Ok(all_results)
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        let etag = header(reqwest::header::ETAG);
+        let last_modified = header(reqwest::header::LAST_MODIFIED);
+        let results = rss::parse_feed(&response.text().await?);
+        debug!(count = results.len(), "Parsed nyaa feed");
+
+        if etag.is_some() || last_modified.is_some() {
+            self.feed_cache.lock().unwrap().insert(
+                url,
+                CachedFeed {
+                    etag,
+                    last_modified,
+                    results: results.clone(),
+                },
+            );
+        }
+        Ok(results)
     }
 
+    /// Page `page` of a search, counting from 1
     pub async fn search_with_options(
         &self,
         query: &str,
+        page: u32,
         category: NyaaCategory,
         filter: NyaaFilter,
         sort: NyaaSort,
-    ) -> Result<Vec<NyaaResult>> {
+    ) -> Result<SearchPage> {
         let encoded_query = urlencoding::encode(query);
-        let url = format!(
+        let mut url = format!(
             "{}/?f={}&c={}&q={}&s={}&o=desc",
-            NYAA_BASE_URL,
+            self.base_url,
             filter.as_query_param(),
             category.as_query_param(),
             encoded_query,
             sort.as_query_param()
         );
+        if page > 1 {
+            url.push_str(&format!("&p={}", page));
+        }
 
         debug!(url = %url, "Searching nyaa.si");
 
@@ -298,7 +390,18 @@ impl NyaaClient {
         }
 
         let html = response.text().await?;
-        self.parse_results(&html)
+        let results = self.parse_results(&html)?;
+        let total = parse_total(&html);
+        let seen = (page.max(1) as usize - 1) * PAGE_SIZE + results.len();
+        let more = match total {
+            Some(total) => seen < total,
+            None => results.len() >= PAGE_SIZE,
+        };
+        Ok(SearchPage {
+            results,
+            total,
+            more,
+        })
     }
 
     fn parse_results(&self, html: &str) -> Result<Vec<NyaaResult>> {
@@ -314,6 +417,7 @@ impl NyaaClient {
         for row in document.select(&row_selector) {
             let row_class = row.value().attr("class").unwrap_or("default");
             let is_trusted = row_class.contains("success");
+            let is_remake = row_class.contains("danger");
 
             let cells: Vec<_> = row.select(&cell_selector).collect();
 
@@ -346,7 +450,7 @@ impl NyaaClient {
             for link in links_cell.select(&link_selector) {
                 if let Some(href) = link.attr("href") {
                     if href.ends_with(".torrent") {
-                        torrent_url = format!("{}{}", NYAA_BASE_URL, href);
+                        torrent_url = format!("{}{}", self.base_url, href);
                     } else if href.starts_with("magnet:") {
                         magnet_link = href.to_string();
                     }
@@ -374,8 +478,8 @@ impl NyaaClient {
                 .parse()
                 .unwrap_or(0);
 
-            let is_batch = get_batch_patterns().iter().any(|re| re.is_match(&title))
-                || parse_size_mb(&size) > 5120.0;
+            let is_batch = is_batch_release(&title, &size);
+            let info_hash = magnet_hash(&magnet_link).unwrap_or_default();
 
             results.push(NyaaResult {
                 title,
@@ -387,8 +491,10 @@ impl NyaaClient {
                 torrent_url,
                 magnet_link,
                 date,
-                is_trusted,
+                is_trusted: Some(is_trusted),
+                is_remake,
                 is_batch,
+                info_hash,
             });
         }
 
@@ -397,8 +503,183 @@ impl NyaaClient {
     }
 }
 
+/// The "out of N results" under a search page's pagination. Nyaa stops
+/// counting at 1000.
+fn parse_total(html: &str) -> Option<usize> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse(".pagination-page-info").expect("Invalid info selector");
+    let info: String = document.select(&selector).next()?.text().collect();
+    let (_, rest) = info.split_once("out of ")?;
+    rest.split_whitespace().next()?.parse().ok()
+}
+
+#[async_trait::async_trait]
+impl Indexer for NyaaClient {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn categories(&self) -> &'static [NyaaCategory] {
+        &[
+            NyaaCategory::AllAnime,
+            NyaaCategory::AnimeEnglish,
+            NyaaCategory::AnimeRaw,
+            NyaaCategory::AnimeNonEnglish,
+        ]
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        category: NyaaCategory,
+        filter: NyaaFilter,
+        sort: NyaaSort,
+    ) -> Result<Vec<NyaaResult>> {
+        self.search_with_options(query, 1, category, filter, sort)
+            .await
+            .map(|page| page.results)
+    }
+
+    async fn search_page(
+        &self,
+        query: &str,
+        page: u32,
+        category: NyaaCategory,
+        filter: NyaaFilter,
+        sort: NyaaSort,
+    ) -> Result<SearchPage> {
+        self.search_with_options(query, page, category, filter, sort)
+            .await
+    }
+
+    async fn rss(
+        &self,
+        query: &str,
+        category: NyaaCategory,
+        filter: NyaaFilter,
+    ) -> Result<Vec<NyaaResult>> {
+        self.search_feed(query, category, filter).await
+    }
+}
+
 impl Default for NyaaClient {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockResponse, mock_server, reply};
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/nyaa-rss.xml");
+
+    #[tokio::test]
+    async fn test_rss_conditional_get() {
+        let (port, log) = mock_server(|req| {
+            assert!(
+                req.head
+                    .starts_with("GET /?page=rss&f=0&c=1_2&q=sousou%20no%20frieren ")
+            );
+            if req.header("if-none-match") == Some("\"v1\"") {
+                reply(304, "")
+            } else {
+                MockResponse {
+                    headers: vec![("ETag".to_string(), "\"v1\"".to_string())],
+                    ..reply(200, FIXTURE)
+                }
+            }
+        })
+        .await;
+        let client = NyaaClient::new().with_base_url(&format!("http://127.0.0.1:{}", port));
+
+        let fetch = || {
+            client.search_feed(
+                "sousou no frieren",
+                NyaaCategory::AnimeEnglish,
+                NyaaFilter::NoFilter,
+            )
+        };
+        let first = fetch().await.unwrap();
+        assert_eq!(first.len(), 3);
+        // The second answer is a bodiless 304; results come from the cache
+        let second = fetch().await.unwrap();
+        assert_eq!(log.lock().unwrap().len(), 2);
+        assert_eq!(
+            second.iter().map(|r| &r.info_hash).collect::<Vec<_>>(),
+            first.iter().map(|r| &r.info_hash).collect::<Vec<_>>()
+        );
+    }
+
+    /// A search page with one row per hash, and its pagination footer
+    fn search_html(hashes: &[&str], info: &str) -> String {
+        let rows: String = hashes
+            .iter()
+            .map(|hash| {
+                format!(
+                    r#"<tr class="success">
+                        <td><a href="/?c=1_2" title="Anime - English-translated"></a></td>
+                        <td><a href="/view/1">[SubsPlease] Sousou no Frieren - 05 (1080p) [{hash}]</a></td>
+                        <td><a href="/download/1.torrent"></a><a href="magnet:?xt=urn:btih:{hash}"></a></td>
+                        <td>1.4 GiB</td>
+                        <td>2023-10-06 15:32</td>
+                        <td>1490</td><td>38</td><td>21004</td>
+                    </tr>"#
+                )
+            })
+            .collect();
+        format!(
+            r#"<html><body><table class="torrent-list"><tbody>{rows}</tbody></table>
+            <div class="pagination-page-info">{info}<br />Please refine your search results if you can't find what you were looking for.</div>
+            </body></html>"#
+        )
+    }
+
+    #[tokio::test]
+    async fn test_search_pages() {
+        let (port, log) = mock_server(|req| {
+            if req.head.contains("&p=2 ") {
+                reply(
+                    200,
+                    &search_html(&["cccc"], "Displaying results 76-76 out of 76 results."),
+                )
+            } else {
+                assert!(!req.head.contains("&p="));
+                let hashes = vec!["aaaa"; PAGE_SIZE];
+                reply(
+                    200,
+                    &search_html(&hashes, "Displaying results 1-75 out of 76 results."),
+                )
+            }
+        })
+        .await;
+        let client = NyaaClient::new().with_base_url(&format!("http://127.0.0.1:{}", port));
+
+        let search = |page| {
+            client.search_with_options(
+                "frieren",
+                page,
+                NyaaCategory::AnimeEnglish,
+                NyaaFilter::NoFilter,
+                NyaaSort::Seeders,
+            )
+        };
+        let first = search(1).await.unwrap();
+        assert_eq!(first.results.len(), PAGE_SIZE);
+        assert_eq!(first.total, Some(76));
+        assert!(first.more);
+        assert_eq!(first.results[0].is_trusted, Some(true));
+        assert_eq!(first.results[0].info_hash, "aaaa");
+        assert_eq!(
+            first.results[0].torrent_url,
+            format!("http://127.0.0.1:{}/download/1.torrent", port)
+        );
+
+        let second = search(2).await.unwrap();
+        assert_eq!(second.results.len(), 1);
+        assert_eq!(second.total, Some(76));
+        assert!(!second.more);
+        assert_eq!(log.lock().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,597 @@
+//! The search box's filter syntax. `key:value` terms narrow the results by
+//! release traits; everything else is free text for the indexers, Nyaa's
+//! own operators (`"exact phrase"`, `a|b`, `-word`) included:
+//!
+//! `frieren group:SubsPlease res:1080p size:<2GB seeders:>10 -batch trusted:yes after:2024-01-01`
+//!
+//! Filters are checked against every result, since only Nyaa understands
+//! some of them. Where an indexer can do the same (the trusted filter, the
+//! group and resolution as words) they also go into the request.
+
+use super::smart_search::parse_query;
+use super::{NyaaFilter, NyaaResult, SearchQuery, parse_size_mb, smart_search};
+use crate::error::{Error, Result};
+use crate::library::release::ReleaseInfo;
+
+/// Filter keys, in the order completion offers them
+pub const KEYS: [&str; 8] = [
+    "group", "res", "size", "seeders", "batch", "trusted", "after", "before",
+];
+
+/// Values completion offers for a key
+fn key_values(key: &str) -> &'static [&'static str] {
+    match key {
+        "batch" | "trusted" => &["yes", "no"],
+        "res" => &["2160p", "1080p", "720p", "480p"],
+        _ => &[],
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    AtMost,
+    Equal,
+    AtLeast,
+    Greater,
+}
+
+/// `>10`, `<=2GB` and the like
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bound<T> {
+    pub op: Comparison,
+    pub value: T,
+}
+
+impl<T: PartialOrd> Bound<T> {
+    pub fn admits(&self, value: T) -> bool {
+        match self.op {
+            Comparison::Less => value < self.value,
+            Comparison::AtMost => value <= self.value,
+            Comparison::Equal => value == self.value,
+            Comparison::AtLeast => value >= self.value,
+            Comparison::Greater => value > self.value,
+        }
+    }
+}
+
+/// What the `key:value` terms ask of a result
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryFilters {
+    /// Release group, case-insensitive
+    pub group: Option<String>,
+    /// `1080p`, `2160p`, ...
+    pub resolution: Option<String>,
+    /// MiB
+    pub size_mb: Option<Bound<f64>>,
+    pub seeders: Option<Bound<u32>>,
+    pub batch: Option<bool>,
+    /// Results from indexers that don't know who's trusted pass either way
+    pub trusted: Option<bool>,
+    /// `YYYY-MM-DD`, both inclusive
+    pub after: Option<String>,
+    pub before: Option<String>,
+    /// Lowercase words after a `-`, which titles mustn't contain
+    pub excluded: Vec<String>,
+    /// Lowercase quoted phrases, which titles must contain
+    pub phrases: Vec<String>,
+}
+
+impl QueryFilters {
+    pub fn matches(&self, result: &NyaaResult) -> bool {
+        let title = result.title.to_lowercase();
+        if self.excluded.iter().any(|word| title.contains(word))
+            || !self.phrases.iter().all(|phrase| title.contains(phrase))
+        {
+            return false;
+        }
+        if self.batch.is_some_and(|batch| batch != result.is_batch)
+            || self
+                .trusted
+                .is_some_and(|trusted| result.is_trusted.is_some_and(|t| t != trusted))
+        {
+            return false;
+        }
+        if let Some(seeders) = &self.seeders
+            && !seeders.admits(result.seeders)
+        {
+            return false;
+        }
+        if let Some(size) = &self.size_mb
+            && !size.admits(parse_size_mb(&result.size))
+        {
+            return false;
+        }
+
+        // `YYYY-MM-DD HH:MM` compares as text
+        let day = result.date.get(..10).unwrap_or("");
+        if self
+            .after
+            .as_ref()
+            .is_some_and(|after| day < after.as_str())
+            || self
+                .before
+                .as_ref()
+                .is_some_and(|before| day > before.as_str())
+        {
+            return false;
+        }
+
+        if self.group.is_none() && self.resolution.is_none() {
+            return true;
+        }
+        let release = ReleaseInfo::parse(&result.title);
+        let group_ok = self.group.as_ref().is_none_or(|group| {
+            release
+                .group
+                .as_ref()
+                .is_some_and(|g| g.eq_ignore_ascii_case(group))
+        });
+        let resolution_ok = self
+            .resolution
+            .as_ref()
+            .is_none_or(|res| release.resolution.as_ref() == Some(res));
+        group_ok && resolution_ok
+    }
+}
+
+/// A search box query, split into free text and filters
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AdvancedQuery {
+    /// The free text as typed, operators and all
+    pub text: String,
+    /// The free text without operators, for working out show and episode
+    pub words: String,
+    /// Whether `text` uses Nyaa's operators, and so goes out as typed
+    /// rather than respelled by `smart_search`
+    pub native: bool,
+    pub filters: QueryFilters,
+}
+
+impl AdvancedQuery {
+    /// Unknown keys (`Re:Zero`) are left in the text; a known key with a
+    /// value it can't take is an error
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut query = AdvancedQuery::default();
+        let mut text = Vec::new();
+        let mut words = Vec::new();
+
+        for token in tokenize(input) {
+            if token.eq_ignore_ascii_case("-batch") {
+                query.filters.batch = Some(false);
+                continue;
+            }
+            if let Some((key, value)) = token.split_once(':')
+                && KEYS.contains(&key.to_lowercase().as_str())
+            {
+                query.set(&key.to_lowercase(), &unquote(value))?;
+                continue;
+            }
+
+            if token.len() > 2 && token.starts_with('"') && token.ends_with('"') {
+                let phrase = unquote(&token);
+                query.filters.phrases.push(phrase.to_lowercase());
+                words.push(phrase);
+                query.native = true;
+            } else if let Some(word) = token.strip_prefix('-').filter(|w| !w.is_empty()) {
+                query.filters.excluded.push(unquote(word).to_lowercase());
+                query.native = true;
+            } else if token.contains('|') {
+                // The first spelling stands in for the rest
+                let first = token.split('|').find(|w| !w.is_empty()).unwrap_or("");
+                words.push(unquote(first));
+                query.native = true;
+            } else {
+                words.push(token.clone());
+            }
+            text.push(token);
+        }
+
+        query.text = text.join(" ");
+        query.words = words.join(" ");
+        Ok(query)
+    }
+
+    fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let bad = |why: &str| Error::NyaaSearch(format!("{}:{} - {}", key, value, why));
+        let filters = &mut self.filters;
+        match key {
+            "group" if !value.is_empty() => filters.group = Some(value.to_string()),
+            "res" => {
+                filters.resolution =
+                    Some(parse_resolution(value).ok_or_else(|| bad("expected e.g. 1080p or 4k"))?)
+            }
+            "size" => {
+                let bound =
+                    parse_bound(value, parse_size).ok_or_else(|| bad("expected e.g. <2GB"))?;
+                if bound.op == Comparison::Equal {
+                    return Err(bad("sizes need <, <=, > or >="));
+                }
+                filters.size_mb = Some(bound);
+            }
+            "seeders" => {
+                filters.seeders = Some(
+                    parse_bound(value, |v| v.parse().ok())
+                        .ok_or_else(|| bad("expected e.g. >10"))?,
+                )
+            }
+            "batch" => filters.batch = Some(parse_yes_no(value).ok_or_else(|| bad("yes or no"))?),
+            "trusted" => {
+                filters.trusted = Some(parse_yes_no(value).ok_or_else(|| bad("yes or no"))?)
+            }
+            "after" | "before" => {
+                chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                    .map_err(|_| bad("expected YYYY-MM-DD"))?;
+                let date = Some(value.to_string());
+                if key == "after" {
+                    filters.after = date;
+                } else {
+                    filters.before = date;
+                }
+            }
+            _ => return Err(bad("missing value")),
+        }
+        Ok(())
+    }
+
+    /// What to ask the indexers for. Group and resolution ride along as
+    /// plain words, which every indexer can match in titles.
+    pub fn search_query(&self) -> SearchQuery {
+        let mut query = if self.native {
+            SearchQuery {
+                primary: self.text.clone(),
+                alternatives: Vec::new(),
+                parsed: parse_query(&self.words),
+            }
+        } else {
+            smart_search(&self.text)
+        };
+
+        let extra: Vec<&str> = [&self.filters.group, &self.filters.resolution]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        if !extra.is_empty() {
+            let extra = extra.join(" ");
+            for spelling in std::iter::once(&mut query.primary).chain(&mut query.alternatives) {
+                *spelling = format!("{} {}", spelling, extra).trim().to_string();
+            }
+        }
+        query
+    }
+
+    /// `filter` narrowed to trusted uploads if the query asks for them
+    pub fn nyaa_filter(&self, filter: NyaaFilter) -> NyaaFilter {
+        match self.filters.trusted {
+            Some(true) => NyaaFilter::TrustedOnly,
+            _ => filter,
+        }
+    }
+}
+
+/// Whitespace-separated, but a quoted stretch stays in one token, quotes
+/// included
+fn tokenize(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in input.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn unquote(value: &str) -> String {
+    value.trim_matches('"').to_string()
+}
+
+fn parse_yes_no(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "yes" | "y" | "true" | "1" => Some(true),
+        "no" | "n" | "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// `1080`, `1080p` and `4k` all mean what `ReleaseInfo` calls `1080p` and
+/// `2160p`
+fn parse_resolution(value: &str) -> Option<String> {
+    let value = value.to_lowercase();
+    if value == "4k" {
+        return Some("2160p".to_string());
+    }
+    let digits = value.strip_suffix('p').unwrap_or(&value);
+    digits.parse::<u32>().ok().map(|n| format!("{}p", n))
+}
+
+/// A number and an optional unit, as MiB. Nyaa lists sizes in binary
+/// units, so `GB` is read as `GiB`.
+fn parse_size(value: &str) -> Option<f64> {
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let factor = match unit
+        .to_lowercase()
+        .trim_end_matches("ib")
+        .trim_end_matches('b')
+    {
+        "" | "m" => 1.0,
+        "k" => 1.0 / 1024.0,
+        "g" => 1024.0,
+        "t" => 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some(number * factor)
+}
+
+fn parse_bound<T>(value: &str, parse: impl Fn(&str) -> Option<T>) -> Option<Bound<T>> {
+    let (op, rest) = [
+        ("<=", Comparison::AtMost),
+        (">=", Comparison::AtLeast),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+        ("=", Comparison::Equal),
+    ]
+    .into_iter()
+    .find_map(|(prefix, op)| Some((op, value.strip_prefix(prefix)?)))
+    .unwrap_or((Comparison::Equal, value));
+    Some(Bound {
+        op,
+        value: parse(rest)?,
+    })
+}
+
+/// `input` with its last word completed to a filter key, or to one of the
+/// key's values after the `:`. Several candidates complete as far as they
+/// agree; `None` when there's nothing to add.
+pub fn complete(input: &str) -> Option<String> {
+    let start = input.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+    let (head, word) = input.split_at(start);
+    if word.is_empty() {
+        return None;
+    }
+
+    let candidates: Vec<String> = match word.split_once(':') {
+        Some((key, _)) => key_values(&key.to_lowercase())
+            .iter()
+            .map(|value| format!("{}:{}", key, value))
+            .collect(),
+        None => KEYS.iter().map(|key| format!("{}:", key)).collect(),
+    };
+    let typed = word.to_lowercase();
+    let matching: Vec<&String> = candidates
+        .iter()
+        .filter(|c| c.to_lowercase().starts_with(&typed))
+        .collect();
+    let (first, rest) = matching.split_first()?;
+    let common = rest
+        .iter()
+        .map(|other| shared_prefix(first, other))
+        .fold(first.len(), usize::min);
+
+    (common > word.len()).then(|| format!("{}{}", head, &first[..common]))
+}
+
+/// Length in bytes of what `a` and `b` start with, ignoring case
+fn shared_prefix(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .take_while(|((_, x), y)| x.eq_ignore_ascii_case(y))
+        .last()
+        .map_or(0, |((i, x), _)| i + x.len_utf8())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(title: &str, size: &str, seeders: u32, date: &str) -> NyaaResult {
+        NyaaResult {
+            title: title.to_string(),
+            category: "Anime - English-translated".to_string(),
+            size: size.to_string(),
+            seeders,
+            date: date.to_string(),
+            is_trusted: Some(true),
+            ..NyaaResult::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_filters() {
+        let query = AdvancedQuery::parse(
+            "frieren group:SubsPlease res:1080p size:<2GB seeders:>10 -batch trusted:yes after:2024-01-01",
+        )
+        .unwrap();
+        assert_eq!(query.text, "frieren");
+        assert!(!query.native);
+
+        let filters = &query.filters;
+        assert_eq!(filters.group.as_deref(), Some("SubsPlease"));
+        assert_eq!(filters.resolution.as_deref(), Some("1080p"));
+        assert_eq!(
+            filters.size_mb,
+            Some(Bound {
+                op: Comparison::Less,
+                value: 2048.0
+            })
+        );
+        assert_eq!(
+            filters.seeders,
+            Some(Bound {
+                op: Comparison::Greater,
+                value: 10
+            })
+        );
+        assert_eq!(filters.batch, Some(false));
+        assert_eq!(filters.trusted, Some(true));
+        assert_eq!(filters.after.as_deref(), Some("2024-01-01"));
+
+        assert_eq!(
+            query.nyaa_filter(NyaaFilter::NoFilter),
+            NyaaFilter::TrustedOnly
+        );
+        let search = query.search_query();
+        assert_eq!(search.primary, "frieren SubsPlease 1080p");
+        assert!(
+            search
+                .alternatives
+                .iter()
+                .all(|a| a.ends_with(" SubsPlease 1080p"))
+        );
+    }
+
+    #[test]
+    fn test_parse_values() {
+        let parse = |input: &str| AdvancedQuery::parse(input).unwrap().filters;
+        assert_eq!(parse("res:4k").resolution.as_deref(), Some("2160p"));
+        assert_eq!(parse("res:720").resolution.as_deref(), Some("720p"));
+        assert_eq!(parse("size:>=500mb").size_mb.unwrap().value, 500.0);
+        assert_eq!(parse("size:<1.5GiB").size_mb.unwrap().value, 1536.0);
+        assert_eq!(parse("seeders:5").seeders.unwrap().op, Comparison::Equal);
+        assert_eq!(parse("batch:yes").batch, Some(true));
+        assert_eq!(
+            parse("GROUP:\"Anime Time\"").group.as_deref(),
+            Some("Anime Time")
+        );
+
+        for bad in [
+            "res:high",
+            "size:2GB",
+            "size:<lots",
+            "seeders:>many",
+            "trusted:maybe",
+            "after:2024-13-01",
+            "group:",
+        ] {
+            assert!(AdvancedQuery::parse(bad).is_err(), "{}", bad);
+        }
+
+        // Not a filter key; part of the title
+        let query = AdvancedQuery::parse("Re:Zero S03E05").unwrap();
+        assert_eq!(query.text, "Re:Zero S03E05");
+        assert_eq!(query.filters, QueryFilters::default());
+        assert_eq!(query.search_query().parsed.episode, Some(5));
+    }
+
+    #[test]
+    fn test_native_operators() {
+        let query = AdvancedQuery::parse("\"sousou no frieren\" frieren|furiren -raw 05").unwrap();
+        assert!(query.native);
+        assert_eq!(query.text, "\"sousou no frieren\" frieren|furiren -raw 05");
+        assert_eq!(query.words, "sousou no frieren frieren 05");
+        assert_eq!(query.filters.phrases, vec!["sousou no frieren"]);
+        assert_eq!(query.filters.excluded, vec!["raw"]);
+
+        // Sent as typed, not respelled
+        let search = query.search_query();
+        assert_eq!(search.primary, query.text);
+        assert!(search.alternatives.is_empty());
+    }
+
+    #[test]
+    fn test_matches() {
+        let filters = AdvancedQuery::parse(
+            "group:subsplease res:1080p size:<2GB seeders:>=10 after:2024-01-01 before:2024-12-31 -hevc",
+        )
+        .unwrap()
+        .filters;
+        let good = result(
+            "[SubsPlease] Frieren - 05 (1080p) [8E2F1A0C].mkv",
+            "1.4 GiB",
+            10,
+            "2024-10-06 15:32",
+        );
+        assert!(filters.matches(&good));
+
+        let misses = [
+            result(
+                "[Erai-raws] Frieren - 05 [1080p]",
+                "1.4 GiB",
+                10,
+                "2024-10-06 15:32",
+            ),
+            result(
+                "[SubsPlease] Frieren - 05 (720p)",
+                "700.0 MiB",
+                10,
+                "2024-10-06 15:32",
+            ),
+            result(
+                "[SubsPlease] Frieren - 05 (1080p)",
+                "2.1 GiB",
+                10,
+                "2024-10-06 15:32",
+            ),
+            result(
+                "[SubsPlease] Frieren - 05 (1080p)",
+                "1.4 GiB",
+                9,
+                "2024-10-06 15:32",
+            ),
+            result(
+                "[SubsPlease] Frieren - 05 (1080p)",
+                "1.4 GiB",
+                10,
+                "2023-12-31 23:59",
+            ),
+            result(
+                "[SubsPlease] Frieren - 05 (1080p)",
+                "1.4 GiB",
+                10,
+                "2025-01-01 00:00",
+            ),
+            result(
+                "[SubsPlease] Frieren - 05 (1080p HEVC)",
+                "1.4 GiB",
+                10,
+                "2024-10-06 15:32",
+            ),
+            result("[SubsPlease] Frieren - 05 (1080p)", "1.4 GiB", 10, ""),
+        ];
+        for miss in &misses {
+            assert!(!filters.matches(miss), "{}", miss.title);
+        }
+
+        let batch = AdvancedQuery::parse("-batch trusted:no").unwrap().filters;
+        let mut untrusted = good.clone();
+        untrusted.is_trusted = Some(false);
+        assert!(batch.matches(&untrusted));
+        assert!(!batch.matches(&good));
+        untrusted.is_batch = true;
+        assert!(!batch.matches(&untrusted));
+    }
+
+    #[test]
+    fn test_complete() {
+        assert_eq!(complete("frieren gr").as_deref(), Some("frieren group:"));
+        assert_eq!(complete("frieren TR").as_deref(), Some("frieren trusted:"));
+        // `size:` and `seeders:` agree on nothing past the `s`
+        assert_eq!(complete("frieren s"), None);
+        assert_eq!(complete("frieren se").as_deref(), Some("frieren seeders:"));
+        assert_eq!(complete("a").as_deref(), Some("after:"));
+        assert_eq!(complete("trusted:y").as_deref(), Some("trusted:yes"));
+        assert_eq!(complete("res:1").as_deref(), Some("res:1080p"));
+        assert_eq!(complete("res:").as_deref(), None);
+        assert_eq!(complete("frieren "), None);
+        assert_eq!(complete("frieren"), None);
+        assert_eq!(complete("group:Sub"), None);
+    }
+}
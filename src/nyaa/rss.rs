@@ -0,0 +1,168 @@
+//! Nyaa's RSS feed (`?page=rss`). One request per query, no HTML scraping,
+//! and it carries the info hash, so tracking checks use it.
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use super::{NyaaResult, is_batch_release, magnet_from_hash};
+
+static ITEM: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?s)<item>(.*?)</item>").unwrap());
+
+/// A leaf element: plain text or a single CDATA section
+static FIELD: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<([\w:]+)(?:\s[^>]*)?>(<!\[CDATA\[.*?\]\]>|[^<]*)</([\w:]+)>").unwrap()
+});
+
+static ENTITY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"&(#x[0-9a-fA-F]+|#[0-9]+|[a-z]+);").unwrap());
+
+/// Every `<item>` in a feed. Items without a title or info hash are skipped.
+pub fn parse_feed(xml: &str) -> Vec<NyaaResult> {
+    items(xml).filter_map(parse_item).collect()
+}
+
+/// The inside of each `<item>` of an RSS document
+pub fn items(xml: &str) -> impl Iterator<Item = &str> {
+    ITEM.captures_iter(xml)
+        .filter_map(|item| item.get(1).map(|m| m.as_str()))
+}
+
+/// An item's text elements by tag name (namespace prefix included),
+/// entities decoded. Elements with attributes only, like `<enclosure/>`,
+/// aren't included.
+pub fn item_fields(item: &str) -> HashMap<String, String> {
+    let mut fields = HashMap::new();
+    for caps in FIELD.captures_iter(item) {
+        if caps[1] != caps[3] {
+            continue;
+        }
+        let text = match caps[2].strip_prefix("<![CDATA[") {
+            Some(cdata) => cdata.trim_end_matches("]]>").to_string(),
+            None => unescape(caps[2].trim()),
+        };
+        fields.insert(caps[1].to_string(), text);
+    }
+    fields
+}
+
+/// RSS dates as `YYYY-MM-DD HH:MM`, the search page's date column format
+pub fn format_date(pub_date: &str) -> String {
+    match chrono::DateTime::parse_from_rfc2822(pub_date) {
+        Ok(date) => date.format("%Y-%m-%d %H:%M").to_string(),
+        Err(_) => pub_date.to_string(),
+    }
+}
+
+fn parse_item(item: &str) -> Option<NyaaResult> {
+    let mut fields = item_fields(item);
+
+    let title = fields.remove("title").filter(|t| !t.is_empty())?;
+    let info_hash = fields
+        .remove("nyaa:infoHash")
+        .filter(|h| !h.is_empty())?
+        .to_lowercase();
+    let count = |name: &str| fields.get(name).and_then(|v| v.parse().ok()).unwrap_or(0);
+    let flag = |name: &str| fields.get(name).is_some_and(|v| v == "Yes");
+    let size = fields.get("nyaa:size").cloned().unwrap_or_default();
+    let date = fields
+        .get("pubDate")
+        .map(|d| format_date(d))
+        .unwrap_or_default();
+
+    Some(NyaaResult {
+        magnet_link: magnet_from_hash(&info_hash, &title),
+        is_batch: is_batch_release(&title, &size),
+        category: fields
+            .get("nyaa:category")
+            .cloned()
+            .unwrap_or_else(|| "Unknown".to_string()),
+        seeders: count("nyaa:seeders"),
+        leechers: count("nyaa:leechers"),
+        downloads: count("nyaa:downloads"),
+        torrent_url: fields.get("link").cloned().unwrap_or_default(),
+        is_trusted: Some(flag("nyaa:trusted")),
+        is_remake: flag("nyaa:remake"),
+        title,
+        size,
+        date,
+        info_hash,
+    })
+}
+
+pub fn unescape(text: &str) -> String {
+    ENTITY
+        .replace_all(text, |caps: &regex::Captures| {
+            let entity = &caps[1];
+            let decoded = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => entity
+                    .strip_prefix("#x")
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(str::parse))
+                    .and_then(|code| code.ok())
+                    .and_then(char::from_u32),
+            };
+            decoded.map_or_else(|| caps[0].to_string(), String::from)
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::torrent::preview::{magnet_hash, magnet_name};
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/nyaa-rss.xml");
+
+    #[test]
+    fn test_parse_feed_fields() {
+        let results = parse_feed(FIXTURE);
+        assert_eq!(results.len(), 3);
+
+        let first = &results[0];
+        assert_eq!(
+            first.title,
+            "[SubsPlease] Sousou no Frieren - 05 (1080p) [8E2F1A0C].mkv"
+        );
+        assert_eq!(first.info_hash, "3a6b1d8e2c0f4b7a9d5e1c3f8a2b6d4e0c9f7a1b");
+        assert_eq!(first.seeders, 1532);
+        assert_eq!(first.leechers, 41);
+        assert_eq!(first.downloads, 20877);
+        assert_eq!(first.size, "1.4 GiB");
+        assert_eq!(first.date, "2023-10-06 15:32");
+        assert_eq!(first.category, "Anime - English-translated");
+        assert_eq!(
+            first.torrent_url,
+            "https://nyaa.si/download/1729403.torrent"
+        );
+        assert!(first.is_trusted == Some(true) && !first.is_remake && !first.is_batch);
+        assert_eq!(
+            magnet_hash(&first.magnet_link).as_deref(),
+            Some(first.info_hash.as_str())
+        );
+        assert_eq!(magnet_name(&first.magnet_link), Some(first.title.clone()));
+
+        let remake = &results[1];
+        assert_eq!(
+            remake.title,
+            r#"[Fans & Friends] Frieren - 05 "The Fake Corpses" [720p]"#
+        );
+        assert!(remake.is_remake && remake.is_trusted == Some(false));
+
+        assert!(results[2].is_batch);
+    }
+
+    #[test]
+    fn test_items_without_hash_are_skipped() {
+        let xml = "<rss><channel><item><title>No hash</title></item>\
+                   <item><title>A &#x26; B &#38; C</title><nyaa:infoHash>ab</nyaa:infoHash></item></channel></rss>";
+        let results = parse_feed(xml);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "A & B & C");
+    }
+}
@@ -0,0 +1,282 @@
+//! Release scoring profiles: how much a release's group, resolution, codec,
+//! source and so on are worth. Search ranks results by their profile score
+//! on top of how well they match the query; tracking downloads the
+//! best-scoring release of each episode.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::{NyaaResult, parse_size_mb};
+use crate::library::release::ReleaseInfo;
+
+/// Points for each release trait. Unlisted values score nothing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScoringProfile {
+    /// Release groups worth `preferred_group_bonus`, matched case-insensitively
+    pub preferred_groups: Vec<String>,
+    pub preferred_group_bonus: i32,
+    /// Releases from these groups are never picked
+    pub blocked_groups: Vec<String>,
+    /// By resolution: `2160p`, `1080p`, `720p`, `480p`, `360p`
+    pub resolutions: BTreeMap<String, i32>,
+    /// By codec: `hevc`, `av1`, `x264`
+    pub codecs: BTreeMap<String, i32>,
+    /// By source: `web`, `bd`
+    pub sources: BTreeMap<String, i32>,
+    pub dual_audio: i32,
+    /// Added to batches, so negative prefers single episodes
+    pub batch: i32,
+    pub trusted: i32,
+    pub remake: i32,
+    /// Releases outside these bounds are never picked
+    pub min_size_mb: Option<f64>,
+    pub max_size_mb: Option<f64>,
+    pub min_seeders: u32,
+}
+
+impl Default for ScoringProfile {
+    fn default() -> Self {
+        Self {
+            preferred_groups: ["SubsPlease", "Erai-raws", "Judas", "HorribleSubs"]
+                .iter()
+                .map(|g| g.to_string())
+                .collect(),
+            preferred_group_bonus: 15,
+            blocked_groups: Vec::new(),
+            resolutions: BTreeMap::from([
+                ("1080p".to_string(), 10),
+                ("720p".to_string(), 5),
+                ("480p".to_string(), -20),
+                ("360p".to_string(), -20),
+            ]),
+            codecs: BTreeMap::new(),
+            sources: BTreeMap::new(),
+            dual_audio: 0,
+            batch: 0,
+            trusted: 0,
+            remake: 0,
+            min_size_mb: None,
+            max_size_mb: None,
+            min_seeders: 0,
+        }
+    }
+}
+
+/// How a release's score came about
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScoreBreakdown {
+    pub total: i32,
+    /// Every rule that gave or took points, in the order they were applied
+    pub parts: Vec<(String, i32)>,
+    /// Why the release can't be picked at all
+    pub rejected: Option<String>,
+}
+
+impl ScoreBreakdown {
+    fn add(&mut self, what: impl Into<String>, points: i32) {
+        if points != 0 {
+            self.total += points;
+            self.parts.push((what.into(), points));
+        }
+    }
+
+    /// One line, e.g. `SubsPlease +15, 1080p +10`
+    pub fn summary(&self) -> String {
+        if let Some(reason) = &self.rejected {
+            return format!("rejected: {}", reason);
+        }
+        if self.parts.is_empty() {
+            return "no scoring rules apply".to_string();
+        }
+        self.parts
+            .iter()
+            .map(|(what, points)| format!("{} {:+}", what, points))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+impl ScoringProfile {
+    pub fn score(&self, result: &NyaaResult) -> ScoreBreakdown {
+        let mut breakdown = self.score_title(&result.title);
+        if result.is_batch {
+            breakdown.add("batch", self.batch);
+        }
+        if result.is_trusted == Some(true) {
+            breakdown.add("trusted", self.trusted);
+        }
+        if result.is_remake {
+            breakdown.add("remake", self.remake);
+        }
+
+        if breakdown.rejected.is_none() {
+            breakdown.rejected = self.limit_broken(result);
+        }
+        breakdown
+    }
+
+    /// Only what the name says: group, resolution, codec, source and dual
+    /// audio. Works on file names too.
+    pub fn score_title(&self, title: &str) -> ScoreBreakdown {
+        let mut breakdown = ScoreBreakdown::default();
+
+        let release = ReleaseInfo::parse(title);
+        let is_group = |name: &String| match &release.group {
+            Some(g) => g.eq_ignore_ascii_case(name),
+            None => title.to_lowercase().contains(&name.to_lowercase()),
+        };
+        if let Some(blocked) = self.blocked_groups.iter().find(|g| is_group(g)) {
+            breakdown.rejected = Some(format!("{} is blocked", blocked));
+        }
+        if let Some(preferred) = self.preferred_groups.iter().find(|g| is_group(g)) {
+            breakdown.add(preferred.clone(), self.preferred_group_bonus);
+        }
+
+        if let Some(resolution) = &release.resolution {
+            breakdown.add(resolution, lookup(&self.resolutions, resolution));
+        }
+        if let Some(codec) = release.video_codec {
+            breakdown.add(codec.to_uppercase(), lookup(&self.codecs, codec));
+        }
+        if let Some(source) = release.source {
+            breakdown.add(source.to_uppercase(), lookup(&self.sources, source));
+        }
+        if release.is_dual_audio() {
+            breakdown.add("dual audio", self.dual_audio);
+        }
+        breakdown
+    }
+
+    fn limit_broken(&self, result: &NyaaResult) -> Option<String> {
+        if result.seeders < self.min_seeders {
+            return Some(format!("fewer than {} seeders", self.min_seeders));
+        }
+        if self.min_size_mb.is_none() && self.max_size_mb.is_none() {
+            return None;
+        }
+        let size = parse_size_mb(&result.size);
+        if size == 0.0 {
+            // Unknown size, nothing to compare
+            return None;
+        }
+        if let Some(min) = self.min_size_mb
+            && size < min
+        {
+            return Some(format!("smaller than {} MiB", min));
+        }
+        if let Some(max) = self.max_size_mb
+            && size > max
+        {
+            return Some(format!("larger than {} MiB", max));
+        }
+        None
+    }
+}
+
+/// Map keys are matched case-insensitively
+fn lookup(weights: &BTreeMap<String, i32>, key: &str) -> i32 {
+    weights
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map_or(0, |(_, points)| *points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(title: &str, size: &str, seeders: u32) -> NyaaResult {
+        NyaaResult {
+            title: title.to_string(),
+            category: "Anime".to_string(),
+            size: size.to_string(),
+            seeders,
+            is_trusted: Some(false),
+            ..NyaaResult::default()
+        }
+    }
+
+    #[test]
+    fn test_default_profile_matches_old_scoring() {
+        let profile = ScoringProfile::default();
+        let breakdown = profile.score(&result(
+            "[SubsPlease] Frieren - 05 (1080p) [8E2F1A0C].mkv",
+            "1.4 GiB",
+            100,
+        ));
+        assert_eq!(breakdown.total, 25);
+        assert_eq!(breakdown.summary(), "SubsPlease +15, 1080p +10");
+
+        let low = profile.score(&result("[Foo] Frieren - 05 [480p].mkv", "200 MiB", 3));
+        assert_eq!(low.total, -20);
+        assert_eq!(low.rejected, None);
+    }
+
+    #[test]
+    fn test_codec_source_and_dual_audio() {
+        let profile = ScoringProfile {
+            codecs: BTreeMap::from([("hevc".to_string(), 8), ("x264".to_string(), -2)]),
+            sources: BTreeMap::from([("bd".to_string(), 12)]),
+            dual_audio: 5,
+            ..ScoringProfile::default()
+        };
+        let breakdown = profile.score(&result(
+            "[Judas] Frieren (Season 1) [BD 1080p][HEVC x265 10bit][Dual-Audio]",
+            "9.8 GiB",
+            288,
+        ));
+        assert_eq!(breakdown.total, 15 + 10 + 8 + 12 + 5);
+        assert_eq!(
+            breakdown.summary(),
+            "Judas +15, 1080p +10, HEVC +8, BD +12, dual audio +5"
+        );
+    }
+
+    #[test]
+    fn test_rejections() {
+        let profile = ScoringProfile {
+            blocked_groups: vec!["erai-raws".to_string()],
+            max_size_mb: Some(2048.0),
+            min_seeders: 5,
+            ..ScoringProfile::default()
+        };
+        let blocked = profile.score(&result("[Erai-raws] Frieren - 05 [1080p]", "1.4 GiB", 90));
+        assert_eq!(blocked.rejected.as_deref(), Some("erai-raws is blocked"));
+
+        let huge = profile.score(&result("[Foo] Frieren - 05 [1080p]", "3.2 GiB", 90));
+        assert_eq!(huge.rejected.as_deref(), Some("larger than 2048 MiB"));
+
+        let dead = profile.score(&result("[Foo] Frieren - 05 [1080p]", "1.4 GiB", 2));
+        assert_eq!(dead.rejected.as_deref(), Some("fewer than 5 seeders"));
+
+        let fine = profile.score(&result("[Foo] Frieren - 05 [1080p]", "1.4 GiB", 5));
+        assert_eq!(fine.rejected, None);
+    }
+
+    #[test]
+    fn test_config_default_and_named_profiles() {
+        let config: crate::config::ScoringConfig = toml::from_str(
+            r#"
+            blocked_groups = ["Foo"]
+            codecs = { hevc = 5 }
+
+            [profiles.bluray]
+            sources = { bd = 20 }
+            max_size_mb = 30000
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.default.blocked_groups, vec!["Foo"]);
+        assert_eq!(config.default.codecs.get("hevc"), Some(&5));
+        // Unset fields keep their defaults
+        assert_eq!(config.default.preferred_group_bonus, 15);
+
+        let bluray = config.profile(Some("bluray"));
+        assert_eq!(bluray.sources.get("bd"), Some(&20));
+        assert_eq!(bluray.max_size_mb, Some(30000.0));
+        assert_eq!(config.profile(Some("missing")), &config.default);
+        assert_eq!(config.profile(None), &config.default);
+    }
+}
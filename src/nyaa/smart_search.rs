@@ -252,6 +252,8 @@ fn ordinal(n: u32) -> String {
     format!("{}{} Season", n, suffix)
 }
 
+/// How well a result matches the query. Release preferences like group and
+/// resolution come from the scoring profile instead.
 pub fn score_result(result_title: &str, parsed: &ParsedQuery) -> i32 {
     let title_lower = result_title.to_lowercase();
     let show_lower = parsed.show_name.to_lowercase();
@@ -308,15 +310,6 @@ pub fn score_result(result_title: &str, parsed: &ParsedQuery) -> i32 {
         }
     }
 
-    if title_lower.contains("1080p") {
-        score += 10;
-    }
-
-    let good_subgroups = ["subsplease", "erai-raws", "judas", "horriblesubs"];
-    if good_subgroups.iter().any(|g| title_lower.contains(g)) {
-        score += 15;
-    }
-
     if parsed.episode.is_some() && !parsed.is_batch_request {
         let batch_indicators = ["batch", "complete", "1-", "01-"];
         if batch_indicators.iter().any(|b| title_lower.contains(b)) {
@@ -324,24 +317,9 @@ pub fn score_result(result_title: &str, parsed: &ParsedQuery) -> i32 {
         }
     }
 
-    if title_lower.contains("480p") || title_lower.contains("360p") {
-        score -= 20;
-    }
-
     score
 }
 
-pub fn rank_results<T, F>(results: &mut [T], parsed: &ParsedQuery, get_title: F)
-where
-    F: Fn(&T) -> &str,
-{
-    results.sort_by(|a, b| {
-        let score_a = score_result(get_title(a), parsed);
-        let score_b = score_result(get_title(b), parsed);
-        score_b.cmp(&score_a) // Descending order
-    });
-}
-
 /// Main entry point: convert user input to search queries
 ///
 /// # Examples
@@ -0,0 +1,118 @@
+//! Helpers shared by tests that talk HTTP to a local mock server
+
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub head: String,
+    pub body: String,
+}
+
+impl MockRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.head.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+}
+
+pub struct MockResponse {
+    pub status: u16,
+    pub body: String,
+    pub set_cookie: Option<String>,
+    pub headers: Vec<(String, String)>,
+}
+
+pub fn reply(status: u16, body: &str) -> MockResponse {
+    MockResponse {
+        status,
+        body: body.to_string(),
+        set_cookie: None,
+        headers: Vec::new(),
+    }
+}
+
+/// Bare-bones HTTP/1.1 server on a random local port, one request per
+/// connection. Returns the port and a log of "METHOD /path" lines.
+pub async fn mock_server<F>(handler: F) -> (u16, Arc<Mutex<Vec<String>>>)
+where
+    F: Fn(&MockRequest) -> MockResponse + Send + Sync + 'static,
+{
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let handler = Arc::new(handler);
+
+    let server_log = log.clone();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let handler = handler.clone();
+            let log = server_log.clone();
+            tokio::spawn(async move {
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 4096];
+                let head_end = loop {
+                    let n = stream.read(&mut chunk).await.unwrap_or(0);
+                    if n == 0 {
+                        return;
+                    }
+                    buf.extend_from_slice(&chunk[..n]);
+                    if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break pos + 4;
+                    }
+                };
+
+                let head = String::from_utf8_lossy(&buf[..head_end]).to_string();
+                let mut request = MockRequest {
+                    method: String::new(),
+                    path: String::new(),
+                    head,
+                    body: String::new(),
+                };
+                let content_length: usize = request
+                    .header("content-length")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0);
+                while buf.len() < head_end + content_length {
+                    let n = stream.read(&mut chunk).await.unwrap_or(0);
+                    if n == 0 {
+                        break;
+                    }
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+                request.body = String::from_utf8_lossy(&buf[head_end..]).to_string();
+
+                let mut request_line = request.head.split_whitespace();
+                request.method = request_line.next().unwrap_or("").to_string();
+                let target = request_line.next().unwrap_or("");
+                request.path = target.split('?').next().unwrap_or("").to_string();
+                log.lock()
+                    .unwrap()
+                    .push(format!("{} {}", request.method, request.path));
+
+                let response = handler(&request);
+                let mut out = format!(
+                    "HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n",
+                    response.status,
+                    response.body.len()
+                );
+                if let Some(cookie) = response.set_cookie {
+                    out.push_str(&format!("Set-Cookie: {}\r\n", cookie));
+                }
+                for (name, value) in &response.headers {
+                    out.push_str(&format!("{}: {}\r\n", name, value));
+                }
+                out.push_str("\r\n");
+                out.push_str(&response.body);
+                let _ = stream.write_all(out.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    });
+
+    (port, log)
+}
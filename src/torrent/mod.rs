@@ -1,5 +1,6 @@
-mod qbittorrent;
 pub mod preview;
+mod qbittorrent;
+pub mod seeding;
 mod transmission;
 
 pub use qbittorrent::QBittorrentClient;
@@ -16,6 +17,11 @@ pub struct TorrentStatus {
     pub upload_rate: u64,
     pub size: u64,
     pub downloaded: u64,
+    pub uploaded: u64,
+    /// None when the client can't say
+    pub ratio: Option<f64>,
+    /// Seconds spent seeding since the download finished
+    pub seeding_time: u64,
     pub seeders: u32,
     pub state: TorrentState,
     pub save_path: String,
@@ -47,9 +53,109 @@ impl TorrentState {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilePriority {
+    /// Not downloaded at all
+    Skip,
+    Low,
+    Normal,
+    High,
+}
+
+impl FilePriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FilePriority::Skip => "Skip",
+            FilePriority::Low => "Low",
+            FilePriority::Normal => "Normal",
+            FilePriority::High => "High",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TorrentFileStatus {
+    /// Index of the file inside the torrent, as the client numbers it
+    pub index: usize,
+    pub name: String,
+    pub size: u64,
+    pub progress: f64,
+    pub priority: FilePriority,
+}
+
+#[derive(Debug, Clone)]
+pub struct PeerInfo {
+    pub address: String,
+    pub client: String,
+    pub progress: f64,
+    pub download_rate: u64,
+    pub upload_rate: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TrackerInfo {
+    pub url: String,
+    pub status: String,
+    pub seeders: Option<u32>,
+    pub leechers: Option<u32>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TorrentDetails {
+    pub hash: String,
+    /// Seconds until completion, if the client can estimate it
+    pub eta: Option<u64>,
+    pub files: Vec<TorrentFileStatus>,
+    pub peers: Vec<PeerInfo>,
+    pub trackers: Vec<TrackerInfo>,
+    pub error: Option<String>,
+}
+
+/// What changed in the client since the previous `poll_changes` call
+#[derive(Debug, Clone, Default)]
+pub struct TorrentUpdate {
+    /// The client sent its whole list, anything missing from `changed` is gone
+    pub full: bool,
+    pub changed: Vec<TorrentStatus>,
+    pub removed: Vec<String>,
+}
+
+impl TorrentUpdate {
+    /// Apply the delta in place, keeping the existing order so selections stay put
+    pub fn apply_to(self, torrents: &mut Vec<TorrentStatus>) {
+        if self.full {
+            torrents.retain(|t| self.changed.iter().any(|c| c.hash == t.hash));
+        } else {
+            torrents.retain(|t| !self.removed.contains(&t.hash));
+        }
+
+        for status in self.changed {
+            match torrents.iter_mut().find(|t| t.hash == status.hash) {
+                Some(existing) => *existing = status,
+                None => torrents.push(status),
+            }
+        }
+    }
+}
+
 pub trait TorrentClient {
     fn add_magnet(&self, magnet: &str) -> impl std::future::Future<Output = Result<String>> + Send;
 
+    /// Add a torrent from the raw contents of a `.torrent` file. Returns the
+    /// info hash when the client reports it, or an empty string.
+    fn add_torrent_file(
+        &self,
+        bytes: &[u8],
+    ) -> impl std::future::Future<Output = Result<String>> + Send;
+
+    /// Add a `.torrent` the client downloads itself from `url`. Returns the
+    /// info hash when the client reports it, or an empty string.
+    fn add_torrent_url(
+        &self,
+        url: &str,
+    ) -> impl std::future::Future<Output = Result<String>> + Send;
+
     fn list_torrents(&self)
     -> impl std::future::Future<Output = Result<Vec<TorrentStatus>>> + Send;
 
@@ -62,6 +168,22 @@ pub trait TorrentClient {
         hash: &str,
         delete_data: bool,
     ) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    fn details(
+        &self,
+        hash: &str,
+    ) -> impl std::future::Future<Output = Result<TorrentDetails>> + Send;
+
+    fn set_file_priority(
+        &self,
+        hash: &str,
+        file_indices: &[usize],
+        priority: FilePriority,
+    ) -> impl std::future::Future<Output = Result<()>> + Send;
+
+    /// Incremental update since the last call. The first call (and the first
+    /// one after an error) returns the full list.
+    fn poll_changes(&self) -> impl std::future::Future<Output = Result<TorrentUpdate>> + Send;
 }
 
 #[derive(Clone)]
@@ -78,6 +200,36 @@ impl AnyTorrentClient {
         }
     }
 
+    pub async fn add_torrent_file(&self, bytes: &[u8]) -> Result<String> {
+        match self {
+            AnyTorrentClient::Transmission(c) => c.add_torrent_file(bytes).await,
+            AnyTorrentClient::QBittorrent(c) => c.add_torrent_file(bytes).await,
+        }
+    }
+
+    pub async fn add_torrent_url(&self, url: &str) -> Result<String> {
+        match self {
+            AnyTorrentClient::Transmission(c) => c.add_torrent_url(url).await,
+            AnyTorrentClient::QBittorrent(c) => c.add_torrent_url(url).await,
+        }
+    }
+
+    /// Add a search result by its magnet, or by its `.torrent` link for
+    /// indexers that give none. `info_hash` stands in when the client
+    /// doesn't say what it added.
+    pub async fn add_link(&self, link: &str, info_hash: &str) -> Result<String> {
+        let hash = if link.starts_with("magnet:") {
+            self.add_magnet(link).await?
+        } else {
+            self.add_torrent_url(link).await?
+        };
+        Ok(if hash.is_empty() {
+            info_hash.to_lowercase()
+        } else {
+            hash
+        })
+    }
+
     pub async fn list_torrents(&self) -> Result<Vec<TorrentStatus>> {
         match self {
             AnyTorrentClient::Transmission(c) => c.list_torrents().await,
@@ -105,4 +257,89 @@ impl AnyTorrentClient {
             AnyTorrentClient::QBittorrent(c) => c.remove(hash, delete_data).await,
         }
     }
+
+    pub async fn details(&self, hash: &str) -> Result<TorrentDetails> {
+        match self {
+            AnyTorrentClient::Transmission(c) => c.details(hash).await,
+            AnyTorrentClient::QBittorrent(c) => c.details(hash).await,
+        }
+    }
+
+    pub async fn set_file_priority(
+        &self,
+        hash: &str,
+        file_indices: &[usize],
+        priority: FilePriority,
+    ) -> Result<()> {
+        match self {
+            AnyTorrentClient::Transmission(c) => {
+                c.set_file_priority(hash, file_indices, priority).await
+            }
+            AnyTorrentClient::QBittorrent(c) => {
+                c.set_file_priority(hash, file_indices, priority).await
+            }
+        }
+    }
+
+    pub async fn poll_changes(&self) -> Result<TorrentUpdate> {
+        match self {
+            AnyTorrentClient::Transmission(c) => c.poll_changes().await,
+            AnyTorrentClient::QBittorrent(c) => c.poll_changes().await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(hash: &str, progress: f64) -> TorrentStatus {
+        TorrentStatus {
+            name: hash.to_string(),
+            hash: hash.to_string(),
+            progress,
+            download_rate: 0,
+            upload_rate: 0,
+            size: 0,
+            downloaded: 0,
+            uploaded: 0,
+            ratio: None,
+            seeding_time: 0,
+            seeders: 0,
+            state: TorrentState::Downloading,
+            save_path: String::new(),
+            content_path: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_update_apply_delta() {
+        let mut torrents = vec![status("a", 0.1), status("b", 0.2), status("c", 0.3)];
+
+        TorrentUpdate {
+            full: false,
+            changed: vec![status("b", 0.5), status("d", 0.0)],
+            removed: vec!["a".to_string()],
+        }
+        .apply_to(&mut torrents);
+
+        let hashes: Vec<&str> = torrents.iter().map(|t| t.hash.as_str()).collect();
+        assert_eq!(hashes, vec!["b", "c", "d"]);
+        assert_eq!(torrents[0].progress, 0.5);
+    }
+
+    #[test]
+    fn test_update_apply_full() {
+        let mut torrents = vec![status("a", 0.1), status("b", 0.2)];
+
+        TorrentUpdate {
+            full: true,
+            changed: vec![status("b", 0.4)],
+            removed: Vec::new(),
+        }
+        .apply_to(&mut torrents);
+
+        assert_eq!(torrents.len(), 1);
+        assert_eq!(torrents[0].progress, 0.4);
+    }
 }
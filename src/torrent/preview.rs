@@ -1,10 +1,12 @@
+use std::path::PathBuf;
 use std::time::Duration;
 
 use ratatui::widgets::ListState;
-use regex::Regex;
 use serde::Deserialize;
 
 use crate::error::{Error, Result};
+use crate::library::models::BindTarget;
+use crate::library::release::ReleaseInfo;
 use crate::metadata::AnimeMetadata;
 
 #[derive(Debug, Clone)]
@@ -27,12 +29,43 @@ pub enum PreviewSection<T> {
     Error(String),
 }
 
+/// Where the previewed torrent came from, which decides how Enter adds it
+pub enum PreviewSource {
+    /// The selected Nyaa search result
+    Search,
+    Magnet(String),
+    /// A `.torrent` file from the watch folder or typed in by path
+    TorrentFile {
+        path: PathBuf,
+        bytes: Vec<u8>,
+    },
+}
+
 pub struct PreviewState {
     pub torrent_title: String,
     pub torrent_files: PreviewSection<Vec<TorrentFileEntry>>,
     pub mal_info: PreviewSection<AnimeMetadata>,
     pub is_magnet_only: bool,
     pub scroll_state: ListState,
+    pub source: PreviewSource,
+    /// Shows and tracked series the download can be bound to, best match first
+    pub bind_options: Vec<(BindTarget, String)>,
+    pub bind_selected: Option<usize>,
+}
+
+impl PreviewState {
+    pub fn binding(&self) -> Option<&(BindTarget, String)> {
+        self.bind_selected.and_then(|i| self.bind_options.get(i))
+    }
+
+    /// Step through "no binding" and each option
+    pub fn cycle_binding(&mut self) {
+        self.bind_selected = match self.bind_selected {
+            None if !self.bind_options.is_empty() => Some(0),
+            Some(i) if i + 1 < self.bind_options.len() => Some(i + 1),
+            _ => None,
+        };
+    }
 }
 
 // Bencode deserialization structs (private)
@@ -67,8 +100,14 @@ pub fn classify_file(path: &str) -> FileType {
 }
 
 pub fn parse_torrent_files(bytes: &[u8]) -> Result<Vec<TorrentFileEntry>> {
+    parse_torrent(bytes).map(|(_, files)| files)
+}
+
+/// Parse a `.torrent` file into its name and file listing
+pub fn parse_torrent(bytes: &[u8]) -> Result<(String, Vec<TorrentFileEntry>)> {
     let meta: TorrentMeta = serde_bencode::from_bytes(bytes)
         .map_err(|e| Error::TorrentClient(format!("Failed to parse torrent: {}", e)))?;
+    let name = meta.info.name.clone();
 
     let entries = if let Some(files) = meta.info.files {
         files
@@ -92,7 +131,45 @@ pub fn parse_torrent_files(bytes: &[u8]) -> Result<Vec<TorrentFileEntry>> {
         }]
     };
 
-    Ok(entries)
+    Ok((name, entries))
+}
+
+/// Turn a pasted magnet link or `.torrent` path into a preview source
+pub fn load_preview_source(input: &str) -> Result<PreviewSource> {
+    let input = input.trim();
+    if input.starts_with("magnet:") {
+        return Ok(PreviewSource::Magnet(input.to_string()));
+    }
+
+    let path = PathBuf::from(input);
+    let bytes = std::fs::read(&path)?;
+    parse_torrent(&bytes)?;
+    Ok(PreviewSource::TorrentFile { path, bytes })
+}
+
+/// The `dn` (display name) of a magnet link
+pub fn magnet_name(magnet: &str) -> Option<String> {
+    magnet_param(magnet, "dn").map(|dn| {
+        urlencoding::decode(&dn.replace('+', " "))
+            .map(|s| s.into_owned())
+            .unwrap_or(dn)
+    })
+}
+
+/// The info hash from a magnet's `xt=urn:btih:` parameter
+pub fn magnet_hash(magnet: &str) -> Option<String> {
+    magnet_param(magnet, "xt")
+        .and_then(|xt| xt.strip_prefix("urn:btih:").map(|h| h.to_string()))
+        .map(|h| h.to_lowercase())
+}
+
+fn magnet_param(magnet: &str, key: &str) -> Option<String> {
+    let query = magnet.strip_prefix("magnet:?")?;
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| v.to_string())
 }
 
 pub async fn fetch_torrent_files(
@@ -110,18 +187,32 @@ pub async fn fetch_torrent_files(
 }
 
 pub fn extract_anime_title(torrent_name: &str) -> String {
-    // Strip [bracketed] content (subgroup, hash, quality)
-    let re_brackets = Regex::new(r"\[.*?\]").unwrap();
-    let clean = re_brackets.replace_all(torrent_name, "");
-
-    // Strip (parenthesized) content
-    let re_parens = Regex::new(r"\(.*?\)").unwrap();
-    let clean = re_parens.replace_all(&clean, "");
+    ReleaseInfo::parse(torrent_name).title
+}
 
-    // Take everything before episode indicators
-    let re_episode =
-        Regex::new(r"(?i)\s*-\s*\d{1,3}\b|\bS\d{1,2}E\d{1,3}|\bEp?\s*\d{1,3}\b").unwrap();
-    let title = re_episode.split(&clean).next().unwrap_or(&clean);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_torrent_multi_file() {
+        let bytes = b"d4:infod5:filesld6:lengthi100e4:pathl8:ep01.mkveed6:lengthi5e4:pathl4:subs8:ep01.asseee4:name4:Showee";
+        let (name, files) = parse_torrent(bytes).unwrap();
+        assert_eq!(name, "Show");
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[1].path, "subs/ep01.ass");
+        assert!(matches!(files[0].file_type, FileType::Video));
+    }
 
-    title.trim().to_string()
+    #[test]
+    fn test_magnet_fields() {
+        let magnet =
+            "magnet:?xt=urn:btih:ABCDEF0123&dn=%5BSubsPlease%5D+Frieren+-+09&tr=udp%3A%2F%2Ft";
+        assert_eq!(magnet_hash(magnet).as_deref(), Some("abcdef0123"));
+        assert_eq!(
+            magnet_name(magnet).as_deref(),
+            Some("[SubsPlease] Frieren - 09")
+        );
+        assert_eq!(magnet_name("not a magnet"), None);
+    }
 }
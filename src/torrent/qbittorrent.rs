@@ -1,57 +1,213 @@
+use std::collections::HashMap;
+
 use serde::Deserialize;
 use tracing::debug;
 
-use super::{TorrentClient, TorrentState, TorrentStatus};
-use crate::error::{Error, Result};
+use super::preview::parse_torrent;
+use super::{
+    FilePriority, PeerInfo, TorrentClient, TorrentDetails, TorrentFileStatus, TorrentState,
+    TorrentStatus, TorrentUpdate, TrackerInfo,
+};
+use crate::error::{QBittorrentError, Result};
 
 #[derive(Clone)]
 pub struct QBittorrentClient {
     client: reqwest::Client,
     base_url: String,
+    /// Username and password for cookie sessions. `None` when using an API
+    /// key or when the Web UI bypasses auth (e.g. for localhost).
+    credentials: Option<(String, String)>,
+    api_key: Option<String>,
+    session: std::sync::Arc<tokio::sync::Mutex<QBSession>>,
+    sync: std::sync::Arc<tokio::sync::Mutex<QBSyncState>>,
+}
+
+#[derive(Default)]
+struct QBSession {
+    logged_in: bool,
+    major_version: Option<u32>,
 }
 
 impl QBittorrentClient {
-    pub fn new(host: &str, port: u16, username: Option<&str>, password: Option<&str>) -> Self {
+    pub fn new(
+        host: &str,
+        port: u16,
+        username: Option<&str>,
+        password: Option<&str>,
+        api_key: Option<&str>,
+    ) -> Self {
         let client = reqwest::Client::builder()
             .cookie_store(true)
             .build()
             .expect("Failed to create HTTP client");
 
-        let qb = Self {
-            client,
-            base_url: format!("http://{}:{}", host, port),
+        let credentials = match (username, password, api_key) {
+            (Some(user), Some(pass), None) => Some((user.to_string(), pass.to_string())),
+            _ => None,
         };
 
-        if let (Some(_user), Some(_pass)) = (username, password) {}
-
-        qb
+        Self {
+            client,
+            base_url: format!("http://{}:{}", host, port),
+            credentials,
+            api_key: api_key.map(|k| k.to_string()),
+            session: Default::default(),
+            sync: Default::default(),
+        }
     }
 
     pub async fn login(&self, username: &str, password: &str) -> Result<()> {
         let url = format!("{}/api/v2/auth/login", self.base_url);
 
+        // The Web UI rejects logins whose Referer doesn't match its own host
         let response = self
             .client
             .post(&url)
+            .header(reqwest::header::REFERER, &self.base_url)
             .form(&[("username", username), ("password", password)])
             .send()
             .await?;
 
-        if !response.status().is_success() {
-            return Err(Error::TorrentClient("qBittorrent login failed".to_string()));
+        if response.status() == reqwest::StatusCode::FORBIDDEN {
+            return Err(QBittorrentError::LoginFailed(
+                "too many failed attempts, IP is banned".to_string(),
+            )
+            .into());
         }
+        let response = check_status("auth/login", response)?;
 
         let text = response.text().await?;
         if text != "Ok." {
-            return Err(Error::TorrentClient(format!(
-                "qBittorrent login failed: {}",
-                text
-            )));
+            return Err(QBittorrentError::LoginFailed(text).into());
         }
 
         debug!("Logged in to qBittorrent");
         Ok(())
     }
+
+    async fn ensure_logged_in(&self) -> Result<()> {
+        let Some((username, password)) = &self.credentials else {
+            return Ok(());
+        };
+
+        let mut session = self.session.lock().await;
+        if !session.logged_in {
+            self.login(username, password).await?;
+            session.logged_in = true;
+        }
+        Ok(())
+    }
+
+    /// Send a request to `/api/v2/{endpoint}`, logging in first when needed.
+    /// qBittorrent answers 403 once the session cookie expires, so on a 403
+    /// we log in again and retry once.
+    async fn send<F>(&self, endpoint: &str, build: F) -> Result<reqwest::Response>
+    where
+        F: Fn(&reqwest::Client, &str) -> reqwest::RequestBuilder,
+    {
+        let url = format!("{}/api/v2/{}", self.base_url, endpoint);
+
+        self.ensure_logged_in().await?;
+        let response = self.authorize(build(&self.client, &url)).send().await?;
+
+        if response.status() != reqwest::StatusCode::FORBIDDEN || self.credentials.is_none() {
+            return check_status(endpoint, response);
+        }
+
+        debug!(endpoint, "qBittorrent session expired, logging in again");
+        self.session.lock().await.logged_in = false;
+        self.ensure_logged_in().await?;
+
+        let response = self.authorize(build(&self.client, &url)).send().await?;
+        check_status(endpoint, response)
+    }
+
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
+    /// qBittorrent doesn't echo the hash back for uploads, so look for the
+    /// newest torrent with the uploaded one's name. It can take a moment to
+    /// show up.
+    async fn find_added(&self, name: &str) -> Result<Option<String>> {
+        for attempt in 0..5 {
+            if attempt > 0 {
+                tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            }
+            let response = self
+                .send("torrents/info", |c, url| {
+                    c.get(url)
+                        .query(&[("sort", "added_on"), ("reverse", "true")])
+                })
+                .await?;
+            let torrents: Vec<QBTorrent> = response.json().await?;
+            if let Some(torrent) = torrents.into_iter().find(|t| t.name == name) {
+                return Ok(Some(torrent.hash.to_lowercase()));
+            }
+        }
+        debug!(name, "Uploaded torrent never showed up in qBittorrent");
+        Ok(None)
+    }
+
+    /// Major version of the qBittorrent app, fetched once per client
+    async fn major_version(&self) -> Result<u32> {
+        let cached = self.session.lock().await.major_version;
+        if let Some(major) = cached {
+            return Ok(major);
+        }
+
+        let text = self
+            .send("app/version", |c, url| c.get(url))
+            .await?
+            .text()
+            .await?;
+        let major = parse_major_version(&text)
+            .ok_or_else(|| QBittorrentError::UnexpectedResponse(format!("version {:?}", text)))?;
+
+        debug!(version = %text.trim(), "Detected qBittorrent version");
+        self.session.lock().await.major_version = Some(major);
+        Ok(major)
+    }
+
+    /// qBittorrent 5 renamed `pause`/`resume` to `stop`/`start`
+    async fn pause_endpoint(&self, pause: bool) -> Result<&'static str> {
+        let v5 = self.major_version().await? >= 5;
+        Ok(match (pause, v5) {
+            (true, true) => "torrents/stop",
+            (true, false) => "torrents/pause",
+            (false, true) => "torrents/start",
+            (false, false) => "torrents/resume",
+        })
+    }
+}
+
+fn check_status(endpoint: &str, response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status == reqwest::StatusCode::FORBIDDEN {
+        return Err(QBittorrentError::Forbidden.into());
+    }
+    if !status.is_success() {
+        return Err(QBittorrentError::Api {
+            endpoint: endpoint.to_string(),
+            status: status.as_u16(),
+        }
+        .into());
+    }
+    Ok(response)
+}
+
+/// "v4.6.2" or "v5.0.0beta1" -> 4 / 5
+fn parse_major_version(version: &str) -> Option<u32> {
+    version
+        .trim()
+        .trim_start_matches('v')
+        .split('.')
+        .next()?
+        .parse()
+        .ok()
 }
 
 #[derive(Deserialize)]
@@ -63,29 +219,160 @@ struct QBTorrent {
     upspeed: u64,
     size: u64,
     downloaded: u64,
+    #[serde(default)]
+    uploaded: u64,
+    #[serde(default)]
+    ratio: f64,
+    #[serde(default)]
+    seeding_time: u64,
     num_seeds: u32,
     state: String,
     save_path: String,
     content_path: String,
 }
 
-impl TorrentClient for QBittorrentClient {
-    async fn add_magnet(&self, magnet: &str) -> Result<String> {
-        let url = format!("{}/api/v2/torrents/add", self.base_url);
+impl From<QBTorrent> for TorrentStatus {
+    fn from(t: QBTorrent) -> Self {
+        TorrentStatus {
+            hash: t.hash,
+            name: t.name,
+            progress: t.progress,
+            download_rate: t.dlspeed,
+            upload_rate: t.upspeed,
+            size: t.size,
+            downloaded: t.downloaded,
+            uploaded: t.uploaded,
+            ratio: (t.ratio >= 0.0).then_some(t.ratio),
+            seeding_time: t.seeding_time,
+            seeders: t.num_seeds,
+            state: parse_qb_state(&t.state),
+            save_path: t.save_path,
+            content_path: t.content_path,
+        }
+    }
+}
+
+/// `sync/maindata` response. Torrent entries only carry the fields that
+/// changed since `rid`, keyed by hash.
+#[derive(Deserialize)]
+struct QBMainData {
+    rid: i64,
+    #[serde(default)]
+    full_update: bool,
+    #[serde(default)]
+    torrents: HashMap<String, serde_json::Map<String, serde_json::Value>>,
+    #[serde(default)]
+    torrents_removed: Vec<String>,
+}
+
+/// Last seen `rid` plus every torrent's merged fields
+#[derive(Default)]
+struct QBSyncState {
+    rid: i64,
+    torrents: HashMap<String, serde_json::Map<String, serde_json::Value>>,
+}
+
+impl QBSyncState {
+    fn apply(&mut self, data: QBMainData) -> TorrentUpdate {
+        if data.full_update {
+            self.torrents.clear();
+        }
+        self.rid = data.rid;
+
+        let mut changed = Vec::new();
+        for (hash, fields) in data.torrents {
+            let merged = self.torrents.entry(hash.clone()).or_default();
+            merged.extend(fields);
+
+            let mut value = merged.clone();
+            value.insert("hash".to_string(), serde_json::Value::String(hash.clone()));
+            match serde_json::from_value::<QBTorrent>(serde_json::Value::Object(value)) {
+                Ok(t) => changed.push(t.into()),
+                Err(e) => debug!(hash = %hash, error = %e, "Incomplete qBittorrent sync entry"),
+            }
+        }
+
+        for hash in &data.torrents_removed {
+            self.torrents.remove(hash);
+        }
+
+        TorrentUpdate {
+            full: data.full_update,
+            changed,
+            removed: data.torrents_removed,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct QBFile {
+    #[serde(default)]
+    index: Option<usize>,
+    name: String,
+    size: u64,
+    progress: f64,
+    priority: i64,
+}
+
+#[derive(Deserialize)]
+struct QBProperties {
+    #[serde(default)]
+    eta: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct QBTracker {
+    url: String,
+    status: i64,
+    #[serde(default)]
+    num_seeds: i64,
+    #[serde(default)]
+    num_leeches: i64,
+    #[serde(default)]
+    msg: String,
+}
+
+#[derive(Deserialize)]
+struct QBPeers {
+    #[serde(default)]
+    peers: HashMap<String, QBPeer>,
+}
 
+#[derive(Deserialize)]
+struct QBPeer {
+    #[serde(default)]
+    client: String,
+    #[serde(default)]
+    progress: f64,
+    #[serde(default)]
+    dl_speed: u64,
+    #[serde(default)]
+    up_speed: u64,
+}
+
+/// qBittorrent reports this ETA for torrents that will never finish
+const QB_INFINITE_ETA: i64 = 8_640_000;
+
+impl QBittorrentClient {
+    async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        endpoint: &str,
+        hash: &str,
+    ) -> Result<T> {
         let response = self
-            .client
-            .post(&url)
-            .form(&[("urls", magnet)])
-            .send()
+            .send(endpoint, |c, url| c.get(url).query(&[("hash", hash)]))
             .await?;
 
-        if !response.status().is_success() {
-            return Err(Error::TorrentClient(format!(
-                "qBittorrent add failed: {}",
-                response.status()
-            )));
-        }
+        Ok(response.json().await?)
+    }
+}
+
+impl TorrentClient for QBittorrentClient {
+    async fn add_magnet(&self, magnet: &str) -> Result<String> {
+        self.send("torrents/add", |c, url| {
+            c.post(url).form(&[("urls", magnet)])
+        })
+        .await?;
 
         let hash = magnet
             .split("btih:")
@@ -98,88 +385,425 @@ impl TorrentClient for QBittorrentClient {
         Ok(hash)
     }
 
-    async fn list_torrents(&self) -> Result<Vec<TorrentStatus>> {
-        let url = format!("{}/api/v2/torrents/info", self.base_url);
+    async fn add_torrent_file(&self, bytes: &[u8]) -> Result<String> {
+        let (name, _) = parse_torrent(bytes)?;
+
+        // torrents/add only takes files as multipart/form-data
+        let boundary = format!("miru{:016x}", rand::random::<u64>());
+        let mut body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"torrents\"; \
+             filename=\"upload.torrent\"\r\nContent-Type: application/x-bittorrent\r\n\r\n"
+        )
+        .into_bytes();
+        body.extend_from_slice(bytes);
+        body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+        self.send("torrents/add", |c, url| {
+            c.post(url)
+                .header(
+                    reqwest::header::CONTENT_TYPE,
+                    format!("multipart/form-data; boundary={boundary}"),
+                )
+                .body(body.clone())
+        })
+        .await?;
+
+        let hash = self.find_added(&name).await?.unwrap_or_default();
+        debug!(hash = %hash, "Added torrent file to qBittorrent");
+        Ok(hash)
+    }
 
-        let response = self.client.get(&url).send().await?;
+    async fn add_torrent_url(&self, url: &str) -> Result<String> {
+        self.send("torrents/add", |c, api| c.post(api).form(&[("urls", url)]))
+            .await?;
 
-        if !response.status().is_success() {
-            return Err(Error::TorrentClient(format!(
-                "qBittorrent list failed: {}",
-                response.status()
-            )));
-        }
+        // The name isn't known until qBittorrent has fetched the file
+        debug!(url, "Added torrent URL to qBittorrent");
+        Ok(String::new())
+    }
 
-        let torrents: Vec<QBTorrent> = response.json().await?;
+    async fn list_torrents(&self) -> Result<Vec<TorrentStatus>> {
+        let response = self.send("torrents/info", |c, url| c.get(url)).await?;
 
-        let statuses = torrents
-            .into_iter()
-            .map(|t| TorrentStatus {
-                hash: t.hash,
-                name: t.name,
-                progress: t.progress,
-                download_rate: t.dlspeed,
-                upload_rate: t.upspeed,
-                size: t.size,
-                downloaded: t.downloaded,
-                seeders: t.num_seeds,
-                state: parse_qb_state(&t.state),
-                save_path: t.save_path,
-                content_path: t.content_path,
-            })
-            .collect();
+        let torrents: Vec<QBTorrent> = response.json().await?;
 
-        Ok(statuses)
+        Ok(torrents.into_iter().map(TorrentStatus::from).collect())
     }
 
     async fn pause(&self, hash: &str) -> Result<()> {
-        let url = format!("{}/api/v2/torrents/pause", self.base_url);
+        let endpoint = self.pause_endpoint(true).await?;
 
-        self.client
-            .post(&url)
-            .form(&[("hashes", hash)])
-            .send()
+        self.send(endpoint, |c, url| c.post(url).form(&[("hashes", hash)]))
             .await?;
 
         Ok(())
     }
 
     async fn resume(&self, hash: &str) -> Result<()> {
-        let url = format!("{}/api/v2/torrents/resume", self.base_url);
+        let endpoint = self.pause_endpoint(false).await?;
 
-        self.client
-            .post(&url)
-            .form(&[("hashes", hash)])
-            .send()
+        self.send(endpoint, |c, url| c.post(url).form(&[("hashes", hash)]))
             .await?;
 
         Ok(())
     }
 
     async fn remove(&self, hash: &str, delete_data: bool) -> Result<()> {
-        let url = format!("{}/api/v2/torrents/delete", self.base_url);
+        let delete_files = if delete_data { "true" } else { "false" };
 
-        self.client
-            .post(&url)
-            .form(&[
-                ("hashes", hash),
-                ("deleteFiles", if delete_data { "true" } else { "false" }),
-            ])
-            .send()
-            .await?;
+        self.send("torrents/delete", |c, url| {
+            c.post(url)
+                .form(&[("hashes", hash), ("deleteFiles", delete_files)])
+        })
+        .await?;
 
         Ok(())
     }
+
+    async fn details(&self, hash: &str) -> Result<TorrentDetails> {
+        let files: Vec<QBFile> = self.get_json("torrents/files", hash).await?;
+        let properties: QBProperties = self.get_json("torrents/properties", hash).await?;
+        let trackers: Vec<QBTracker> = self.get_json("torrents/trackers", hash).await?;
+        let peers: QBPeers = self.get_json("sync/torrentPeers", hash).await?;
+
+        let files = files
+            .into_iter()
+            .enumerate()
+            .map(|(i, f)| TorrentFileStatus {
+                index: f.index.unwrap_or(i),
+                name: f.name,
+                size: f.size,
+                progress: f.progress,
+                priority: match f.priority {
+                    0 => FilePriority::Skip,
+                    6 | 7 => FilePriority::High,
+                    _ => FilePriority::Normal,
+                },
+            })
+            .collect();
+
+        let mut peers: Vec<PeerInfo> = peers
+            .peers
+            .into_iter()
+            .map(|(address, p)| PeerInfo {
+                address,
+                client: p.client,
+                progress: p.progress,
+                download_rate: p.dl_speed,
+                upload_rate: p.up_speed,
+            })
+            .collect();
+        peers.sort_by_key(|p| std::cmp::Reverse(p.download_rate));
+
+        let trackers = trackers
+            .into_iter()
+            // DHT, PeX and LSD show up as pseudo-trackers named "** [DHT] **" etc.
+            .filter(|t| !t.url.starts_with("**"))
+            .map(|t| TrackerInfo {
+                url: t.url,
+                status: match t.status {
+                    0 => "Disabled",
+                    1 => "Not contacted",
+                    2 => "Working",
+                    3 => "Updating",
+                    _ => "Not working",
+                }
+                .to_string(),
+                seeders: (t.num_seeds >= 0).then_some(t.num_seeds as u32),
+                leechers: (t.num_leeches >= 0).then_some(t.num_leeches as u32),
+                message: t.msg,
+            })
+            .collect();
+
+        let eta = properties
+            .eta
+            .filter(|e| *e >= 0 && *e < QB_INFINITE_ETA)
+            .map(|e| e as u64);
+
+        Ok(TorrentDetails {
+            hash: hash.to_string(),
+            eta,
+            files,
+            peers,
+            trackers,
+            // qBittorrent has no per-torrent error string; tracker messages carry the details
+            error: None,
+        })
+    }
+
+    async fn set_file_priority(
+        &self,
+        hash: &str,
+        file_indices: &[usize],
+        priority: FilePriority,
+    ) -> Result<()> {
+        let ids = file_indices
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join("|");
+        let priority = match priority {
+            FilePriority::Skip => "0",
+            FilePriority::Low | FilePriority::Normal => "1",
+            FilePriority::High => "7",
+        };
+
+        self.send("torrents/filePrio", |c, url| {
+            c.post(url)
+                .form(&[("hash", hash), ("id", ids.as_str()), ("priority", priority)])
+        })
+        .await?;
+
+        Ok(())
+    }
+
+    async fn poll_changes(&self) -> Result<TorrentUpdate> {
+        let mut sync = self.sync.lock().await;
+        let rid = sync.rid;
+
+        let result: Result<QBMainData> = async {
+            let response = self
+                .send("sync/maindata", |c, url| c.get(url).query(&[("rid", rid)]))
+                .await?;
+            Ok(response.json().await?)
+        }
+        .await;
+
+        match result {
+            Ok(data) => Ok(sync.apply(data)),
+            Err(e) => {
+                // rid 0 asks for a full update next time
+                *sync = QBSyncState::default();
+                Err(e)
+            }
+        }
+    }
 }
 
 fn parse_qb_state(state: &str) -> TorrentState {
     match state {
         "downloading" | "forcedDL" | "metaDL" | "stalledDL" => TorrentState::Downloading,
         "uploading" | "forcedUP" | "stalledUP" => TorrentState::Seeding,
-        "pausedDL" | "pausedUP" => TorrentState::Paused,
+        // qBittorrent 5 reports stopped* instead of paused*
+        "pausedDL" | "pausedUP" | "stoppedDL" | "stoppedUP" => TorrentState::Paused,
         "queuedDL" | "queuedUP" => TorrentState::Queued,
         "checkingDL" | "checkingUP" | "checkingResumeData" => TorrentState::Checking,
         "error" | "missingFiles" => TorrentState::Error,
         _ => TorrentState::Unknown,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::error::Error;
+    use crate::test_support::{MockResponse, mock_server, reply};
+    use crate::torrent::AnyTorrentClient;
+
+    #[tokio::test]
+    async fn test_relogin_after_session_expires() {
+        // The SID the server currently accepts, and how many logins it has seen
+        let state = Arc::new(Mutex::new((None::<String>, 0u32)));
+
+        let server_state = state.clone();
+        let (port, _) = mock_server(move |req| {
+            let mut state = server_state.lock().unwrap();
+            match req.path.as_str() {
+                "/api/v2/auth/login" if req.body.contains("password=secret") => {
+                    state.1 += 1;
+                    let sid = format!("sid{}", state.1);
+                    state.0 = Some(sid.clone());
+                    MockResponse {
+                        set_cookie: Some(format!("SID={}; path=/", sid)),
+                        ..reply(200, "Ok.")
+                    }
+                }
+                "/api/v2/auth/login" => reply(200, "Fails."),
+                "/api/v2/torrents/info" => {
+                    let cookie = req.header("cookie").unwrap_or("");
+                    match &state.0 {
+                        Some(sid) if cookie.contains(&format!("SID={}", sid)) => reply(200, "[]"),
+                        _ => reply(403, "Forbidden"),
+                    }
+                }
+                _ => reply(404, ""),
+            }
+        })
+        .await;
+
+        let client = QBittorrentClient::new("127.0.0.1", port, Some("admin"), Some("secret"), None);
+        assert!(client.list_torrents().await.unwrap().is_empty());
+
+        // Server drops the session, the next call has to log in again
+        state.lock().unwrap().0 = None;
+        assert!(client.list_torrents().await.unwrap().is_empty());
+        assert_eq!(state.lock().unwrap().1, 2);
+
+        let bad = QBittorrentClient::new("127.0.0.1", port, Some("admin"), Some("wrong"), None);
+        let err = bad.list_torrents().await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::QBittorrent(QBittorrentError::LoginFailed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_torrent_file_hash_is_looked_up() {
+        let (port, log) = mock_server(|req| match req.path.as_str() {
+            "/api/v2/torrents/add" => reply(200, "Ok."),
+            "/api/v2/torrents/info" => {
+                assert!(req.head.contains("sort=added_on&reverse=true"));
+                reply(
+                    200,
+                    r#"[{"hash":"AAAA","name":"Other","progress":1.0,"dlspeed":0,"upspeed":0,"size":1,"downloaded":1,"num_seeds":0,"state":"uploading","save_path":"/dl","content_path":"/dl/Other"},
+                        {"hash":"BBBB","name":"Show","progress":0.0,"dlspeed":0,"upspeed":0,"size":105,"downloaded":0,"num_seeds":0,"state":"metaDL","save_path":"/dl","content_path":"/dl/Show"}]"#,
+                )
+            }
+            _ => reply(404, ""),
+        })
+        .await;
+        let client = QBittorrentClient::new("127.0.0.1", port, None, None, Some("key"));
+
+        let bytes = b"d4:infod5:filesld6:lengthi100e4:pathl8:ep01.mkveed6:lengthi5e4:pathl4:subs8:ep01.asseee4:name4:Showee";
+        assert_eq!(client.add_torrent_file(bytes).await.unwrap(), "bbbb");
+        assert_eq!(log.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_torrent_link_without_magnet_is_added_by_url() {
+        let (port, log) = mock_server(|req| match req.path.as_str() {
+            "/api/v2/torrents/add" => {
+                assert_eq!(
+                    req.body,
+                    "urls=http%3A%2F%2Fprowlarr%3A9696%2F1%2Fdownload%3Fid%3D5"
+                );
+                reply(200, "Ok.")
+            }
+            _ => reply(404, ""),
+        })
+        .await;
+        let client = AnyTorrentClient::QBittorrent(QBittorrentClient::new(
+            "127.0.0.1",
+            port,
+            None,
+            None,
+            Some("key"),
+        ));
+
+        // qBittorrent can't say what it added, so the indexer's hash is used
+        let hash = client
+            .add_link("http://prowlarr:9696/1/download?id=5", "ABCD")
+            .await
+            .unwrap();
+        assert_eq!(hash, "abcd");
+        assert_eq!(*log.lock().unwrap(), vec!["POST /api/v2/torrents/add"]);
+    }
+
+    #[tokio::test]
+    async fn test_api_key_skips_login() {
+        let (port, log) = mock_server(|req| match req.path.as_str() {
+            "/api/v2/torrents/info" if req.header("authorization") == Some("Bearer key123") => {
+                reply(200, "[]")
+            }
+            _ => reply(403, "Forbidden"),
+        })
+        .await;
+
+        let client = QBittorrentClient::new(
+            "127.0.0.1",
+            port,
+            Some("admin"),
+            Some("secret"),
+            Some("key123"),
+        );
+        assert!(client.list_torrents().await.is_ok());
+        assert_eq!(*log.lock().unwrap(), vec!["GET /api/v2/torrents/info"]);
+
+        // Without credentials or a key a 403 is reported as-is, no login attempt
+        let bypass = QBittorrentClient::new("127.0.0.1", port, None, None, None);
+        let err = bypass.list_torrents().await.unwrap_err();
+        assert!(matches!(
+            err,
+            Error::QBittorrent(QBittorrentError::Forbidden)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_pause_endpoint_follows_version() {
+        for (version, pause, resume) in [
+            (
+                "v4.6.7",
+                "/api/v2/torrents/pause",
+                "/api/v2/torrents/resume",
+            ),
+            ("v5.0.2", "/api/v2/torrents/stop", "/api/v2/torrents/start"),
+        ] {
+            let (port, log) = mock_server(move |req| match req.path.as_str() {
+                "/api/v2/app/version" => reply(200, version),
+                p if p == pause || p == resume => reply(200, ""),
+                _ => reply(404, ""),
+            })
+            .await;
+
+            let client = QBittorrentClient::new("127.0.0.1", port, None, None, None);
+            client.pause("abc").await.unwrap();
+            client.resume("abc").await.unwrap();
+
+            let log = log.lock().unwrap().clone();
+            assert_eq!(
+                log,
+                vec![
+                    "GET /api/v2/app/version".to_string(),
+                    format!("POST {}", pause),
+                    format!("POST {}", resume),
+                ]
+            );
+        }
+    }
+
+    fn maindata(json: serde_json::Value) -> QBMainData {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_sync_merges_partial_updates() {
+        let mut sync = QBSyncState::default();
+
+        let update = sync.apply(maindata(serde_json::json!({
+            "rid": 1,
+            "full_update": true,
+            "torrents": {
+                "aaa": {
+                    "name": "[SubsPlease] Frieren - 09 (1080p).mkv", "progress": 0.1,
+                    "dlspeed": 100, "upspeed": 0, "size": 1000, "downloaded": 100,
+                    "num_seeds": 5, "state": "downloading",
+                    "save_path": "/dl", "content_path": "/dl/frieren.mkv"
+                }
+            }
+        })));
+        assert!(update.full);
+        assert_eq!(update.changed.len(), 1);
+        assert_eq!(sync.rid, 1);
+
+        // Later responses only carry the fields that changed
+        let update = sync.apply(maindata(serde_json::json!({
+            "rid": 2,
+            "torrents": { "aaa": { "progress": 1.0, "state": "uploading" } }
+        })));
+        assert!(!update.full);
+        let t = &update.changed[0];
+        assert_eq!(t.hash, "aaa");
+        assert_eq!(t.progress, 1.0);
+        assert_eq!(t.state, TorrentState::Seeding);
+        assert_eq!(t.content_path, "/dl/frieren.mkv");
+
+        let update = sync.apply(maindata(serde_json::json!({
+            "rid": 3,
+            "torrents_removed": ["aaa"]
+        })));
+        assert_eq!(update.removed, vec!["aaa".to_string()]);
+        assert!(sync.torrents.is_empty());
+    }
+}
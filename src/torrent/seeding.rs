@@ -0,0 +1,182 @@
+//! Seeding policies - decide when finished torrents should leave the client
+
+use std::collections::HashSet;
+
+use super::{TorrentState, TorrentStatus};
+use crate::config::SeedingPolicy;
+use crate::library::models::TrackedSeries;
+
+/// Find the tracked series a torrent was downloaded for, by name match
+pub fn series_for_torrent<'a>(
+    torrent: &TorrentStatus,
+    tracked: &'a [TrackedSeries],
+) -> Option<&'a TrackedSeries> {
+    let name = torrent.name.to_lowercase();
+    tracked.iter().find(|s| {
+        let query = s.query.to_lowercase();
+        let title = s.title.to_lowercase();
+        (!query.is_empty() && name.contains(&query)) || (!title.is_empty() && name.contains(&title))
+    })
+}
+
+/// The policy that applies to a torrent: the series override if it has one,
+/// otherwise the global `[torrent.seeding]` policy
+pub fn policy_for<'a>(
+    torrent: &TorrentStatus,
+    tracked: &'a [TrackedSeries],
+    global: &'a SeedingPolicy,
+) -> &'a SeedingPolicy {
+    series_for_torrent(torrent, tracked)
+        .and_then(|s| s.seeding.as_ref())
+        .unwrap_or(global)
+}
+
+pub fn has_any_policy(tracked: &[TrackedSeries], global: &SeedingPolicy) -> bool {
+    !global.is_unlimited()
+        || tracked
+            .iter()
+            .any(|s| s.seeding.as_ref().is_some_and(|p| !p.is_unlimited()))
+}
+
+/// Hashes of finished torrents whose seeding policy has been met. Only
+/// torrents miru added (`added`, lowercase hashes) are considered; the rest
+/// of the client is the user's business.
+pub fn torrents_to_remove(
+    torrents: &[TorrentStatus],
+    added: &HashSet<String>,
+    tracked: &[TrackedSeries],
+    global: &SeedingPolicy,
+) -> Vec<String> {
+    torrents
+        .iter()
+        .filter(|t| added.contains(&t.hash.to_lowercase()))
+        .filter(|t| t.progress >= 1.0)
+        .filter(|t| {
+            matches!(
+                t.state,
+                TorrentState::Seeding | TorrentState::Paused | TorrentState::Queued
+            )
+        })
+        .filter(|t| policy_for(t, tracked, global).is_met(t.ratio, t.seeding_time))
+        .map(|t| t.hash.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn torrent(name: &str, ratio: f64, seeding_time: u64) -> TorrentStatus {
+        TorrentStatus {
+            name: name.to_string(),
+            hash: name.to_lowercase(),
+            progress: 1.0,
+            download_rate: 0,
+            upload_rate: 0,
+            size: 0,
+            downloaded: 0,
+            uploaded: 0,
+            ratio: Some(ratio),
+            seeding_time,
+            seeders: 0,
+            state: TorrentState::Seeding,
+            save_path: String::new(),
+            content_path: String::new(),
+        }
+    }
+
+    fn added(torrents: &[TorrentStatus]) -> HashSet<String> {
+        torrents.iter().map(|t| t.hash.clone()).collect()
+    }
+
+    fn series(query: &str, seeding: Option<SeedingPolicy>) -> TrackedSeries {
+        TrackedSeries {
+            id: query.to_lowercase(),
+            title: query.to_string(),
+            query: query.to_string(),
+            seeding,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_policy_ratio_or_time() {
+        let policy = SeedingPolicy {
+            ratio_limit: Some(2.0),
+            seed_days: Some(7),
+        };
+        assert!(!policy.is_met(Some(1.5), DAY));
+        assert!(policy.is_met(Some(2.0), DAY));
+        assert!(policy.is_met(Some(0.1), 7 * DAY));
+        assert!(!SeedingPolicy::default().is_met(Some(100.0), 100 * DAY));
+        // Unknown ratios only ever meet the time limit
+        assert!(!policy.is_met(None, DAY));
+        assert!(policy.is_met(None, 7 * DAY));
+    }
+
+    #[test]
+    fn test_global_policy_applies_to_untracked() {
+        let global = SeedingPolicy {
+            ratio_limit: Some(1.0),
+            seed_days: None,
+        };
+        let torrents = vec![
+            torrent("[SubsPlease] Frieren - 09 [1080p].mkv", 1.2, 0),
+            torrent("[SubsPlease] Dandadan - 03 [1080p].mkv", 0.4, 0),
+        ];
+        let removed = torrents_to_remove(&torrents, &added(&torrents), &[], &global);
+        assert_eq!(removed, vec![torrents[0].hash.clone()]);
+    }
+
+    #[test]
+    fn test_series_override_wins() {
+        let global = SeedingPolicy {
+            ratio_limit: Some(1.0),
+            seed_days: None,
+        };
+        let tracked = vec![series(
+            "Frieren",
+            Some(SeedingPolicy {
+                ratio_limit: Some(3.0),
+                seed_days: None,
+            }),
+        )];
+        let torrents = vec![torrent("[SubsPlease] Frieren - 09 [1080p].mkv", 1.2, 0)];
+        assert!(torrents_to_remove(&torrents, &added(&torrents), &tracked, &global).is_empty());
+    }
+
+    #[test]
+    fn test_unfinished_torrents_are_kept() {
+        let global = SeedingPolicy {
+            ratio_limit: Some(0.5),
+            seed_days: None,
+        };
+        let mut t = torrent("[SubsPlease] Frieren - 09 [1080p].mkv", 0.9, 0);
+        t.progress = 0.5;
+        t.state = TorrentState::Downloading;
+        let torrents = [t];
+        assert!(torrents_to_remove(&torrents, &added(&torrents), &[], &global).is_empty());
+    }
+
+    #[test]
+    fn test_only_torrents_miru_added_are_removed() {
+        let global = SeedingPolicy {
+            ratio_limit: Some(1.0),
+            seed_days: None,
+        };
+        let mut unknown_ratio = torrent("[SubsPlease] Dandadan - 03 [1080p].mkv", 0.0, 0);
+        unknown_ratio.ratio = None;
+        let torrents = vec![
+            torrent("[SubsPlease] Frieren - 09 [1080p].mkv", 1.2, 0),
+            torrent("ubuntu-24.04-desktop-amd64.iso", 5.0, 0),
+            unknown_ratio,
+        ];
+        let mut ours = added(&torrents);
+        ours.remove(&torrents[1].hash);
+
+        let removed = torrents_to_remove(&torrents, &ours, &[], &global);
+        assert_eq!(removed, vec![torrents[0].hash.clone()]);
+    }
+}
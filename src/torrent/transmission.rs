@@ -1,8 +1,13 @@
+use std::collections::HashMap;
+
 use serde::Deserialize;
 use serde_json::json;
 use tracing::debug;
 
-use super::{TorrentClient, TorrentState, TorrentStatus};
+use super::{
+    FilePriority, PeerInfo, TorrentClient, TorrentDetails, TorrentFileStatus, TorrentState,
+    TorrentStatus, TorrentUpdate, TrackerInfo,
+};
 use crate::error::{Error, Result};
 
 #[derive(Clone)]
@@ -10,6 +15,9 @@ pub struct TransmissionClient {
     client: reqwest::Client,
     url: String,
     session_id: std::sync::Arc<tokio::sync::RwLock<Option<String>>>,
+    /// Transmission ids of the torrents we've seen, so `removed` ids can be
+    /// mapped back to hashes. `None` until the first full poll.
+    known_ids: std::sync::Arc<tokio::sync::Mutex<Option<HashMap<i64, String>>>>,
 }
 
 impl TransmissionClient {
@@ -33,6 +41,7 @@ impl TransmissionClient {
             client: builder.build().expect("Failed to create HTTP client"),
             url: format!("http://{}:{}/transmission/rpc", host, port),
             session_id: std::sync::Arc::new(tokio::sync::RwLock::new(None)),
+            known_ids: std::sync::Arc::new(tokio::sync::Mutex::new(None)),
         }
     }
 
@@ -85,6 +94,24 @@ impl TransmissionClient {
     }
 }
 
+const TORRENT_FIELDS: &[&str] = &[
+    "id",
+    "hashString",
+    "name",
+    "percentDone",
+    "rateDownload",
+    "rateUpload",
+    "totalSize",
+    "downloadedEver",
+    "uploadedEver",
+    "uploadRatio",
+    "secondsSeeding",
+    "status",
+    "peersSendingToUs",
+    "downloadDir",
+    "files",
+];
+
 #[derive(Deserialize)]
 struct TransmissionResponse {
     #[allow(dead_code)]
@@ -113,67 +140,53 @@ impl TorrentClient for TransmissionClient {
         Ok(hash)
     }
 
-    async fn list_torrents(&self) -> Result<Vec<TorrentStatus>> {
+    async fn add_torrent_file(&self, bytes: &[u8]) -> Result<String> {
         let args = json!({
-            "fields": [
-                "hashString", "name", "percentDone", "rateDownload", "rateUpload",
-                "totalSize", "downloadedEver", "status", "peersSendingToUs",
-                "downloadDir", "files"
-            ]
+            "metainfo": base64::engine::general_purpose::STANDARD.encode(bytes)
+        });
+
+        let result = self.rpc_call("torrent-add", args).await?;
+
+        let hash = result
+            .get("torrent-added")
+            .or_else(|| result.get("torrent-duplicate"))
+            .and_then(|t| t.get("hashString"))
+            .and_then(|h| h.as_str())
+            .unwrap_or("")
+            .to_string();
+
+        debug!(hash = %hash, "Added torrent file to Transmission");
+        Ok(hash)
+    }
+
+    async fn add_torrent_url(&self, url: &str) -> Result<String> {
+        let args = json!({
+            "filename": url
         });
 
+        let result = self.rpc_call("torrent-add", args).await?;
+
+        let hash = result
+            .get("torrent-added")
+            .or_else(|| result.get("torrent-duplicate"))
+            .and_then(|t| t.get("hashString"))
+            .and_then(|h| h.as_str())
+            .unwrap_or("")
+            .to_string();
+
+        debug!(hash = %hash, url, "Added torrent URL to Transmission");
+        Ok(hash)
+    }
+
+    async fn list_torrents(&self) -> Result<Vec<TorrentStatus>> {
+        let args = json!({ "fields": TORRENT_FIELDS });
+
         let result = self.rpc_call("torrent-get", args).await?;
 
         let torrents = result
             .get("torrents")
             .and_then(|t| t.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|t| {
-                        let name = t.get("name")?.as_str()?.to_string();
-                        let download_dir = t.get("downloadDir")?.as_str()?.to_string();
-
-                        let content_path =
-                            if let Some(files) = t.get("files").and_then(|f| f.as_array()) {
-                                if !files.is_empty() {
-                                    if let Some(first_file) =
-                                        files[0].get("name").and_then(|n| n.as_str())
-                                    {
-                                        let first_component =
-                                            first_file.split('/').next().unwrap_or(first_file);
-                                        if files.len() > 1 && first_file.contains('/') {
-                                            format!("{}/{}", download_dir, first_component)
-                                        } else if files.len() == 1 {
-                                            format!("{}/{}", download_dir, first_file)
-                                        } else {
-                                            format!("{}/{}", download_dir, name)
-                                        }
-                                    } else {
-                                        format!("{}/{}", download_dir, name)
-                                    }
-                                } else {
-                                    format!("{}/{}", download_dir, name)
-                                }
-                            } else {
-                                format!("{}/{}", download_dir, name)
-                            };
-
-                        Some(TorrentStatus {
-                            hash: t.get("hashString")?.as_str()?.to_string(),
-                            name,
-                            progress: t.get("percentDone")?.as_f64()?,
-                            download_rate: t.get("rateDownload")?.as_u64()?,
-                            upload_rate: t.get("rateUpload")?.as_u64()?,
-                            size: t.get("totalSize")?.as_u64()?,
-                            downloaded: t.get("downloadedEver")?.as_u64()?,
-                            seeders: t.get("peersSendingToUs")?.as_u64()? as u32,
-                            state: parse_transmission_status(t.get("status")?.as_i64()?),
-                            save_path: download_dir,
-                            content_path,
-                        })
-                    })
-                    .collect()
-            })
+            .map(|arr| arr.iter().filter_map(parse_transmission_torrent).collect())
             .unwrap_or_default();
 
         Ok(torrents)
@@ -203,6 +216,307 @@ impl TorrentClient for TransmissionClient {
         self.rpc_call("torrent-remove", args).await?;
         Ok(())
     }
+
+    async fn details(&self, hash: &str) -> Result<TorrentDetails> {
+        let args = json!({
+            "ids": [hash],
+            "fields": [
+                "hashString", "eta", "errorString", "files", "fileStats", "peers", "trackerStats"
+            ]
+        });
+
+        let result = self.rpc_call("torrent-get", args).await?;
+
+        let torrent = result
+            .get("torrents")
+            .and_then(|t| t.as_array())
+            .and_then(|arr| arr.first())
+            .ok_or_else(|| Error::TorrentClient(format!("Torrent not found: {}", hash)))?;
+
+        Ok(parse_transmission_details(hash, torrent))
+    }
+
+    async fn set_file_priority(
+        &self,
+        hash: &str,
+        file_indices: &[usize],
+        priority: FilePriority,
+    ) -> Result<()> {
+        let args = match priority {
+            FilePriority::Skip => json!({
+                "ids": [hash],
+                "files-unwanted": file_indices
+            }),
+            FilePriority::Low => json!({
+                "ids": [hash],
+                "files-wanted": file_indices,
+                "priority-low": file_indices
+            }),
+            FilePriority::Normal => json!({
+                "ids": [hash],
+                "files-wanted": file_indices,
+                "priority-normal": file_indices
+            }),
+            FilePriority::High => json!({
+                "ids": [hash],
+                "files-wanted": file_indices,
+                "priority-high": file_indices
+            }),
+        };
+        self.rpc_call("torrent-set", args).await?;
+        Ok(())
+    }
+
+    async fn poll_changes(&self) -> Result<TorrentUpdate> {
+        let mut known = self.known_ids.lock().await;
+        let full = known.is_none();
+
+        let args = if full {
+            json!({ "fields": TORRENT_FIELDS })
+        } else {
+            json!({ "ids": "recently-active", "fields": TORRENT_FIELDS })
+        };
+
+        match self.rpc_call("torrent-get", args).await {
+            Ok(result) => Ok(parse_transmission_changes(
+                known.get_or_insert_with(HashMap::new),
+                &result,
+                full,
+            )),
+            Err(e) => {
+                // Start over with a full list once the daemon is back
+                *known = None;
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Turn a `torrent-get` response into a delta, keeping `known` in sync
+fn parse_transmission_changes(
+    known: &mut HashMap<i64, String>,
+    result: &serde_json::Value,
+    full: bool,
+) -> TorrentUpdate {
+    if full {
+        known.clear();
+    }
+
+    let mut changed = Vec::new();
+    for t in result
+        .get("torrents")
+        .and_then(|t| t.as_array())
+        .into_iter()
+        .flatten()
+    {
+        if let Some(status) = parse_transmission_torrent(t) {
+            if let Some(id) = t.get("id").and_then(|v| v.as_i64()) {
+                known.insert(id, status.hash.clone());
+            }
+            changed.push(status);
+        }
+    }
+
+    let removed = result
+        .get("removed")
+        .and_then(|r| r.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|id| id.as_i64())
+        .filter_map(|id| known.remove(&id))
+        .collect();
+
+    TorrentUpdate {
+        full,
+        changed,
+        removed,
+    }
+}
+
+fn parse_transmission_torrent(t: &serde_json::Value) -> Option<TorrentStatus> {
+    let name = t.get("name")?.as_str()?.to_string();
+    let download_dir = t.get("downloadDir")?.as_str()?.to_string();
+
+    let content_path = if let Some(files) = t.get("files").and_then(|f| f.as_array()) {
+        if !files.is_empty() {
+            if let Some(first_file) = files[0].get("name").and_then(|n| n.as_str()) {
+                let first_component = first_file.split('/').next().unwrap_or(first_file);
+                if files.len() > 1 && first_file.contains('/') {
+                    format!("{}/{}", download_dir, first_component)
+                } else if files.len() == 1 {
+                    format!("{}/{}", download_dir, first_file)
+                } else {
+                    format!("{}/{}", download_dir, name)
+                }
+            } else {
+                format!("{}/{}", download_dir, name)
+            }
+        } else {
+            format!("{}/{}", download_dir, name)
+        }
+    } else {
+        format!("{}/{}", download_dir, name)
+    };
+
+    Some(TorrentStatus {
+        hash: t.get("hashString")?.as_str()?.to_string(),
+        name,
+        progress: t.get("percentDone")?.as_f64()?,
+        download_rate: t.get("rateDownload")?.as_u64()?,
+        upload_rate: t.get("rateUpload")?.as_u64()?,
+        size: t.get("totalSize")?.as_u64()?,
+        downloaded: t.get("downloadedEver")?.as_u64()?,
+        uploaded: t.get("uploadedEver").and_then(|v| v.as_u64()).unwrap_or(0),
+        // Transmission reports -1/-2 when the ratio is unknown or infinite
+        ratio: t
+            .get("uploadRatio")
+            .and_then(|v| v.as_f64())
+            .filter(|r| *r >= 0.0),
+        seeding_time: t
+            .get("secondsSeeding")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0),
+        seeders: t.get("peersSendingToUs")?.as_u64()? as u32,
+        state: parse_transmission_status(t.get("status")?.as_i64()?),
+        save_path: download_dir,
+        content_path,
+    })
+}
+
+fn parse_transmission_details(hash: &str, t: &serde_json::Value) -> TorrentDetails {
+    let empty = Vec::new();
+    let files = t.get("files").and_then(|f| f.as_array()).unwrap_or(&empty);
+    let stats = t
+        .get("fileStats")
+        .and_then(|f| f.as_array())
+        .unwrap_or(&empty);
+
+    let files = files
+        .iter()
+        .enumerate()
+        .map(|(index, f)| {
+            let size = f.get("length").and_then(|v| v.as_u64()).unwrap_or(0);
+            let stat = stats.get(index);
+            let completed = stat
+                .and_then(|s| s.get("bytesCompleted"))
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0);
+            let wanted = stat
+                .and_then(|s| s.get("wanted"))
+                .and_then(|v| v.as_bool())
+                .unwrap_or(true);
+            let priority = if !wanted {
+                FilePriority::Skip
+            } else {
+                match stat
+                    .and_then(|s| s.get("priority"))
+                    .and_then(|v| v.as_i64())
+                {
+                    Some(-1) => FilePriority::Low,
+                    Some(1) => FilePriority::High,
+                    _ => FilePriority::Normal,
+                }
+            };
+
+            TorrentFileStatus {
+                index,
+                name: f
+                    .get("name")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string(),
+                size,
+                progress: if size > 0 {
+                    completed as f64 / size as f64
+                } else {
+                    1.0
+                },
+                priority,
+            }
+        })
+        .collect();
+
+    let peers = t
+        .get("peers")
+        .and_then(|p| p.as_array())
+        .unwrap_or(&empty)
+        .iter()
+        .map(|p| PeerInfo {
+            address: p
+                .get("address")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string(),
+            client: p
+                .get("clientName")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string(),
+            progress: p.get("progress").and_then(|v| v.as_f64()).unwrap_or(0.0),
+            download_rate: p.get("rateToClient").and_then(|v| v.as_u64()).unwrap_or(0),
+            upload_rate: p.get("rateToPeer").and_then(|v| v.as_u64()).unwrap_or(0),
+        })
+        .collect();
+
+    let trackers = t
+        .get("trackerStats")
+        .and_then(|p| p.as_array())
+        .unwrap_or(&empty)
+        .iter()
+        .map(|tr| {
+            let status = match tr.get("announceState").and_then(|v| v.as_i64()) {
+                Some(1) => "Waiting",
+                Some(2) => "Queued",
+                Some(3) => "Announcing",
+                _ => "Idle",
+            };
+            // -1 means the tracker never reported a count
+            let count = |key: &str| {
+                tr.get(key)
+                    .and_then(|v| v.as_i64())
+                    .filter(|n| *n >= 0)
+                    .map(|n| n as u32)
+            };
+
+            TrackerInfo {
+                url: tr
+                    .get("announce")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string(),
+                status: status.to_string(),
+                seeders: count("seederCount"),
+                leechers: count("leecherCount"),
+                message: tr
+                    .get("lastAnnounceResult")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("")
+                    .to_string(),
+            }
+        })
+        .collect();
+
+    let error = t
+        .get("errorString")
+        .and_then(|v| v.as_str())
+        .filter(|e| !e.is_empty())
+        .map(|e| e.to_string());
+
+    // Negative ETAs mean "not available" or "unknown"
+    let eta = t
+        .get("eta")
+        .and_then(|v| v.as_i64())
+        .filter(|e| *e >= 0)
+        .map(|e| e as u64);
+
+    TorrentDetails {
+        hash: hash.to_string(),
+        eta,
+        files,
+        peers,
+        trackers,
+        error,
+    }
 }
 
 fn parse_transmission_status(status: i64) -> TorrentState {
@@ -218,3 +532,74 @@ fn parse_transmission_status(status: i64) -> TorrentState {
 }
 
 use base64::Engine;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_details() {
+        let t = serde_json::json!({
+            "hashString": "abc",
+            "eta": -1,
+            "errorString": "",
+            "files": [
+                { "name": "Show/Show - 01.mkv", "length": 1000 },
+                { "name": "Show/Show - 02.mkv", "length": 1000 },
+                { "name": "Show/Show - 03.mkv", "length": 1000 }
+            ],
+            "fileStats": [
+                { "bytesCompleted": 500, "wanted": true, "priority": 0 },
+                { "bytesCompleted": 0, "wanted": false, "priority": 0 },
+                { "bytesCompleted": 1000, "wanted": true, "priority": 1 }
+            ],
+            "peers": [],
+            "trackerStats": [
+                { "announce": "http://tracker/announce", "announceState": 1,
+                  "seederCount": 12, "leecherCount": -1, "lastAnnounceResult": "Success" }
+            ]
+        });
+
+        let details = parse_transmission_details("abc", &t);
+        assert_eq!(details.eta, None);
+        assert_eq!(details.error, None);
+        assert_eq!(details.files.len(), 3);
+        assert_eq!(details.files[0].priority, FilePriority::Normal);
+        assert!((details.files[0].progress - 0.5).abs() < f64::EPSILON);
+        assert_eq!(details.files[1].priority, FilePriority::Skip);
+        assert_eq!(details.files[2].priority, FilePriority::High);
+        assert_eq!(details.trackers[0].seeders, Some(12));
+        assert_eq!(details.trackers[0].leechers, None);
+        assert_eq!(details.trackers[0].status, "Waiting");
+    }
+
+    fn torrent_json(id: i64, hash: &str, progress: f64) -> serde_json::Value {
+        serde_json::json!({
+            "id": id, "hashString": hash, "name": hash, "percentDone": progress,
+            "rateDownload": 0, "rateUpload": 0, "totalSize": 100, "downloadedEver": 0,
+            "status": 4, "peersSendingToUs": 0, "downloadDir": "/dl", "files": []
+        })
+    }
+
+    #[test]
+    fn test_recently_active_changes() {
+        let mut known = HashMap::new();
+
+        let first = serde_json::json!({
+            "torrents": [torrent_json(1, "aaa", 0.1), torrent_json(2, "bbb", 0.2)]
+        });
+        let update = parse_transmission_changes(&mut known, &first, true);
+        assert!(update.full);
+        assert_eq!(update.changed.len(), 2);
+
+        let delta = serde_json::json!({
+            "torrents": [torrent_json(2, "bbb", 0.6)],
+            "removed": [1]
+        });
+        let update = parse_transmission_changes(&mut known, &delta, false);
+        assert!(!update.full);
+        assert_eq!(update.changed[0].progress, 0.6);
+        assert_eq!(update.removed, vec!["aaa".to_string()]);
+        assert!(!known.contains_key(&1));
+    }
+}
@@ -1,13 +1,14 @@
 use humansize::{BINARY, format_size};
 use ratatui::{
     Frame,
-    layout::Rect,
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{List, ListItem, ListState},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
 };
 
-use crate::torrent::{TorrentState, TorrentStatus};
+use crate::torrent::preview::PreviewSection;
+use crate::torrent::{FilePriority, TorrentDetails, TorrentState, TorrentStatus};
 
 use super::widgets::titled_block;
 
@@ -29,15 +30,7 @@ pub fn render_downloads_view(
     let items: Vec<ListItem> = torrents
         .iter()
         .map(|t| {
-            let state_color = match t.state {
-                TorrentState::Downloading => Color::Green,
-                TorrentState::Seeding => Color::Cyan,
-                TorrentState::Paused => Color::Yellow,
-                TorrentState::Queued => Color::Blue,
-                TorrentState::Checking => Color::Magenta,
-                TorrentState::Error => Color::Red,
-                TorrentState::Unknown => Color::DarkGray,
-            };
+            let state_color = state_color(t.state);
 
             let progress_pct = (t.progress * 100.0) as u8;
 
@@ -64,6 +57,12 @@ pub fn render_downloads_view(
                 Span::raw(" "),
                 Span::styled(format!("{:>10}", speed), Style::default().fg(Color::Cyan)),
                 Span::raw(" │ "),
+                Span::styled(
+                    t.ratio
+                        .map_or("R     -".to_string(), |r| format!("R {:>5.2}", r)),
+                    Style::default().fg(Color::LightMagenta),
+                ),
+                Span::raw(" │ "),
                 Span::raw(truncate_name(&t.name, 50)),
             ]);
 
@@ -84,6 +83,279 @@ pub fn render_downloads_view(
     frame.render_stateful_widget(list, area, list_state);
 }
 
+/// Downloads list on top, details of the selected torrent below
+pub fn render_torrent_details_view(
+    frame: &mut Frame,
+    area: Rect,
+    torrents: &[TorrentStatus],
+    list_state: &mut ListState,
+    details: &PreviewSection<TorrentDetails>,
+    file_state: &mut ListState,
+    accent: Color,
+) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(area);
+
+    render_downloads_view(frame, chunks[0], torrents, list_state, accent);
+
+    let Some(torrent) = list_state.selected().and_then(|i| torrents.get(i)) else {
+        return;
+    };
+
+    let block = titled_block("Details", accent);
+    let inner = block.inner(chunks[1]);
+    frame.render_widget(block, chunks[1]);
+
+    let details = match details {
+        PreviewSection::Loading => {
+            frame.render_widget(
+                Paragraph::new("\u{25CB} Loading torrent details...")
+                    .style(Style::default().fg(Color::DarkGray)),
+                inner,
+            );
+            return;
+        }
+        PreviewSection::Error(msg) => {
+            frame.render_widget(
+                Paragraph::new(msg.as_str()).style(Style::default().fg(Color::Red)),
+                inner,
+            );
+            return;
+        }
+        PreviewSection::Loaded(details) => details,
+    };
+
+    let sections = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(3),
+            Constraint::Length(6),
+        ])
+        .split(inner);
+
+    render_details_summary(frame, sections[0], torrent, details);
+    render_details_files(frame, sections[1], details, file_state, accent);
+
+    let bottom = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(sections[2]);
+
+    render_details_trackers(frame, bottom[0], details);
+    render_details_peers(frame, bottom[1], details);
+}
+
+fn render_details_summary(
+    frame: &mut Frame,
+    area: Rect,
+    torrent: &TorrentStatus,
+    details: &TorrentDetails,
+) {
+    let label = Style::default().fg(Color::DarkGray);
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled("State: ", label),
+            Span::styled(
+                torrent.state.as_str(),
+                Style::default().fg(state_color(torrent.state)),
+            ),
+            Span::styled("  Progress: ", label),
+            Span::raw(format!("{:.1}%", torrent.progress * 100.0)),
+            Span::styled("  ETA: ", label),
+            Span::raw(
+                details
+                    .eta
+                    .map(format_eta)
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("Ratio: ", label),
+            Span::raw(
+                torrent
+                    .ratio
+                    .map_or("-".to_string(), |r| format!("{:.2}", r)),
+            ),
+            Span::styled("  Up: ", label),
+            Span::raw(format!(
+                "{} ({}/s)",
+                format_size(torrent.uploaded, BINARY),
+                format_size(torrent.upload_rate, BINARY)
+            )),
+            Span::styled("  Peers: ", label),
+            Span::raw(details.peers.len().to_string()),
+        ]),
+    ];
+
+    if let Some(err) = &details.error {
+        lines.push(Line::from(vec![
+            Span::styled("Error: ", label),
+            Span::styled(err.as_str(), Style::default().fg(Color::Red)),
+        ]));
+    }
+
+    frame.render_widget(Paragraph::new(lines), area);
+}
+
+fn render_details_files(
+    frame: &mut Frame,
+    area: Rect,
+    details: &TorrentDetails,
+    file_state: &mut ListState,
+    accent: Color,
+) {
+    let name_width = area.width.saturating_sub(24) as usize;
+
+    let items: Vec<ListItem> = details
+        .files
+        .iter()
+        .map(|f| {
+            let skipped = f.priority == FilePriority::Skip;
+            let (check, color) = if skipped {
+                ("[ ]", Color::DarkGray)
+            } else {
+                ("[x]", Color::Green)
+            };
+            let name = f.name.rsplit('/').next().unwrap_or(&f.name);
+
+            let mut spans = vec![
+                Span::styled(check, Style::default().fg(color)),
+                Span::styled(
+                    format!(" {:>3}% ", (f.progress * 100.0) as u8),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::styled(
+                    format!("{:>9} ", format_size(f.size, BINARY)),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(
+                    truncate_name(name, name_width.max(10)),
+                    Style::default().fg(if skipped {
+                        Color::DarkGray
+                    } else {
+                        Color::White
+                    }),
+                ),
+            ];
+            if f.priority == FilePriority::High {
+                spans.push(Span::styled(" ↑", Style::default().fg(Color::Yellow)));
+            }
+
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::TOP)
+                .title(format!(" Files ({}) ", details.files.len())),
+        )
+        .highlight_style(Style::default().bg(accent).fg(Color::Black))
+        .highlight_symbol("▶ ");
+
+    frame.render_stateful_widget(list, area, file_state);
+}
+
+fn render_details_trackers(frame: &mut Frame, area: Rect, details: &TorrentDetails) {
+    let lines: Vec<Line> = details
+        .trackers
+        .iter()
+        .map(|t| {
+            let host = t
+                .url
+                .split("://")
+                .nth(1)
+                .and_then(|rest| rest.split(['/', ':']).next())
+                .unwrap_or(&t.url);
+            let counts = match (t.seeders, t.leechers) {
+                (Some(s), Some(l)) => format!(" {}S/{}L", s, l),
+                _ => String::new(),
+            };
+            let mut spans = vec![
+                Span::raw(host.to_string()),
+                Span::styled(
+                    format!(" {}{}", t.status, counts),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ];
+            if !t.message.is_empty() {
+                spans.push(Span::styled(
+                    format!(" {}", t.message),
+                    Style::default().fg(Color::Yellow),
+                ));
+            }
+            Line::from(spans)
+        })
+        .collect();
+
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::TOP).title(" Trackers ")),
+        area,
+    );
+}
+
+fn render_details_peers(frame: &mut Frame, area: Rect, details: &TorrentDetails) {
+    let lines: Vec<Line> = details
+        .peers
+        .iter()
+        .map(|p| {
+            Line::from(vec![
+                Span::raw(format!("{:<22}", p.address)),
+                Span::styled(
+                    format!(" {:>3}%", (p.progress * 100.0) as u8),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::styled(
+                    format!(" ↓{}/s", format_size(p.download_rate, BINARY)),
+                    Style::default().fg(Color::Green),
+                ),
+                Span::styled(
+                    format!(" ↑{}/s", format_size(p.upload_rate, BINARY)),
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::styled(
+                    format!(" {}", p.client),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ])
+        })
+        .collect();
+
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::TOP).title(" Peers ")),
+        area,
+    );
+}
+
+fn state_color(state: TorrentState) -> Color {
+    match state {
+        TorrentState::Downloading => Color::Green,
+        TorrentState::Seeding => Color::Cyan,
+        TorrentState::Paused => Color::Yellow,
+        TorrentState::Queued => Color::Blue,
+        TorrentState::Checking => Color::Magenta,
+        TorrentState::Error => Color::Red,
+        TorrentState::Unknown => Color::DarkGray,
+    }
+}
+
+fn format_eta(secs: u64) -> String {
+    let hours = secs / 3600;
+    let mins = (secs % 3600) / 60;
+    if hours >= 24 {
+        format!("{}d {:02}h", hours / 24, hours % 24)
+    } else if hours > 0 {
+        format!("{}h {:02}m", hours, mins)
+    } else {
+        format!("{}m {:02}s", mins, secs % 60)
+    }
+}
+
 fn truncate_name(name: &str, max_len: usize) -> String {
     if name.len() <= max_len {
         name.to_string()
@@ -1,16 +1,21 @@
 use ratatui::{
     Frame,
-    layout::Rect,
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{List, ListItem, ListState},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
 };
 
 use crate::library::{Show, models::Episode};
+use crate::metadata::EpisodeMetadata;
 
 use super::widgets::{format_episode_num, titled_block};
 
-fn episode_list_item(ep: &Episode, indent: &str) -> ListItem<'static> {
+fn episode_list_item(
+    ep: &Episode,
+    info: Option<&EpisodeMetadata>,
+    indent: &str,
+) -> ListItem<'static> {
     let status_icon = if ep.watched { "✓" } else { "○" };
     let status_color = if ep.watched {
         Color::Green
@@ -27,9 +32,33 @@ fn episode_list_item(ep: &Episode, indent: &str) -> ListItem<'static> {
             Style::default().add_modifier(Modifier::BOLD),
         ),
         Span::raw(" - "),
-        Span::raw(ep.filename.clone()),
     ];
 
+    match info.and_then(|i| i.title.clone()) {
+        Some(title) => spans.push(Span::raw(title)),
+        None => spans.push(Span::raw(ep.filename.clone())),
+    }
+    if let Some(info) = info {
+        if let Some(aired) = &info.aired {
+            spans.push(Span::styled(
+                format!("  {}", aired),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        if info.filler {
+            spans.push(Span::styled(
+                " [filler]",
+                Style::default().fg(Color::Magenta),
+            ));
+        }
+        if info.recap {
+            spans.push(Span::styled(
+                " [recap]",
+                Style::default().fg(Color::Magenta),
+            ));
+        }
+    }
+
     if ep.last_position > 0 && !ep.watched {
         let mins = ep.last_position / 60;
         let secs = ep.last_position % 60;
@@ -77,7 +106,8 @@ pub fn render_episodes_view(
             ])));
 
             for ep in &season.episodes {
-                items.push(episode_list_item(ep, "  "));
+                let info = show.episode_info(Some(season.number), ep.number);
+                items.push(episode_list_item(ep, info, "  "));
             }
         }
 
@@ -96,7 +126,7 @@ pub fn render_episodes_view(
             ])));
 
             for ep in &show.specials {
-                items.push(episode_list_item(ep, "  "));
+                items.push(episode_list_item(ep, None, "  "));
             }
         }
 
@@ -115,12 +145,20 @@ pub fn render_episodes_view(
             ])));
 
             for ep in &show.episodes {
-                items.push(episode_list_item(ep, "  "));
+                items.push(episode_list_item(
+                    ep,
+                    show.episode_info(None, ep.number),
+                    "  ",
+                ));
             }
         }
     } else {
         for ep in &show.episodes {
-            items.push(episode_list_item(ep, ""));
+            items.push(episode_list_item(
+                ep,
+                show.episode_info(None, ep.number),
+                "",
+            ));
         }
     }
 
@@ -140,5 +178,28 @@ pub fn render_episodes_view(
         )
         .highlight_symbol("▶ ");
 
-    frame.render_stateful_widget(list, area, list_state);
+    // Room for the highlighted episode's synopsis, when the list has any.
+    // Seasonal lists mix in headers, so rows don't map onto episodes.
+    let has_synopses = show.episode_info.iter().any(|i| i.synopsis.is_some());
+    if !has_synopses || show.is_seasonal() {
+        frame.render_stateful_widget(list, area, list_state);
+        return;
+    }
+    let synopsis = list_state
+        .selected()
+        .and_then(|i| show.episodes.get(i))
+        .and_then(|ep| show.episode_info(None, ep.number))
+        .and_then(|info| info.synopsis.as_deref());
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(6)])
+        .split(area);
+    frame.render_stateful_widget(list, chunks[0], list_state);
+    frame.render_widget(
+        Paragraph::new(synopsis.unwrap_or(""))
+            .wrap(Wrap { trim: true })
+            .block(Block::default().borders(Borders::TOP).title(" Synopsis ")),
+        chunks[1],
+    );
 }
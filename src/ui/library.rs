@@ -181,7 +181,7 @@ fn render_details(
                 )));
                 info_text.push(Line::from(""));
                 info_text.push(Line::from(Span::styled(
-                    "Press 'm' to fetch metadata",
+                    "Press 'm' to match metadata",
                     Style::default().fg(Color::DarkGray),
                 )));
             }
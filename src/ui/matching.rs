@@ -0,0 +1,97 @@
+use crate::image_cache::ImageCache;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState, Paragraph},
+};
+use ratatui_image::picker::Picker;
+use ratatui_image::{Image, Resize};
+
+use crate::metadata::matcher::Candidate;
+
+use super::widgets::titled_block;
+
+/// The candidate list's selection, and where the selected one's cover
+/// comes from
+pub struct CandidateSelection<'a> {
+    pub list_state: &'a mut ListState,
+    pub image_cache: &'a ImageCache,
+    pub picker: &'a mut Picker,
+}
+
+/// Metadata candidates for a show, best first, with the selected one's cover
+pub fn render_match_candidates(
+    frame: &mut Frame,
+    area: Rect,
+    title: &str,
+    candidates: &[Candidate],
+    selection: CandidateSelection,
+    accent: Color,
+) {
+    let CandidateSelection {
+        list_state,
+        image_cache,
+        picker,
+    } = selection;
+
+    let block = titled_block(title, accent);
+    let inner_area = block.inner(area);
+    frame.render_widget(block, area);
+
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(30)])
+        .split(inner_area);
+
+    if candidates.is_empty() {
+        frame.render_widget(
+            Paragraph::new("No candidates").style(Style::default().fg(Color::DarkGray)),
+            chunks[0],
+        );
+        return;
+    }
+
+    let items: Vec<ListItem> = candidates
+        .iter()
+        .map(|c| {
+            let meta = &c.metadata;
+            let year = meta.year.map(|y| y.to_string()).unwrap_or_default();
+            let episodes = meta
+                .episodes
+                .map(|n| format!("{} eps", n))
+                .unwrap_or_else(|| "? eps".to_string());
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("{:>3.0}% ", c.score * 100.0),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::raw(meta.title.clone()),
+                Span::styled(
+                    format!(
+                        "  {} {} {}:{}",
+                        year,
+                        episodes,
+                        meta.provider.short_name(),
+                        meta.id
+                    ),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .highlight_style(Style::default().fg(accent).add_modifier(Modifier::BOLD))
+        .highlight_symbol("> ");
+    frame.render_stateful_widget(list, chunks[0], list_state);
+
+    let selected = list_state.selected().and_then(|i| candidates.get(i));
+    if let Some(url) = selected.and_then(|c| c.metadata.cover_url.as_ref())
+        && let Some(img) = image_cache.get(url)
+        && let Ok(protocol) = picker.new_protocol(img, chunks[1], Resize::Fit(None))
+    {
+        frame.render_widget(Image::new(&protocol), chunks[1]);
+    }
+}
@@ -1,12 +1,14 @@
 pub mod downloads;
 pub mod episodes;
 pub mod library;
+pub mod matching;
 pub mod search;
 pub mod widgets;
 
-pub use downloads::render_downloads_view;
+pub use downloads::{render_downloads_view, render_torrent_details_view};
 pub use episodes::render_episodes_view;
 pub use library::render_library_view;
+pub use matching::{CandidateSelection, render_match_candidates};
 pub use search::render_glossary_popup;
 pub use search::render_preview_popup;
 pub use search::render_search_view;
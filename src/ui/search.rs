@@ -8,7 +8,7 @@ use ratatui::{
 };
 use regex::Regex;
 
-use crate::nyaa::{NyaaCategory, NyaaFilter, NyaaResult, NyaaSort};
+use crate::nyaa::{NyaaCategory, NyaaFilter, NyaaResult, NyaaSort, ScoreBreakdown, ScoringProfile};
 use crate::torrent::preview::{FileType, PreviewSection, PreviewState, TorrentFileEntry};
 
 use super::widgets::titled_block;
@@ -74,9 +74,12 @@ pub fn render_search_view(
     results: &[NyaaResult],
     list_state: &mut ListState,
     is_loading: bool,
+    total: Option<usize>,
+    more: bool,
     category: NyaaCategory,
     filter: NyaaFilter,
     sort: NyaaSort,
+    profile: &ScoringProfile,
     accent: Color,
 ) {
     let chunks = Layout::default()
@@ -85,6 +88,7 @@ pub fn render_search_view(
             Constraint::Length(3),
             Constraint::Length(1),
             Constraint::Min(3),
+            Constraint::Length(1),
         ])
         .split(area);
 
@@ -92,7 +96,44 @@ pub fn render_search_view(
 
     render_filter_bar(frame, chunks[1], category, filter, sort);
 
-    render_search_results(frame, chunks[2], results, list_state, accent);
+    let scores: Vec<ScoreBreakdown> = results.iter().map(|r| profile.score(r)).collect();
+    let mut title = match total {
+        // Indexers count what they match before filtering and merging
+        Some(total) => format!(
+            "Results ({} of ~{})",
+            results.len(),
+            total.max(results.len())
+        ),
+        None if more => format!("Results ({}+)", results.len()),
+        None => format!("Results ({})", results.len()),
+    };
+    if is_loading && !results.is_empty() {
+        title.push_str(" - loading more...");
+    }
+    render_search_results(
+        frame, chunks[2], &title, results, &scores, list_state, accent,
+    );
+
+    let selected = list_state.selected().and_then(|i| scores.get(i));
+    render_score_breakdown(frame, chunks[3], selected);
+}
+
+/// Why the selected result scored what it did
+fn render_score_breakdown(frame: &mut Frame, area: Rect, breakdown: Option<&ScoreBreakdown>) {
+    let Some(breakdown) = breakdown else {
+        return;
+    };
+    let color = if breakdown.rejected.is_some() {
+        Color::LightRed
+    } else {
+        Color::DarkGray
+    };
+    let line = Line::from(vec![
+        Span::raw(" "),
+        Span::styled("Score: ", Style::default().add_modifier(Modifier::BOLD)),
+        Span::styled(breakdown.summary(), Style::default().fg(color)),
+    ]);
+    frame.render_widget(Paragraph::new(line), area);
 }
 
 fn render_search_input(
@@ -161,7 +202,9 @@ fn render_filter_bar(
 fn render_search_results(
     frame: &mut Frame,
     area: Rect,
+    title: &str,
     results: &[NyaaResult],
+    scores: &[ScoreBreakdown],
     list_state: &mut ListState,
     accent: Color,
 ) {
@@ -173,11 +216,12 @@ fn render_search_results(
         return;
     }
 
-    let title_width = area.width.saturating_sub(36) as usize;
+    let title_width = area.width.saturating_sub(42) as usize;
 
     let items: Vec<ListItem> = results
         .iter()
-        .map(|r| {
+        .zip(scores)
+        .map(|(r, score)| {
             let seeder_color = if r.seeders >= 50 {
                 Color::Green
             } else if r.seeders >= 10 {
@@ -188,7 +232,7 @@ fn render_search_results(
                 Color::DarkGray
             };
 
-            let seeder_style = if r.is_trusted {
+            let seeder_style = if r.is_trusted == Some(true) {
                 Style::default()
                     .fg(Color::LightGreen)
                     .add_modifier(Modifier::BOLD)
@@ -198,7 +242,7 @@ fn render_search_results(
                     .add_modifier(Modifier::BOLD)
             };
 
-            let trust_indicator = if r.is_trusted {
+            let trust_indicator = if r.is_trusted == Some(true) {
                 Span::styled("★ ", Style::default().fg(Color::LightGreen))
             } else {
                 Span::raw("  ")
@@ -210,14 +254,27 @@ fn render_search_results(
                 Span::raw("        ")
             };
 
-            let title_style = if r.is_trusted || r.is_batch {
+            let title_style = if r.is_trusted == Some(true) || r.is_batch {
                 Style::default().fg(Color::White)
+            } else if r.is_remake {
+                Style::default().fg(Color::LightRed)
             } else {
                 Style::default().fg(Color::Gray)
             };
 
+            let score_span = if score.rejected.is_some() {
+                Span::styled("   ✗", Style::default().fg(Color::LightRed))
+            } else {
+                Span::styled(
+                    format!("{:>+4}", score.total),
+                    Style::default().fg(Color::LightBlue),
+                )
+            };
+
             let line = Line::from(vec![
                 trust_indicator,
+                score_span,
+                Span::raw(" │ "),
                 Span::styled(format!("{:>4}", r.seeders), seeder_style),
                 Span::raw(" │ "),
                 Span::styled(format!("{:>9}", r.size), Style::default().fg(Color::Cyan)),
@@ -231,7 +288,7 @@ fn render_search_results(
         .collect();
 
     let list = List::new(items)
-        .block(titled_block("Results", accent))
+        .block(titled_block(title, accent))
         .highlight_style(
             Style::default()
                 .bg(accent)
@@ -250,8 +307,8 @@ pub fn render_preview_popup(frame: &mut Frame, preview: &mut PreviewState, accen
     let (items, _section_count) = build_file_list_items(preview, accent);
     let file_lines = items.len().max(1) as u16;
 
-    // Dynamic height: borders(2) + file_lines + mal_footer(2) + summary(1) + hints(1)
-    let content_height = file_lines + 4;
+    // Dynamic height: borders(2) + file_lines + mal_footer(2) + summary(1) + binding(1) + hints(1)
+    let content_height = file_lines + 5;
     let popup_height = (content_height + 2).min(area.height.saturating_sub(4)); // +2 for border
     let popup_width = 72u16.min(area.width.saturating_sub(6));
 
@@ -282,6 +339,7 @@ pub fn render_preview_popup(frame: &mut Frame, preview: &mut PreviewState, accen
         Constraint::Min(3),    // file list
         Constraint::Length(2), // MAL footer
         Constraint::Length(1), // summary line
+        Constraint::Length(1), // binding
         Constraint::Length(1), // action hints
     ])
     .split(inner);
@@ -295,14 +353,25 @@ pub fn render_preview_popup(frame: &mut Frame, preview: &mut PreviewState, accen
     // -- Summary line --
     render_summary_line(frame, chunks[2], preview);
 
+    // -- Binding --
+    let binding = match preview.binding() {
+        Some((_, label)) => Span::styled(label.clone(), Style::default().fg(accent)),
+        None => Span::styled("none", Style::default().fg(Color::DarkGray)),
+    };
+    let binding_line = Line::from(vec![
+        Span::styled("Bind to: ", Style::default().fg(Color::DarkGray)),
+        binding,
+    ]);
+    frame.render_widget(Paragraph::new(binding_line), chunks[3]);
+
     // -- Action hints --
     let hints = if preview.is_magnet_only {
-        "Enter: Download anyway  |  Esc: Close"
+        "Enter: Download anyway  |  b: Bind  |  Esc: Close"
     } else {
-        "Enter: Download  |  j/k: Scroll  |  Esc: Close"
+        "Enter: Download  |  j/k: Scroll  |  b: Bind  |  Esc: Close"
     };
     let hints_paragraph = Paragraph::new(hints).style(Style::default().fg(Color::DarkGray));
-    frame.render_widget(hints_paragraph, chunks[3]);
+    frame.render_widget(hints_paragraph, chunks[4]);
 }
 
 fn build_file_list_items<'a>(preview: &PreviewState, accent: Color) -> (Vec<ListItem<'a>>, usize) {